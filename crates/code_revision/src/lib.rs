@@ -0,0 +1,5 @@
+//! Git revision of the build, kept in its own small crate because its build script reruns on
+//! every commit
+
+/// Git revision this crate was built from
+pub const CODE_REVISION: &str = env!("CODE_REVISION");
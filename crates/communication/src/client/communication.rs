@@ -1,5 +1,6 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, path::Path as FilePath};
 
+use color_eyre::Result;
 use serde_json::Value;
 use tokio::{
     spawn,
@@ -14,9 +15,11 @@ use crate::{
     client::{
         connector::{self, connector, ConnectionStatus},
         parameter_subscription_manager::{self, parameter_subscription_manager},
+        recording::Recorder,
+        replayer::{replay, Replay},
         SubscriberMessage,
     },
-    messages::{Fields, Format, Path},
+    messages::{Fields, Format, Path, SubscriptionPolicy},
 };
 
 use super::{
@@ -101,6 +104,7 @@ impl Communication {
         &self,
         output: CyclerOutput,
         format: Format,
+        policy: SubscriptionPolicy,
     ) -> (Uuid, Receiver<SubscriberMessage>) {
         let (subscriber_sender, subscriber_receiver) = mpsc::channel(10);
         let (response_sender, response_receiver) = oneshot::channel();
@@ -108,6 +112,7 @@ impl Communication {
             .send(output_subscription_manager::Message::Subscribe {
                 output,
                 format,
+                policy,
                 subscriber: subscriber_sender,
                 response_sender,
             })
@@ -124,6 +129,29 @@ impl Communication {
             .unwrap();
     }
 
+    /// Records all updates of subscribed outputs until `stop_recording()` is called
+    pub async fn start_recording(&self, path: impl AsRef<FilePath>) -> Result<()> {
+        let recorder = Recorder::create(path)?;
+        self.output_subscription_manager
+            .send(output_subscription_manager::Message::StartRecording { recorder })
+            .await
+            .unwrap();
+        Ok(())
+    }
+
+    pub async fn stop_recording(&self) {
+        self.output_subscription_manager
+            .send(output_subscription_manager::Message::StopRecording)
+            .await
+            .unwrap();
+    }
+
+    /// Delivers the updates of a recording to output subscribers until the returned replay is
+    /// dropped, the connection should be disabled meanwhile
+    pub fn replay(&self, path: impl AsRef<FilePath>) -> Result<Replay> {
+        replay(path, self.output_subscription_manager.clone())
+    }
+
     pub async fn subscribe_parameter(&self, path: String) -> (Uuid, Receiver<SubscriberMessage>) {
         let (subscriber_sender, subscriber_receiver) = mpsc::channel(10);
         let (response_sender, response_receiver) = oneshot::channel();
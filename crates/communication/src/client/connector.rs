@@ -1,22 +1,35 @@
 use std::time::Duration;
 
-use color_eyre::{eyre::WrapErr, Result};
-use futures_util::StreamExt;
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result,
+};
+use futures_util::{SinkExt, StreamExt};
 use log::{error, info, warn};
+use serde_json::{from_str, to_string};
+use thiserror::Error;
 use tokio::{
     net::TcpStream,
     spawn,
     sync::mpsc::{channel, Receiver, Sender},
     task::JoinHandle,
-    time::sleep,
+    time::{sleep, timeout},
 };
-use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{connect_async, tungstenite, MaybeTlsStream, WebSocketStream};
 
-use crate::client::{
-    output_subscription_manager, parameter_subscription_manager,
-    receiver::receiver as receiver_task, requester::requester, responder,
+use crate::{
+    client::{
+        output_subscription_manager, parameter_subscription_manager,
+        receiver::receiver as receiver_task, requester::requester, responder,
+    },
+    messages::{
+        HelloRequest, HelloResponse, Request, ServerInformation, TextualResponse, CODE_REVISION,
+        PROTOCOL_VERSION,
+    },
 };
 
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Debug)]
 pub enum Message {
     SubscribeToUpdates(Sender<ConnectionStatus>),
@@ -24,7 +37,7 @@ pub enum Message {
     SetAddress(String),
     ReconnectTimerElapsed,
     Connected(Box<WebSocketStream<MaybeTlsStream<TcpStream>>>),
-    ConnectionFailed { info: String },
+    ConnectionFailed { info: String, is_incompatible: bool },
 }
 
 #[derive(Debug)]
@@ -54,8 +67,18 @@ pub enum ConnectionStatus {
     Connected {
         address: String,
     },
+    /// The server speaks a different protocol version, connecting is retried nevertheless
+    Incompatible {
+        address: String,
+        reason: String,
+    },
 }
 
+/// The server does not speak our protocol version
+#[derive(Debug, Error)]
+#[error("{0}")]
+struct Incompatibility(String);
+
 pub async fn connector(
     mut receiver: Receiver<Message>,
     sender: Sender<Message>,
@@ -77,8 +100,19 @@ pub async fn connector(
     };
 
     let mut subscribers = Vec::new();
+    let mut incompatibility = None;
 
     while let Some(message) = receiver.recv().await {
+        match &message {
+            Message::SetAddress(_) | Message::SetConnect(false) | Message::Connected(_) => {
+                incompatibility = None
+            }
+            Message::ConnectionFailed {
+                info,
+                is_incompatible,
+            } => incompatibility = is_incompatible.then(|| info.clone()),
+            _ => {}
+        }
         status = match status {
             ConnectionState::Disconnected {
                 connect: false,
@@ -258,7 +292,7 @@ pub async fn connector(
                     info!("Connected to {}", address);
                     ConnectionState::Connected { address }
                 }
-                Message::ConnectionFailed { info } => {
+                Message::ConnectionFailed { info, .. } => {
                     error!("Connection failed: {}", info);
                     spawn_reconnect_timer(sender.clone());
                     ConnectionState::Disconnected {
@@ -311,7 +345,7 @@ pub async fn connector(
                     }
                 }
                 Message::Connected(_) => panic!("This should never happen"),
-                Message::ConnectionFailed { info } => {
+                Message::ConnectionFailed { info, .. } => {
                     error!("Connection failed: {}", info);
                     spawn_reconnect_timer(sender.clone());
                     output_subscription_manager
@@ -330,18 +364,34 @@ pub async fn connector(
                 Message::ReconnectTimerElapsed => ConnectionState::Connected { address },
             },
         };
-        let status = match &status {
-            ConnectionState::Disconnected { address, connect } => ConnectionStatus::Disconnected {
+        let status = match (&status, &incompatibility) {
+            (
+                ConnectionState::Disconnected {
+                    address: Some(address),
+                    connect: true,
+                }
+                | ConnectionState::Connecting { address, .. },
+                Some(reason),
+            ) => ConnectionStatus::Incompatible {
                 address: address.clone(),
-                connect: *connect,
+                reason: reason.clone(),
             },
-            ConnectionState::Connecting {
-                address,
-                ongoing_connection: _,
-            } => ConnectionStatus::Connecting {
+            (ConnectionState::Disconnected { address, connect }, _) => {
+                ConnectionStatus::Disconnected {
+                    address: address.clone(),
+                    connect: *connect,
+                }
+            }
+            (
+                ConnectionState::Connecting {
+                    address,
+                    ongoing_connection: _,
+                },
+                _,
+            ) => ConnectionStatus::Connecting {
                 address: address.to_string(),
             },
-            ConnectionState::Connected { address } => ConnectionStatus::Connected {
+            (ConnectionState::Connected { address }, _) => ConnectionStatus::Connected {
                 address: address.to_string(),
             },
         };
@@ -366,6 +416,7 @@ fn spawn_connect(address: String, sender: Sender<Message>) -> JoinHandle<()> {
             Err(error) => sender
                 .send(Message::ConnectionFailed {
                     info: format!("{error:#}"),
+                    is_incompatible: error.downcast_ref::<Incompatibility>().is_some(),
                 })
                 .await
                 .unwrap(),
@@ -375,12 +426,70 @@ fn spawn_connect(address: String, sender: Sender<Message>) -> JoinHandle<()> {
 
 async fn try_connect(address: String) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
     info!("Try connection to {}", address);
-    let (ws_stream, _response) = connect_async(&address)
+    let (mut ws_stream, _response) = connect_async(&address)
         .await
         .wrap_err_with(|| format!("cannot connect websocket to {address}"))?;
+    let server_information = timeout(HANDSHAKE_TIMEOUT, handshake(&mut ws_stream))
+        .await
+        .map_err(|_| eyre!("server did not answer the handshake in time"))?
+        .wrap_err_with(|| format!("handshake with {address} failed"))?;
+    info!(
+        "Server at {address} runs revision {} with cycler instances {:?}",
+        server_information.code_revision, server_information.cycler_instances
+    );
     Ok(ws_stream)
 }
 
+/// Exchanges protocol versions and code revisions before any other request is sent
+async fn handshake(
+    ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+) -> Result<ServerInformation> {
+    let request = Request::Hello(HelloRequest {
+        id: 0,
+        protocol_version: PROTOCOL_VERSION,
+        code_revision: CODE_REVISION.to_string(),
+    });
+    ws_stream
+        .send(tungstenite::Message::Text(
+            to_string(&request).wrap_err("failed to serialize hello request")?,
+        ))
+        .await
+        .wrap_err("failed to send hello request")?;
+
+    while let Some(message) = ws_stream.next().await {
+        match message.wrap_err("failed to receive hello response")? {
+            tungstenite::Message::Text(content) => {
+                let Ok(TextualResponse::Hello(HelloResponse { result, .. })) =
+                    from_str::<TextualResponse>(&content)
+                else {
+                    bail!(Incompatibility(
+                        "server answered the hello with an unknown message".to_string()
+                    ));
+                };
+                let server_information = result.map_err(Incompatibility)?;
+                if server_information.protocol_version != PROTOCOL_VERSION {
+                    bail!(Incompatibility(format!(
+                        "server speaks protocol version {} (revision {}) but client speaks version {PROTOCOL_VERSION} (revision {CODE_REVISION})",
+                        server_information.protocol_version, server_information.code_revision,
+                    )));
+                }
+                if server_information.code_revision != CODE_REVISION {
+                    warn!(
+                        "Server was built from revision {} but client from revision {CODE_REVISION}",
+                        server_information.code_revision
+                    );
+                }
+                return Ok(server_information);
+            }
+            tungstenite::Message::Close(_) => bail!(Incompatibility(
+                "server closed the connection during the handshake, it is probably too old to support protocol versioning".to_string()
+            )),
+            _ => {}
+        }
+    }
+    bail!("connection closed during the handshake")
+}
+
 async fn replace_ongoing_connection(
     ongoing_connection: JoinHandle<()>,
     new_address: String,
@@ -4,10 +4,14 @@ mod id_tracker;
 mod output_subscription_manager;
 mod parameter_subscription_manager;
 mod receiver;
+mod recording;
+mod replayer;
 mod requester;
 mod responder;
 mod types;
 
 pub use crate::client::communication::Communication;
 pub use connector::ConnectionStatus;
+pub use recording::{RecordedData, RecordedUpdate, Recorder, RecordingReader, RECORDING_VERSION};
+pub use replayer::Replay;
 pub use types::{Cycler, CyclerOutput, HierarchyType, Output, OutputHierarchy, SubscriberMessage};
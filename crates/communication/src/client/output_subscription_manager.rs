@@ -2,6 +2,7 @@ use std::collections::{hash_map::Entry, HashMap};
 
 use color_eyre::Result;
 use log::{error, info, warn};
+use serde_json::from_str;
 use tokio::{
     spawn,
     sync::{mpsc, oneshot},
@@ -11,10 +12,11 @@ use uuid::Uuid;
 use crate::{
     client::{
         id_tracker::{self, get_message_id},
+        recording::{RecordedData, RecordedUpdate, Recorder},
         responder, Output, SubscriberMessage,
     },
     messages::{
-        Fields, Format, OutputsRequest, Request,
+        Fields, Format, OutputsRequest, Request, SubscriptionPolicy,
         TextualDataOrBinaryReference::{self, BinaryReference, TextualData},
     },
 };
@@ -30,6 +32,7 @@ pub enum Message {
     Subscribe {
         output: CyclerOutput,
         format: Format,
+        policy: SubscriptionPolicy,
         subscriber: mpsc::Sender<SubscriberMessage>,
         response_sender: oneshot::Sender<Uuid>,
     },
@@ -48,13 +51,25 @@ pub enum Message {
     GetOutputFields {
         response_sender: oneshot::Sender<Option<Fields>>,
     },
+    StartRecording {
+        recorder: Recorder,
+    },
+    StopRecording,
+    /// Update from a recording, delivered to the subscribers of the output with the recorded format
+    /// and policy
+    Replay {
+        update: RecordedUpdate,
+    },
 }
 
+/// Subscribers of the same output with different formats or policies need separate subscriptions
+type SubscriptionKey = (CyclerOutput, Format, SubscriptionPolicy);
+
 #[derive(Default)]
 struct SubscriptionManager {
-    ids_to_outputs: HashMap<usize, (CyclerOutput, Format)>,
+    ids_to_outputs: HashMap<usize, SubscriptionKey>,
     outputs_to_subscribers:
-        HashMap<(CyclerOutput, Format), HashMap<Uuid, mpsc::Sender<SubscriberMessage>>>,
+        HashMap<SubscriptionKey, HashMap<Uuid, mpsc::Sender<SubscriberMessage>>>,
 }
 
 pub async fn output_subscription_manager(
@@ -67,7 +82,8 @@ pub async fn output_subscription_manager(
     let mut requester = None;
     let mut fields = None;
     let mut binary_data_waiting_for_references: HashMap<usize, Vec<u8>> = HashMap::new();
-    let mut binary_references_waiting_for_data: HashMap<usize, CyclerOutput> = HashMap::new();
+    let mut binary_references_waiting_for_data: HashMap<usize, SubscriptionKey> = HashMap::new();
+    let mut recorder = None;
 
     while let Some(message) = receiver.recv().await {
         match message {
@@ -75,11 +91,12 @@ pub async fn output_subscription_manager(
                 requester: new_requester,
             } => {
                 assert!(manager.ids_to_outputs.is_empty());
-                for ((output, format), subscribers) in &manager.outputs_to_subscribers {
+                for ((output, format, policy), subscribers) in &manager.outputs_to_subscribers {
                     let subscribers = subscribers.values().cloned().collect();
                     if let Some(subscription_id) = subscribe(
                         output.clone(),
                         *format,
+                        *policy,
                         subscribers,
                         &id_tracker,
                         &responder,
@@ -89,7 +106,7 @@ pub async fn output_subscription_manager(
                     {
                         manager
                             .ids_to_outputs
-                            .insert(subscription_id, (output.clone(), *format));
+                            .insert(subscription_id, (output.clone(), *format, *policy));
                     }
                 }
                 match query_output_fields(sender.clone(), &id_tracker, &responder, &new_requester)
@@ -108,6 +125,7 @@ pub async fn output_subscription_manager(
             Message::Subscribe {
                 output,
                 format,
+                policy,
                 subscriber: output_sender,
                 response_sender,
             } => {
@@ -119,6 +137,7 @@ pub async fn output_subscription_manager(
                             uuid,
                             output,
                             format,
+                            policy,
                             output_sender,
                             &id_tracker,
                             &responder,
@@ -168,6 +187,9 @@ pub async fn output_subscription_manager(
                     if let Some(senders) = manager.outputs_to_subscribers.get(output) {
                         match value_or_reference {
                             TextualData { data } => {
+                                record(&mut recorder, output, || {
+                                    RecordedData::Textual(data.to_string())
+                                });
                                 for sender in senders.values() {
                                     if let Err(error) = sender
                                         .send(SubscriberMessage::Update {
@@ -183,6 +205,9 @@ pub async fn output_subscription_manager(
                                 if let Some(image) =
                                     binary_data_waiting_for_references.remove(&reference_id)
                                 {
+                                    record(&mut recorder, output, || {
+                                        RecordedData::Binary(image.clone())
+                                    });
                                     for sender in senders.values() {
                                         if let Err(error) = sender
                                             .send(SubscriberMessage::UpdateBinary {
@@ -195,7 +220,7 @@ pub async fn output_subscription_manager(
                                     }
                                 } else {
                                     binary_references_waiting_for_data
-                                        .insert(reference_id, output.0.clone());
+                                        .insert(reference_id, output.clone());
                                 }
                             }
                         }
@@ -213,9 +238,8 @@ pub async fn output_subscription_manager(
             Message::UpdateBinary { referenced_items } => {
                 for (reference_id, data) in referenced_items {
                     if let Some(output) = binary_references_waiting_for_data.get(&reference_id) {
-                        let subscribers = manager
-                            .outputs_to_subscribers
-                            .get(&(output.clone(), Format::Binary));
+                        record(&mut recorder, output, || RecordedData::Binary(data.clone()));
+                        let subscribers = manager.outputs_to_subscribers.get(output);
                         if let Some(senders) = subscribers {
                             for sender in senders.values() {
                                 if let Err(error) = sender
@@ -231,11 +255,56 @@ pub async fn output_subscription_manager(
                     }
                 }
             }
+            Message::StartRecording {
+                recorder: new_recorder,
+            } => {
+                recorder = Some(new_recorder);
+            }
+            Message::StopRecording => {
+                if let Some(mut recorder) = recorder.take() {
+                    if let Err(error) = recorder.flush() {
+                        error!("{error:#}");
+                    }
+                }
+            }
+            Message::Replay { update } => {
+                let message = match update.data {
+                    RecordedData::Textual(data) => match from_str(&data) {
+                        Ok(value) => SubscriberMessage::Update { value },
+                        Err(error) => {
+                            error!("failed to deserialize recorded {}: {error}", update.output);
+                            continue;
+                        }
+                    },
+                    RecordedData::Binary(data) => SubscriberMessage::UpdateBinary { data },
+                };
+                let key = (update.output, update.format, update.policy);
+                if let Some(senders) = manager.outputs_to_subscribers.get(&key) {
+                    for sender in senders.values() {
+                        if let Err(error) = sender.send(message.clone()).await {
+                            error!("{error}");
+                        }
+                    }
+                }
+            }
         }
     }
     info!("Finished manager");
 }
 
+fn record(
+    recorder: &mut Option<Recorder>,
+    (output, format, policy): &SubscriptionKey,
+    data: impl FnOnce() -> RecordedData,
+) {
+    if let Some(active_recorder) = recorder {
+        if let Err(error) = active_recorder.record(output, *format, *policy, data()) {
+            error!("stopping recording: {error:#}");
+            *recorder = None;
+        }
+    }
+}
+
 async fn query_output_fields(
     manager: mpsc::Sender<Message>,
     id_tracker: &mpsc::Sender<id_tracker::Message>,
@@ -272,6 +341,7 @@ async fn add_subscription(
     uuid: Uuid,
     output: CyclerOutput,
     format: Format,
+    policy: SubscriptionPolicy,
     output_sender: mpsc::Sender<SubscriberMessage>,
     id_tracker: &mpsc::Sender<id_tracker::Message>,
     responder: &mpsc::Sender<responder::Message>,
@@ -279,7 +349,7 @@ async fn add_subscription(
 ) {
     match manager
         .outputs_to_subscribers
-        .entry((output.clone(), format))
+        .entry((output.clone(), format, policy))
     {
         Entry::Occupied(mut entry) => {
             entry.get_mut().insert(uuid, output_sender);
@@ -289,6 +359,7 @@ async fn add_subscription(
                 if let Some(subscription_id) = subscribe(
                     output.clone(),
                     format,
+                    policy,
                     vec![output_sender.clone()],
                     id_tracker,
                     responder,
@@ -298,7 +369,7 @@ async fn add_subscription(
                 {
                     manager
                         .ids_to_outputs
-                        .insert(subscription_id, (output, format));
+                        .insert(subscription_id, (output, format, policy));
                 }
             };
             entry.insert(HashMap::new()).insert(uuid, output_sender);
@@ -309,6 +380,7 @@ async fn add_subscription(
 async fn subscribe(
     output: CyclerOutput,
     format: Format,
+    policy: SubscriptionPolicy,
     subscribers: Vec<mpsc::Sender<SubscriberMessage>>,
     id_tracker: &mpsc::Sender<id_tracker::Message>,
     responder: &mpsc::Sender<responder::Message>,
@@ -335,6 +407,7 @@ async fn subscribe(
         cycler_instance: output.cycler.to_string(),
         path,
         format,
+        policy,
     });
     if let Err(error) = requester.send(request).await {
         error!("{error}");
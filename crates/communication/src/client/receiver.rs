@@ -1,6 +1,6 @@
 use bincode::deserialize;
 use futures_util::{stream::SplitStream, StreamExt};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use serde_json::from_str;
 use tokio::{net::TcpStream, sync::mpsc::Sender};
 use tokio_tungstenite::{tungstenite, MaybeTlsStream, WebSocketStream};
@@ -90,6 +90,9 @@ pub async fn receiver(
                             ParametersResponse::LoadFromDisk { id: _, result: _ } => todo!(),
                             ParametersResponse::StoreToDisk { id: _, result: _ } => todo!(),
                         },
+                        TextualResponse::Hello(response) => {
+                            warn!("Ignoring unexpected hello response {response:?}")
+                        }
                         message => todo!("unimplemented message {message:?}"),
                     }
                 }
@@ -136,6 +139,7 @@ pub async fn receiver(
                 connector
                     .send(connector::Message::ConnectionFailed {
                         info: "Peer disconnected".to_string(),
+                        is_incompatible: false,
                     })
                     .await
                     .unwrap();
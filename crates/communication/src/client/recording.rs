@@ -0,0 +1,282 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant, SystemTime},
+};
+
+use bincode::{deserialize_from, serialize_into};
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::messages::{Format, SubscriptionPolicy, CODE_REVISION};
+
+use super::CyclerOutput;
+
+const MAGIC: &[u8; 8] = b"HULKLOG\0";
+/// Incremented with every change of the records that breaks reading older recordings
+pub const RECORDING_VERSION: u32 = 2;
+
+/// Entry of a recording, a recording is the magic bytes followed by a header and a sequence of
+/// stream declarations and data records
+#[derive(Debug, Deserialize, Serialize)]
+enum Record {
+    Header {
+        version: u32,
+        code_revision: String,
+        start: SystemTime,
+    },
+    /// Declares a stream before its first data record, the output is stored in its textual
+    /// representation, e.g. `Control.main.robot_to_field`. Subscriptions of the same output with
+    /// different policies receive different updates and are therefore recorded as separate
+    /// streams.
+    Stream {
+        id: usize,
+        output: String,
+        format: Format,
+        policy: SubscriptionPolicy,
+    },
+    Data {
+        stream: usize,
+        time: Duration,
+        data: RecordedData,
+    },
+}
+
+/// Data of one update, textual data is stored as JSON because bincode cannot represent arbitrary
+/// JSON values
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum RecordedData {
+    Textual(String),
+    Binary(Vec<u8>),
+}
+
+/// Writes updates of subscribed outputs with timestamps relative to the start of the recording
+#[derive(Debug)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    start: Instant,
+    streams: HashMap<(CyclerOutput, Format, SubscriptionPolicy), usize>,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .wrap_err_with(|| format!("failed to create recording {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(MAGIC)
+            .wrap_err("failed to write magic bytes")?;
+        serialize_into(
+            &mut writer,
+            &Record::Header {
+                version: RECORDING_VERSION,
+                code_revision: CODE_REVISION.to_string(),
+                start: SystemTime::now(),
+            },
+        )
+        .wrap_err("failed to write header")?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            streams: HashMap::new(),
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        output: &CyclerOutput,
+        format: Format,
+        policy: SubscriptionPolicy,
+        data: RecordedData,
+    ) -> Result<()> {
+        let time = self.start.elapsed();
+        let next_id = self.streams.len();
+        let stream = *self
+            .streams
+            .entry((output.clone(), format, policy))
+            .or_insert(next_id);
+        if stream == next_id {
+            serialize_into(
+                &mut self.writer,
+                &Record::Stream {
+                    id: stream,
+                    output: output.to_string(),
+                    format,
+                    policy,
+                },
+            )
+            .wrap_err("failed to write stream declaration")?;
+        }
+        serialize_into(&mut self.writer, &Record::Data { stream, time, data })
+            .wrap_err("failed to write data")
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().wrap_err("failed to flush recording")
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordedUpdate {
+    pub time: Duration,
+    pub output: CyclerOutput,
+    pub format: Format,
+    pub policy: SubscriptionPolicy,
+    pub data: RecordedData,
+}
+
+/// Reads a recording sequentially without loading it into memory at once
+pub struct RecordingReader {
+    reader: BufReader<File>,
+    pub code_revision: String,
+    pub start: SystemTime,
+    streams: HashMap<usize, (CyclerOutput, Format, SubscriptionPolicy)>,
+}
+
+impl RecordingReader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .wrap_err_with(|| format!("failed to open recording {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut magic = [0; MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .wrap_err("failed to read magic bytes")?;
+        if &magic != MAGIC {
+            bail!("{} is not a recording", path.display());
+        }
+        let Record::Header {
+            version,
+            code_revision,
+            start,
+        } = deserialize_from(&mut reader).wrap_err("failed to read header")?
+        else {
+            bail!("recording does not start with a header");
+        };
+        if version != RECORDING_VERSION {
+            bail!("unsupported recording version {version}, expected {RECORDING_VERSION}");
+        }
+        Ok(Self {
+            reader,
+            code_revision,
+            start,
+            streams: HashMap::new(),
+        })
+    }
+
+    /// Returns the next update or `None` at the end of the recording
+    ///
+    /// A record truncated by an interrupted recording is treated as the end of the recording.
+    pub fn next_update(&mut self) -> Result<Option<RecordedUpdate>> {
+        loop {
+            let record = match deserialize_from(&mut self.reader) {
+                Ok(record) => record,
+                Err(error) => match *error {
+                    bincode::ErrorKind::Io(error) if error.kind() == ErrorKind::UnexpectedEof => {
+                        return Ok(None)
+                    }
+                    error => return Err(error).wrap_err("failed to read record"),
+                },
+            };
+            match record {
+                Record::Header { .. } => bail!("unexpected header in the middle of the recording"),
+                Record::Stream {
+                    id,
+                    output,
+                    format,
+                    policy,
+                } => {
+                    let output = CyclerOutput::from_str(&output)
+                        .wrap_err_with(|| format!("invalid output of stream {id}"))?;
+                    self.streams.insert(id, (output, format, policy));
+                }
+                Record::Data { stream, time, data } => {
+                    let Some((output, format, policy)) = self.streams.get(&stream) else {
+                        bail!("data of undeclared stream {stream}");
+                    };
+                    return Ok(Some(RecordedUpdate {
+                        time,
+                        output: output.clone(),
+                        format: *format,
+                        policy: *policy,
+                        data,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn recorded_updates_are_read_back_in_order() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("recording.hulklog");
+        let robot_to_field = CyclerOutput::from_str("Control.main.robot_to_field").unwrap();
+        let image = CyclerOutput::from_str("VisionTop.main.image").unwrap();
+        let every_fifth_cycle = SubscriptionPolicy::EveryNthCycle { n: 5 };
+        {
+            let mut recorder = Recorder::create(&path).unwrap();
+            recorder
+                .record(
+                    &robot_to_field,
+                    Format::Textual,
+                    SubscriptionPolicy::EveryCycle,
+                    RecordedData::Textual("42".to_string()),
+                )
+                .unwrap();
+            recorder
+                .record(
+                    &image,
+                    Format::Binary,
+                    SubscriptionPolicy::EveryCycle,
+                    RecordedData::Binary(vec![1, 2, 3]),
+                )
+                .unwrap();
+            recorder
+                .record(
+                    &robot_to_field,
+                    Format::Textual,
+                    every_fifth_cycle,
+                    RecordedData::Textual("1337".to_string()),
+                )
+                .unwrap();
+        }
+
+        let mut reader = RecordingReader::open(&path).unwrap();
+        let mut updates = Vec::new();
+        while let Some(update) = reader.next_update().unwrap() {
+            updates.push(update);
+        }
+
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[0].output, robot_to_field);
+        assert!(matches!(&updates[0].data, RecordedData::Textual(data) if data == "42"));
+        assert_eq!(updates[1].output, image);
+        assert_eq!(updates[1].format, Format::Binary);
+        assert!(matches!(&updates[1].data, RecordedData::Binary(data) if data == &[1, 2, 3]));
+        assert_eq!(updates[2].output, robot_to_field);
+        assert_eq!(updates[2].policy, every_fifth_cycle);
+        assert!(matches!(&updates[2].data, RecordedData::Textual(data) if data == "1337"));
+        assert!(updates[0].time <= updates[2].time);
+    }
+}
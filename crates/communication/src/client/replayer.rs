@@ -0,0 +1,101 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use color_eyre::Result;
+use log::{error, info};
+use tokio::{sync::mpsc::Sender, task::spawn_blocking};
+
+use crate::{
+    client::{output_subscription_manager::Message, Output},
+    messages::Fields,
+};
+
+use super::recording::RecordingReader;
+
+/// Longest sleep between checks whether the replay was stopped
+const MAXIMUM_SLEEP: Duration = Duration::from_millis(100);
+
+/// Replay of a recording in real time, stops when dropped
+pub struct Replay {
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl Drop for Replay {
+    fn drop(&mut self) {
+        self.is_stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Hands the updates of the recording to the output subscription manager as if they were
+/// received from a robot
+pub fn replay(
+    path: impl AsRef<Path>,
+    output_subscription_manager: Sender<Message>,
+) -> Result<Replay> {
+    let mut reader = RecordingReader::open(path)?;
+    info!(
+        "Replaying recording of revision {} started at {:?}",
+        reader.code_revision, reader.start
+    );
+    let is_stopped = Arc::new(AtomicBool::new(false));
+    let replay = Replay {
+        is_stopped: is_stopped.clone(),
+    };
+
+    spawn_blocking(move || {
+        let start = Instant::now();
+        let mut fields = Fields::new();
+        while !is_stopped.load(Ordering::SeqCst) {
+            let update = match reader.next_update() {
+                Ok(Some(update)) => update,
+                Ok(None) => {
+                    info!("Replay finished");
+                    break;
+                }
+                Err(error) => {
+                    error!("Replay failed: {error:#}");
+                    break;
+                }
+            };
+
+            let path = match &update.output.output {
+                Output::Main { path } => format!("main_outputs.{path}"),
+                Output::Additional { path } => format!("additional_outputs.{path}"),
+            };
+            let is_new_field = fields
+                .entry(update.output.cycler.to_string())
+                .or_default()
+                .insert(path);
+            if is_new_field
+                && output_subscription_manager
+                    .blocking_send(Message::UpdateFields {
+                        fields: fields.clone(),
+                    })
+                    .is_err()
+            {
+                break;
+            }
+
+            while let Some(remaining) = update.time.checked_sub(start.elapsed()) {
+                if is_stopped.load(Ordering::SeqCst) {
+                    return;
+                }
+                sleep(remaining.min(MAXIMUM_SLEEP));
+            }
+            if output_subscription_manager
+                .blocking_send(Message::Replay { update })
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    Ok(replay)
+}
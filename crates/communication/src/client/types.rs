@@ -41,6 +41,15 @@ impl FromStr for CyclerOutput {
     }
 }
 
+impl Display for CyclerOutput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.output {
+            Output::Main { path } => write!(f, "{}.main.{path}", self.cycler),
+            Output::Additional { path } => write!(f, "{}.additional.{path}", self.cycler),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum Cycler {
     Control,
@@ -1,4 +1,7 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::Duration,
+};
 
 use parameters::directory::Scope;
 use serde::{Deserialize, Serialize};
@@ -11,8 +14,13 @@ pub type Reason = String;
 pub type Type = String;
 pub type Fields = BTreeMap<CyclerInstance, BTreeSet<Path>>;
 
+/// Incremented with every change of the messages that breaks compatibility with older peers
+pub const PROTOCOL_VERSION: u32 = 2;
+pub use code_revision::CODE_REVISION;
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Request {
+    Hello(HelloRequest),
     Injections(InjectionsRequest),
     Outputs(OutputsRequest),
     Parameters(ParametersRequest),
@@ -27,6 +35,7 @@ pub enum Response {
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum TextualResponse {
+    Hello(HelloResponse),
     Injections(InjectionsResponse),
     Outputs(TextualOutputsResponse),
     Parameters(ParametersResponse),
@@ -37,6 +46,28 @@ pub enum BinaryResponse {
     Outputs(BinaryOutputsResponse),
 }
 
+/// First message of a client after connecting
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HelloRequest {
+    pub id: usize,
+    pub protocol_version: u32,
+    pub code_revision: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HelloResponse {
+    pub id: usize,
+    pub result: Result<ServerInformation, Reason>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ServerInformation {
+    pub protocol_version: u32,
+    pub code_revision: String,
+    pub cycler_instances: BTreeSet<CyclerInstance>,
+    pub formats: BTreeSet<Format>,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum InjectionsRequest {
     Set {
@@ -81,6 +112,8 @@ pub enum OutputsRequest {
         cycler_instance: CyclerInstance,
         path: Path,
         format: Format,
+        #[serde(default)]
+        policy: SubscriptionPolicy,
     },
     Unsubscribe {
         id: usize,
@@ -177,7 +210,20 @@ pub enum ParametersResponse {
     },
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+/// Decides in which cycles the server sends the data of an output subscription
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum SubscriptionPolicy {
+    #[default]
+    EveryCycle,
+    /// At most one update per interval, e.g. 100 ms for 10 Hz
+    MaximumRate { interval: Duration },
+    /// Only every n-th cycle starting with the first one, 0 and 1 send every cycle
+    EveryNthCycle { n: usize },
+    /// Only cycles in which the serialized data differs from the last sent data
+    OnChange,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum Format {
     Textual,
     Binary,
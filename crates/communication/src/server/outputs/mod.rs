@@ -1,8 +1,12 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+    time::Instant,
+};
 
 use tokio::sync::mpsc::Sender;
 
-use crate::messages::{Format, OutputsRequest, Path};
+use crate::messages::{Format, HelloRequest, OutputsRequest, Path, SubscriptionPolicy};
 
 use super::client_request::ClientRequest;
 
@@ -12,6 +16,7 @@ pub mod router;
 #[derive(Debug)]
 pub enum Request {
     ClientRequest(ClientRequest<OutputsRequest>),
+    Hello(ClientRequest<HelloRequest>),
     RegisterCycler {
         cycler_instance: String,
         fields: BTreeSet<Path>,
@@ -24,4 +29,101 @@ struct Subscription {
     pub path: Path,
     pub format: Format,
     pub once: bool,
+    pub policy: SubscriptionPolicy,
+    cycles_until_due: usize,
+    last_update: Option<Instant>,
+    last_data_hash: Option<u64>,
+}
+
+impl Subscription {
+    fn new(path: Path, format: Format, once: bool, policy: SubscriptionPolicy) -> Self {
+        Self {
+            path,
+            format,
+            once,
+            policy,
+            cycles_until_due: 0,
+            last_update: None,
+            last_data_hash: None,
+        }
+    }
+
+    /// Whether the policy allows an update in this cycle, checked before serializing the data
+    fn is_due(&mut self, now: Instant) -> bool {
+        match self.policy {
+            SubscriptionPolicy::EveryCycle | SubscriptionPolicy::OnChange => true,
+            SubscriptionPolicy::MaximumRate { interval } => {
+                let is_due = !matches!(
+                    self.last_update,
+                    Some(last_update) if now.duration_since(last_update) < interval
+                );
+                if is_due {
+                    self.last_update = Some(now);
+                }
+                is_due
+            }
+            SubscriptionPolicy::EveryNthCycle { n } => {
+                if self.cycles_until_due == 0 {
+                    self.cycles_until_due = n.saturating_sub(1);
+                    true
+                } else {
+                    self.cycles_until_due -= 1;
+                    false
+                }
+            }
+        }
+    }
+
+    /// Whether the serialized data needs to be sent, only `OnChange` suppresses repeated data
+    fn has_changed(&mut self, serialized_data: &[u8]) -> bool {
+        if self.policy != SubscriptionPolicy::OnChange {
+            return true;
+        }
+        let mut hasher = DefaultHasher::new();
+        serialized_data.hash(&mut hasher);
+        let hash = hasher.finish();
+        let has_changed = self.last_data_hash != Some(hash);
+        self.last_data_hash = Some(hash);
+        has_changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn subscription(policy: SubscriptionPolicy) -> Subscription {
+        Subscription::new("a.b.c".to_string(), Format::Textual, false, policy)
+    }
+
+    #[test]
+    fn every_nth_cycle_starts_with_first_cycle() {
+        let mut subscription = subscription(SubscriptionPolicy::EveryNthCycle { n: 3 });
+        let now = Instant::now();
+        let due_cycles: Vec<_> = (0..7).map(|_| subscription.is_due(now)).collect();
+        assert_eq!(due_cycles, [true, false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn maximum_rate_suppresses_updates_within_interval() {
+        let mut subscription = subscription(SubscriptionPolicy::MaximumRate {
+            interval: Duration::from_millis(100),
+        });
+        let start = Instant::now();
+        assert!(subscription.is_due(start));
+        assert!(!subscription.is_due(start + Duration::from_millis(50)));
+        assert!(subscription.is_due(start + Duration::from_millis(100)));
+        assert!(!subscription.is_due(start + Duration::from_millis(150)));
+    }
+
+    #[test]
+    fn on_change_suppresses_repeated_data() {
+        let mut subscription = subscription(SubscriptionPolicy::OnChange);
+        assert!(subscription.has_changed(b"42"));
+        assert!(!subscription.has_changed(b"42"));
+        assert!(subscription.has_changed(b"1337"));
+        assert!(subscription.has_changed(b"42"));
+    }
 }
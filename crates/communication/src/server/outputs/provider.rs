@@ -2,6 +2,7 @@ use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     num::Wrapping,
     sync::Arc,
+    time::Instant,
 };
 
 use bincode::{DefaultOptions, Options};
@@ -21,7 +22,7 @@ use tokio::{
 use crate::{
     messages::{
         BinaryOutputsResponse, BinaryResponse, Format, OutputsRequest, Response,
-        TextualDataOrBinaryReference, TextualOutputsResponse, TextualResponse,
+        SubscriptionPolicy, TextualDataOrBinaryReference, TextualOutputsResponse, TextualResponse,
     },
     server::{client::Client, client_request::ClientRequest},
 };
@@ -96,6 +97,10 @@ where
     Outputs: SerializeHierarchy,
 {
     let is_get_next = matches!(request.request, OutputsRequest::GetNext { .. });
+    let policy = match request.request {
+        OutputsRequest::Subscribe { policy, .. } => policy,
+        _ => SubscriptionPolicy::EveryCycle,
+    };
     match request.request {
         OutputsRequest::GetFields { .. } => {
             panic!("GetFields should be answered by output router");
@@ -111,6 +116,7 @@ where
             cycler_instance: received_cycler_instance,
             path,
             format,
+            ..
         } => {
             assert_eq!(cycler_instance, received_cycler_instance);
             if Outputs::exists(&path) {
@@ -138,11 +144,7 @@ where
                         SubscriptionsState::Unchanged
                     }
                     Entry::Vacant(entry) => {
-                        entry.insert(Subscription {
-                            path,
-                            format,
-                            once: is_get_next,
-                        });
+                        entry.insert(Subscription::new(path, format, is_get_next, policy));
                         if !is_get_next {
                             request
                                 .client
@@ -245,7 +247,11 @@ async fn handle_notified_output(
     let mut subscriptions_state = SubscriptionsState::Unchanged;
     {
         let output = outputs_reader.next();
+        let now = Instant::now();
         subscriptions.retain(|(client, subscription_id), subscription| {
+            if !subscription.is_due(now) {
+                return true;
+            }
             let data = match subscription.format {
                 Format::Textual => {
                     let data = match output
@@ -257,6 +263,9 @@ async fn handle_notified_output(
                             return true;
                         }
                     };
+                    if !subscription.has_changed(data.to_string().as_bytes()) {
+                        return true;
+                    }
                     TextualDataOrBinaryReference::TextualData { data }
                 }
                 Format::Binary => {
@@ -269,6 +278,9 @@ async fn handle_notified_output(
                         error!("failed to serialize {:?}: {error:?}", subscription.path);
                         return true;
                     }
+                    if !subscription.has_changed(&data) {
+                        return true;
+                    }
                     let reference_id = next_binary_reference_id.0;
                     *next_binary_reference_id += 1;
                     if subscription.once {
@@ -534,6 +546,7 @@ mod tests {
                     cycler_instance: cycler_instance.clone(),
                     path: path.clone(),
                     format,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -571,6 +584,7 @@ mod tests {
                     cycler_instance,
                     path: path.clone(),
                     format,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -635,6 +649,7 @@ mod tests {
                     cycler_instance: cycler_instance.clone(),
                     path: path.clone(),
                     format,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: 1337,
@@ -672,6 +687,7 @@ mod tests {
                     cycler_instance,
                     path: path.clone(),
                     format,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: 7331,
@@ -736,6 +752,7 @@ mod tests {
                     cycler_instance: cycler_instance.clone(),
                     path: path.clone(),
                     format,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -773,6 +790,7 @@ mod tests {
                     cycler_instance,
                     path: path.clone(),
                     format,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -890,6 +908,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -1014,6 +1033,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: 1337,
@@ -1130,6 +1150,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -1257,6 +1278,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Binary,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -1389,6 +1411,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
@@ -1427,6 +1450,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    policy: SubscriptionPolicy::EveryCycle,
                 },
                 client: Client {
                     id: client_id,
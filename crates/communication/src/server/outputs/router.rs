@@ -6,8 +6,13 @@ use tokio::{
     task::JoinHandle,
 };
 
+use log::warn;
+
 use crate::{
-    messages::{OutputsRequest, Path, Response, TextualOutputsResponse, TextualResponse},
+    messages::{
+        Format, HelloRequest, HelloResponse, OutputsRequest, Path, Response, ServerInformation,
+        TextualOutputsResponse, TextualResponse, CODE_REVISION, PROTOCOL_VERSION,
+    },
     server::{client::Client, client_request::ClientRequest},
 };
 
@@ -28,6 +33,9 @@ pub fn router(mut request_receiver: Receiver<Request>) -> JoinHandle<()> {
                     )
                     .await
                 }
+                Request::Hello(request) => {
+                    handle_hello(request, &request_channels_of_cyclers).await
+                }
                 Request::RegisterCycler {
                     cycler_instance,
                     fields,
@@ -40,6 +48,44 @@ pub fn router(mut request_receiver: Receiver<Request>) -> JoinHandle<()> {
     })
 }
 
+async fn handle_hello(
+    request: ClientRequest<HelloRequest>,
+    request_channels_of_cyclers: &HashMap<
+        String,
+        (BTreeSet<Path>, Sender<ClientRequest<OutputsRequest>>),
+    >,
+) {
+    let client_version = request.request.protocol_version;
+    let result = if client_version == PROTOCOL_VERSION {
+        if request.request.code_revision != CODE_REVISION {
+            warn!(
+                "client {} was built from revision {} but server from revision {CODE_REVISION}",
+                request.client.id, request.request.code_revision
+            );
+        }
+        Ok(ServerInformation {
+            protocol_version: PROTOCOL_VERSION,
+            code_revision: CODE_REVISION.to_string(),
+            cycler_instances: request_channels_of_cyclers.keys().cloned().collect(),
+            formats: [Format::Textual, Format::Binary].into(),
+        })
+    } else {
+        Err(format!(
+            "server speaks protocol version {PROTOCOL_VERSION} (revision {CODE_REVISION}) but client speaks version {client_version} (revision {})",
+            request.request.code_revision
+        ))
+    };
+    request
+        .client
+        .response_sender
+        .send(Response::Textual(TextualResponse::Hello(HelloResponse {
+            id: request.request.id,
+            result,
+        })))
+        .await
+        .expect("receiver should always wait for all senders");
+}
+
 async fn handle_request(
     request: ClientRequest<OutputsRequest>,
     request_channels_of_cyclers: &HashMap<
@@ -175,7 +221,7 @@ async fn handle_request(
 mod tests {
     use tokio::sync::mpsc::{channel, error::TryRecvError};
 
-    use crate::messages::Format;
+    use crate::messages::SubscriptionPolicy;
 
     use super::*;
 
@@ -235,6 +281,69 @@ mod tests {
         router_task.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn hello_reports_cycler_instances_or_incompatibility() {
+        let (request_sender, request_receiver) = channel(1);
+        let router_task = router(request_receiver);
+
+        let (provider_request_sender, _provider_request_receiver) = channel(1);
+        request_sender
+            .send(Request::RegisterCycler {
+                cycler_instance: "CyclerInstance".to_string(),
+                fields: Default::default(),
+                request_sender: provider_request_sender,
+            })
+            .await
+            .unwrap();
+
+        let (response_sender, mut response_receiver) = channel(1);
+        for protocol_version in [PROTOCOL_VERSION, PROTOCOL_VERSION + 1] {
+            request_sender
+                .send(Request::Hello(ClientRequest {
+                    request: HelloRequest {
+                        id: 42,
+                        protocol_version,
+                        code_revision: CODE_REVISION.to_string(),
+                    },
+                    client: Client {
+                        id: 1337,
+                        response_sender: response_sender.clone(),
+                    },
+                }))
+                .await
+                .unwrap();
+        }
+        drop(response_sender);
+
+        let response = response_receiver.recv().await.unwrap();
+        assert_eq!(
+            response,
+            Response::Textual(TextualResponse::Hello(HelloResponse {
+                id: 42,
+                result: Ok(ServerInformation {
+                    protocol_version: PROTOCOL_VERSION,
+                    code_revision: CODE_REVISION.to_string(),
+                    cycler_instances: ["CyclerInstance".to_string()].into(),
+                    formats: [Format::Textual, Format::Binary].into(),
+                }),
+            })),
+        );
+        let response = response_receiver.recv().await.unwrap();
+        assert!(
+            matches!(
+                response,
+                Response::Textual(TextualResponse::Hello(HelloResponse {
+                    id: 42,
+                    result: Err(_),
+                }))
+            ),
+            "unexpected {response:?}",
+        );
+
+        drop(request_sender);
+        router_task.await.unwrap();
+    }
+
     #[tokio::test]
     async fn unknown_cycler_instance_results_in_error() {
         let (request_sender, request_receiver) = channel(1);
@@ -343,6 +452,7 @@ mod tests {
                 cycler_instance: "CyclerInstance".to_string(),
                 path: "a.b.c".to_string(),
                 format: Format::Textual,
+                policy: SubscriptionPolicy::EveryCycle,
             },
             client: client.clone(),
         };
@@ -109,6 +109,12 @@ async fn handle_message(
                 response_sender: response_sender.clone(),
             };
             match request {
+                Request::Hello(request) => {
+                    outputs_sender
+                        .send(outputs::Request::Hello(ClientRequest { request, client }))
+                        .await
+                        .expect("receiver should always wait for all senders");
+                }
                 Request::Outputs(request) => {
                     outputs_sender
                         .send(outputs::Request::ClientRequest(ClientRequest {
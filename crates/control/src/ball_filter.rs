@@ -2,7 +2,7 @@ use std::time::{Duration, SystemTime};
 
 use color_eyre::Result;
 use context_attribute::context;
-use filtering::kalman_filter::KalmanFilter;
+use filtering::{hysteresis::greater_than_with_hysteresis, kalman_filter::KalmanFilter};
 use framework::{AdditionalOutput, HistoricInput, MainOutput, PerceptionInput};
 use nalgebra::{
     matrix, vector, Isometry2, Matrix2, Matrix2x4, Matrix4, Matrix4x2, Point2, Vector2, Vector4,
@@ -11,12 +11,16 @@ use projection::Projection;
 use types::{
     ball_filter::Hypothesis, is_above_limbs,
     multivariate_normal_distribution::MultivariateNormalDistribution, Ball, BallPosition,
-    CameraMatrices, CameraMatrix, Circle, CycleTime, FieldDimensions, Limb, ProjectedLimbs,
-    SensorData,
+    BallTrajectory, CameraMatrices, CameraMatrix, Circle, CycleTime, FieldDimensions, Limb,
+    ProjectedLimbs, SensorData,
 };
 
+/// Duration of a control cycle in seconds as assumed by the constant velocity prediction
+const CYCLE_DURATION: f32 = 0.012;
+
 pub struct BallFilter {
     hypotheses: Vec<Hypothesis>,
+    ball_is_moving: bool,
 }
 
 #[context]
@@ -59,6 +63,8 @@ pub struct CycleContext {
     pub initial_covariance: Parameter<Vector4<f32>, "ball_filter.initial_covariance">,
     pub measurement_matching_distance: Parameter<f32, "ball_filter.measurement_matching_distance">,
     pub measurement_noise: Parameter<Vector2<f32>, "ball_filter.measurement_noise">,
+    pub minimum_moving_velocity: Parameter<f32, "ball_filter.minimum_moving_velocity">,
+    pub moving_velocity_hysteresis: Parameter<f32, "ball_filter.moving_velocity_hysteresis">,
     pub process_noise: Parameter<Vector4<f32>, "ball_filter.process_noise">,
     pub validity_discard_threshold: Parameter<f32, "ball_filter.validity_discard_threshold">,
     pub velocity_decay_factor: Parameter<f32, "ball_filter.velocity_decay_factor">,
@@ -73,12 +79,14 @@ pub struct CycleContext {
 #[derive(Default)]
 pub struct MainOutputs {
     pub ball_position: MainOutput<Option<BallPosition>>,
+    pub ball_trajectory: MainOutput<Option<BallTrajectory>>,
 }
 
 impl BallFilter {
     pub fn new(_context: CreationContext) -> Result<Self> {
         Ok(Self {
             hypotheses: Vec::new(),
+            ball_is_moving: false,
         })
     }
 
@@ -140,6 +148,25 @@ impl BallFilter {
             velocity: vector![hypothesis.state.mean.z, hypothesis.state.mean.w],
             last_seen: hypothesis.last_update,
         });
+        let ball_is_moving = match best_hypothesis {
+            Some(hypothesis) => greater_than_with_hysteresis(
+                self.ball_is_moving,
+                hypothesis.state.mean.fixed_rows::<2>(2).norm(),
+                *context.minimum_moving_velocity,
+                *context.moving_velocity_hysteresis,
+            ),
+            None => false,
+        };
+        let velocity_time_constant = velocity_time_constant(*context.velocity_decay_factor);
+        let ball_trajectory = best_hypothesis.map(|hypothesis| {
+            BallTrajectory::new(
+                hypothesis.state,
+                velocity_time_constant,
+                *context.minimum_moving_velocity,
+                ball_is_moving,
+            )
+        });
+        self.ball_is_moving = ball_is_moving;
         context
             .ball_filter_hypotheses
             .fill_if_subscribed(|| self.hypotheses.clone());
@@ -164,6 +191,7 @@ impl BallFilter {
             });
         Ok(MainOutputs {
             ball_position: ball_position.into(),
+            ball_trajectory: ball_trajectory.into(),
         })
     }
 
@@ -202,10 +230,9 @@ impl BallFilter {
         process_noise: Matrix4<f32>,
     ) {
         for hypothesis in self.hypotheses.iter_mut() {
-            let cycle_time = 0.012;
             let constant_velocity_prediction = matrix![
-                1.0, 0.0, cycle_time, 0.0;
-                0.0, 1.0, 0.0, cycle_time;
+                1.0, 0.0, CYCLE_DURATION, 0.0;
+                0.0, 1.0, 0.0, CYCLE_DURATION;
                 0.0, 0.0, velocity_decay_factor, 0.0;
                 0.0, 0.0, 0.0, velocity_decay_factor;
             ];
@@ -333,6 +360,18 @@ impl BallFilter {
     }
 }
 
+/// Time in seconds after which the velocity decayed to `1 / e` of its initial value, infinite if
+/// the velocity does not decay at all
+pub fn velocity_time_constant(velocity_decay_factor: f32) -> f32 {
+    if velocity_decay_factor >= 1.0 {
+        return f32::INFINITY;
+    }
+    if velocity_decay_factor <= 0.0 {
+        return 0.0;
+    }
+    -CYCLE_DURATION / velocity_decay_factor.ln()
+}
+
 fn project_to_image(
     hypothesis: &Hypothesis,
     camera_matrix: &CameraMatrix,
@@ -204,7 +204,9 @@ fn defend_goal_pose(
         _ => role_positions.keeper_x_offset,
     };
 
-    let position_to_defend = point![-field_dimensions.length / 2.0 - 1.0, 0.0];
+    let position_to_defend =
+        predicted_goal_line_crossing(world_state, robot_to_field, field_dimensions)
+            .unwrap_or(point![-field_dimensions.length / 2.0 - 1.0, 0.0]);
     let defend_pose = block_on_line(
         ball.ball_in_field,
         position_to_defend,
@@ -214,6 +216,28 @@ fn defend_goal_pose(
     Some(robot_to_field.inverse() * defend_pose)
 }
 
+/// Where the rolling ball will cross the own goal line in field coordinates, if it could enter
+/// the goal
+fn predicted_goal_line_crossing(
+    world_state: &WorldState,
+    robot_to_field: Isometry2<f32>,
+    field_dimensions: &FieldDimensions,
+) -> Option<Point2<f32>> {
+    let trajectory = world_state
+        .ball_trajectory
+        .filter(|trajectory| trajectory.is_moving)?;
+    let goal_line_x = -field_dimensions.length / 2.0;
+    let field_to_robot = robot_to_field.inverse();
+    let crossing = trajectory.crossing(Line(
+        field_to_robot * point![goal_line_x, -1.0],
+        field_to_robot * point![goal_line_x, 1.0],
+    ))?;
+    let crossing_in_field = robot_to_field * crossing.position;
+    let half_goal_width =
+        field_dimensions.goal_inner_width / 2.0 + field_dimensions.goal_post_diameter;
+    (crossing_in_field.y.abs() - crossing.deviation <= half_goal_width).then_some(crossing_in_field)
+}
+
 fn defend_kick_off_pose(
     world_state: &WorldState,
     field_dimensions: &FieldDimensions,
@@ -4,7 +4,7 @@ use framework::AdditionalOutput;
 use nalgebra::{Isometry2, Point2};
 use spl_network_messages::Team;
 use types::{
-    configuration::{Dribbling, InWalkKickInfo, InWalkKicks},
+    configuration::{kick_info, Dribbling, InWalkKickInfo, InWalkKicks},
     rotate_towards, GameControllerState, HeadMotion, MotionCommand,
     OrientationMode::{self, AlignWithPath},
     PathObstacle, WorldState,
@@ -17,6 +17,7 @@ pub fn execute(
     world_state: &WorldState,
     walk_path_planner: &WalkPathPlanner,
     in_walk_kicks: &InWalkKicks,
+    standalone_kick: &InWalkKickInfo,
     parameters: &Dribbling,
     path_obstacles_output: &mut AdditionalOutput<Vec<PathObstacle>>,
 ) -> Option<MotionCommand> {
@@ -32,14 +33,22 @@ pub fn execute(
         .iter()
         .chain(instant_kick_decisions.iter())
         .find(|decision| {
-            is_kick_pose_reached(decision.kick_pose, &in_walk_kicks[decision.variant])
+            is_kick_pose_reached(
+                decision.kick_pose,
+                kick_info(decision.variant, in_walk_kicks, standalone_kick),
+            )
         });
     if let Some(kick) = available_kick {
-        let command = MotionCommand::InWalkKick {
-            head,
-            kick: kick.variant,
-            kicking_side: kick.kicking_side,
-            strength: kick.strength,
+        let command = match kick.variant.in_walk_kick() {
+            Some(in_walk_kick) => MotionCommand::InWalkKick {
+                head,
+                kick: in_walk_kick,
+                kicking_side: kick.kicking_side,
+                strength: kick.strength,
+            },
+            None => MotionCommand::StandaloneKick {
+                kicking_side: kick.kicking_side,
+            },
         };
         return Some(command);
     }
@@ -106,7 +115,12 @@ pub fn execute(
         rule_obstacles,
         path_obstacles_output,
     );
-    Some(walk_path_planner.walk_with_obstacle_avoiding_arms(head, orientation_mode, path))
+    Some(walk_path_planner.walk_with_obstacle_avoiding_arms(
+        head,
+        orientation_mode,
+        path,
+        Some(best_kick_decision.kicking_side),
+    ))
 }
 
 fn is_kick_pose_reached(kick_pose_to_robot: Isometry2<f32>, kick_info: &InWalkKickInfo) -> bool {
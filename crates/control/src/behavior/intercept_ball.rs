@@ -1,6 +1,6 @@
 use nalgebra::{point, Point2, UnitComplex};
 use types::{
-    configuration::InterceptBall, HeadMotion, LineSegment, MotionCommand, OrientationMode,
+    configuration::InterceptBall, HeadMotion, Line, LineSegment, MotionCommand, OrientationMode,
     PathSegment, PrimaryState, WorldState,
 };
 
@@ -8,9 +8,10 @@ pub fn execute(world_state: &WorldState, parameters: InterceptBall) -> Option<Mo
     match (
         world_state.robot.primary_state,
         world_state.ball,
+        world_state.ball_trajectory,
         world_state.robot.robot_to_field,
     ) {
-        (PrimaryState::Playing, Some(ball), Some(robot_to_field)) => {
+        (PrimaryState::Playing, Some(ball), Some(trajectory), Some(robot_to_field)) => {
             let ball_in_front_of_robot = ball.ball_in_ground.coords.norm()
                 < parameters.maximum_ball_distance
                 && ball.ball_in_ground.x > 0.0;
@@ -19,20 +20,25 @@ pub fn execute(world_state: &WorldState, parameters: InterceptBall) -> Option<Mo
             let ball_moving_towards_own_half = (robot_to_field * ball.ball_in_ground_velocity).x
                 < -parameters.minimum_ball_velocity_towards_own_half;
 
-            if !(ball_in_front_of_robot
+            if !(trajectory.is_moving
+                && ball_in_front_of_robot
                 && ball_moving_towards_robot
                 && ball_moving_towards_own_half)
             {
                 return None;
             }
 
-            let time_to_intercept_point = ball.ball_in_ground.x / ball.ball_in_ground_velocity.x;
-            let intercept_y_position =
-                ball.ball_in_ground.y - ball.ball_in_ground_velocity.y * time_to_intercept_point;
-
-            if intercept_y_position.abs() > parameters.maximum_intercept_distance {
+            // a ball which stops in front of the robot does not cross its lateral axis
+            let crossing = trajectory.crossing(Line(point![0.0, -1.0], point![0.0, 1.0]))?;
+            if crossing.position.y.abs() - crossing.deviation
+                > parameters.maximum_intercept_distance
+            {
                 return None;
             }
+            let intercept_y_position = crossing.position.y.clamp(
+                -parameters.maximum_intercept_distance,
+                parameters.maximum_intercept_distance,
+            );
 
             Some(MotionCommand::Walk {
                 head: HeadMotion::LookAt {
@@ -46,6 +52,7 @@ pub fn execute(world_state: &WorldState, parameters: InterceptBall) -> Option<Mo
                 left_arm: types::ArmMotion::Swing,
                 right_arm: types::ArmMotion::Swing,
                 orientation_mode: OrientationMode::Override(UnitComplex::default()),
+                kicking_side: None,
             })
         }
         _ => None,
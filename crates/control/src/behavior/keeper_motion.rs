@@ -0,0 +1,247 @@
+use nalgebra::point;
+use spl_network_messages::GamePhase;
+use types::{
+    configuration::KeeperMotion, FieldDimensions, GameControllerState, JumpDirection, Line,
+    MotionCommand, PenaltyShotDirection, PrimaryState, WorldState,
+};
+
+pub fn execute(
+    world_state: &WorldState,
+    field_dimensions: &FieldDimensions,
+    parameters: &KeeperMotion,
+) -> Option<MotionCommand> {
+    if world_state.robot.primary_state != PrimaryState::Playing {
+        return None;
+    }
+    let ball = world_state.ball?;
+
+    // a penalty shot is too fast to wait for a trajectory prediction
+    let is_penalty_shootout = matches!(
+        world_state.game_controller_state,
+        Some(GameControllerState {
+            game_phase: GamePhase::PenaltyShootout { .. },
+            ..
+        })
+    );
+    if is_penalty_shootout {
+        match ball.penalty_shot_direction {
+            Some(PenaltyShotDirection::Left) => {
+                return Some(MotionCommand::Jump {
+                    direction: JumpDirection::Left,
+                })
+            }
+            Some(PenaltyShotDirection::Right) => {
+                return Some(MotionCommand::Jump {
+                    direction: JumpDirection::Right,
+                })
+            }
+            Some(PenaltyShotDirection::NotMoving) | None => {}
+        }
+    }
+
+    let trajectory = world_state.ball_trajectory?;
+    if !trajectory.is_moving || ball.ball_in_ground.x < 0.0 {
+        return None;
+    }
+
+    // a ball which stops in front of the robot does not cross its lateral axis
+    let crossing = trajectory.crossing(Line(point![0.0, -1.0], point![0.0, 1.0]))?;
+    if crossing.time > parameters.maximum_time_to_impact {
+        return None;
+    }
+    // passes to the keeper and balls it walks onto do not end up in the own goal
+    let crossing_in_field = world_state.robot.robot_to_field? * crossing.position;
+    let is_in_goal_mouth = crossing_in_field.x
+        < -field_dimensions.length / 2.0 + parameters.maximum_crossing_distance_to_goal_line
+        && crossing_in_field.y.abs() < field_dimensions.goal_inner_width / 2.0;
+    if !is_in_goal_mouth {
+        return None;
+    }
+
+    // block and catch only cover the ball if it crosses within their reach despite the
+    // uncertainty, diving is worth it if the ball might be reachable at all
+    let lateral_distance = crossing.position.y.abs();
+    let direction = if crossing.position.y > 0.0 {
+        JumpDirection::Left
+    } else {
+        JumpDirection::Right
+    };
+    if lateral_distance + crossing.deviation < parameters.block_half_width {
+        Some(MotionCommand::Block)
+    } else if lateral_distance + crossing.deviation < parameters.catch_reach {
+        Some(MotionCommand::Catch { direction })
+    } else if lateral_distance - crossing.deviation < parameters.dive_reach {
+        Some(MotionCommand::Jump { direction })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use nalgebra::{vector, Isometry2, Matrix4};
+    use spl_network_messages::{GameState, Team};
+    use types::{
+        multivariate_normal_distribution::MultivariateNormalDistribution, BallState,
+        BallTrajectory, RobotState,
+    };
+
+    use super::*;
+
+    const PARAMETERS: KeeperMotion = KeeperMotion {
+        maximum_time_to_impact: Duration::from_millis(1500),
+        block_half_width: 0.15,
+        catch_reach: 0.35,
+        dive_reach: 0.8,
+        maximum_crossing_distance_to_goal_line: 1.0,
+    };
+
+    const FIELD_DIMENSIONS: FieldDimensions = FieldDimensions {
+        ball_radius: 0.05,
+        length: 9.0,
+        width: 6.0,
+        line_width: 0.05,
+        penalty_marker_size: 0.1,
+        goal_box_area_length: 0.6,
+        goal_box_area_width: 2.2,
+        penalty_area_length: 1.65,
+        penalty_area_width: 4.0,
+        penalty_marker_distance: 1.3,
+        center_circle_diameter: 1.5,
+        border_strip_width: 0.7,
+        goal_inner_width: 1.5,
+        goal_post_diameter: 0.1,
+        goal_depth: 0.5,
+    };
+
+    /// Keeper standing in front of the center of the own goal
+    fn keeper_in_goal() -> Isometry2<f32> {
+        Isometry2::new(vector![-4.2, 0.0], 0.0)
+    }
+
+    /// Ball rolling straight towards the robot's lateral axis, which it crosses at the given
+    /// lateral offset after about half a second
+    fn world_state_with_ball_crossing_at(lateral_offset: f32, lateral_variance: f32) -> WorldState {
+        let state = MultivariateNormalDistribution {
+            mean: vector![2.0, lateral_offset, -4.0, 0.0],
+            covariance: Matrix4::from_diagonal(&vector![0.0, lateral_variance, 0.0, 0.0]),
+        };
+        WorldState {
+            ball: Some(BallState {
+                ball_in_ground: point![2.0, lateral_offset],
+                ball_in_ground_velocity: vector![-4.0, 0.0],
+                ..BallState::new_at_center(Isometry2::identity())
+            }),
+            ball_trajectory: Some(BallTrajectory::new(state, 10.0, 0.1, true)),
+            robot: RobotState {
+                robot_to_field: Some(keeper_in_goal()),
+                primary_state: PrimaryState::Playing,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ball_crossing_at_the_body_is_blocked() {
+        let world_state = world_state_with_ball_crossing_at(0.05, 0.0);
+
+        assert!(matches!(
+            execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS),
+            Some(MotionCommand::Block)
+        ));
+    }
+
+    #[test]
+    fn ball_crossing_next_to_the_body_is_caught() {
+        let world_state = world_state_with_ball_crossing_at(0.25, 0.0);
+
+        assert!(matches!(
+            execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS),
+            Some(MotionCommand::Catch {
+                direction: JumpDirection::Left
+            })
+        ));
+    }
+
+    #[test]
+    fn uncertain_ball_crossing_at_the_body_is_caught() {
+        let world_state = world_state_with_ball_crossing_at(-0.1, 0.01);
+
+        assert!(matches!(
+            execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS),
+            Some(MotionCommand::Catch {
+                direction: JumpDirection::Right
+            })
+        ));
+    }
+
+    #[test]
+    fn distant_ball_crossing_is_dived_for() {
+        let world_state = world_state_with_ball_crossing_at(-0.6, 0.0);
+
+        assert!(matches!(
+            execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS),
+            Some(MotionCommand::Jump {
+                direction: JumpDirection::Right
+            })
+        ));
+    }
+
+    #[test]
+    fn unreachable_ball_crossing_is_ignored() {
+        let world_state = world_state_with_ball_crossing_at(1.2, 0.0);
+
+        assert!(execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS).is_none());
+    }
+
+    #[test]
+    fn slow_pass_outside_of_the_goal_mouth_is_ignored() {
+        let state = MultivariateNormalDistribution {
+            mean: vector![1.0, 0.1, -1.5, 0.0],
+            covariance: Matrix4::zeros(),
+        };
+        let mut world_state = world_state_with_ball_crossing_at(0.1, 0.0);
+        world_state.ball_trajectory = Some(BallTrajectory::new(state, 10.0, 0.1, true));
+        assert!(matches!(
+            execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS),
+            Some(MotionCommand::Block)
+        ));
+
+        world_state.robot.robot_to_field = Some(Isometry2::new(vector![-2.5, 0.5], 0.0));
+        assert!(execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS).is_none());
+
+        world_state.robot.robot_to_field = Some(Isometry2::new(vector![-4.2, 1.5], 0.0));
+        assert!(execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS).is_none());
+    }
+
+    #[test]
+    fn penalty_shot_direction_triggers_jump_in_shootout() {
+        let mut world_state = world_state_with_ball_crossing_at(1.2, 0.0);
+        world_state.ball_trajectory = None;
+        world_state.ball.as_mut().unwrap().penalty_shot_direction =
+            Some(PenaltyShotDirection::Left);
+        assert!(execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS).is_none());
+
+        world_state.game_controller_state = Some(GameControllerState {
+            game_state: GameState::Playing,
+            game_phase: GamePhase::PenaltyShootout {
+                kicking_team: Team::Opponent,
+            },
+            kicking_team: Team::Opponent,
+            last_game_state_change: UNIX_EPOCH,
+            penalties: Default::default(),
+            remaining_amount_of_messages: 0,
+            sub_state: None,
+        });
+
+        assert!(matches!(
+            execute(&world_state, &FIELD_DIMENSIONS, &PARAMETERS),
+            Some(MotionCommand::Jump {
+                direction: JumpDirection::Left
+            })
+        ));
+    }
+}
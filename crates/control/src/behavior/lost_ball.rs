@@ -33,5 +33,6 @@ pub fn execute(
         HeadMotion::SearchForLostBall,
         OrientationMode::Override(orientation),
         path,
+        None,
     ))
 }
@@ -5,7 +5,7 @@ mod fall_safely;
 mod head;
 mod initial;
 mod intercept_ball;
-mod jump;
+mod keeper_motion;
 mod look_around;
 mod lost_ball;
 pub mod node;
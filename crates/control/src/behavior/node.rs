@@ -6,7 +6,9 @@ use framework::{AdditionalOutput, MainOutput};
 use nalgebra::{point, Point2};
 use spl_network_messages::{GamePhase, GameState, SubState, Team};
 use types::{
-    configuration::{Behavior as BehaviorConfiguration, InWalkKicks, InterceptBall, LostBall},
+    configuration::{
+        Behavior as BehaviorConfiguration, InWalkKickInfo, InWalkKicks, InterceptBall, LostBall,
+    },
     Action, CycleTime, FieldDimensions, FilteredGameState, GameControllerState, MotionCommand,
     PathObstacle, PrimaryState, Role, Side, WorldState,
 };
@@ -16,7 +18,7 @@ use super::{
     defend::Defend,
     dribble, fall_safely,
     head::LookAction,
-    initial, intercept_ball, jump, look_around, lost_ball, penalize, prepare_jump, search,
+    initial, intercept_ball, keeper_motion, look_around, lost_ball, penalize, prepare_jump, search,
     sit_down, stand, stand_up, support, unstiff, walk_to_kick_off, walk_to_penalty_kick,
     walk_to_pose::{WalkAndStand, WalkPathPlanner},
 };
@@ -45,6 +47,7 @@ pub struct CycleContext {
 
     pub configuration: Parameter<BehaviorConfiguration, "behavior">,
     pub in_walk_kicks: Parameter<InWalkKicks, "in_walk_kicks">,
+    pub standalone_kick: Parameter<InWalkKickInfo, "standalone_kick">,
     pub field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
     pub lost_ball_parameters: Parameter<LostBall, "behavior.lost_ball">,
     pub intercept_ball_parameters: Parameter<InterceptBall, "behavior.intercept_ball">,
@@ -100,9 +103,15 @@ impl Behavior {
             Action::FallSafely,
             Action::StandUp,
             Action::Stand,
-            Action::InterceptBall,
-            Action::Calibrate,
         ];
+        if matches!(
+            world_state.robot.role,
+            Role::Keeper | Role::ReplacementKeeper
+        ) {
+            // catching the ball is faster than walking into its path
+            actions.push(Action::KeeperMotion);
+        }
+        actions.extend([Action::InterceptBall, Action::Calibrate]);
 
         if let Some(active_since) = self.active_since {
             if now.duration_since(active_since)? < context.configuration.initial_lookaround_duration
@@ -118,10 +127,7 @@ impl Behavior {
                 Some(GameControllerState {
                     game_phase: GamePhase::PenaltyShootout { .. },
                     ..
-                }) => {
-                    actions.push(Action::Jump);
-                    actions.push(Action::PrepareJump);
-                }
+                }) => actions.push(Action::PrepareJump),
                 _ => actions.push(Action::DefendGoal),
             },
             Role::Loser => actions.push(Action::SearchForLostBall),
@@ -202,10 +208,15 @@ impl Behavior {
                         world_state,
                         &walk_path_planner,
                         context.in_walk_kicks,
+                        context.standalone_kick,
                         &context.configuration.dribbling,
                         &mut context.path_obstacles,
                     ),
-                    Action::Jump => jump::execute(world_state),
+                    Action::KeeperMotion => keeper_motion::execute(
+                        world_state,
+                        context.field_dimensions,
+                        &context.configuration.keeper_motion,
+                    ),
                     Action::PrepareJump => prepare_jump::execute(world_state),
                     Action::Search => search::execute(
                         world_state,
@@ -57,9 +57,12 @@ pub fn execute(
 ) -> Option<MotionCommand> {
     let robot_to_field = world_state.robot.robot_to_field?;
     let search_role = assign_search_role(world_state);
-    let search_position = search_role
-        .map(|role| role.to_position(robot_to_field, field_dimensions))
-        .unwrap_or(point![0.0, 0.0]);
+    let search_position = match (search_role, world_state.ball) {
+        // the team ball tells where to search even if we do not see the ball ourselves
+        (Some(role), Some(ball)) if !matches!(role, SearchRole::Goal) => ball.ball_in_ground,
+        (Some(role), _) => role.to_position(robot_to_field, field_dimensions),
+        (None, _) => point![0.0, 0.0],
+    };
     let head = HeadMotion::SearchForLostBall;
     if let Some(SearchRole::Goal) = search_role {
         let goal_pose = robot_to_field.inverse() * Isometry2::from(search_position.coords);
@@ -81,7 +84,7 @@ pub fn execute(
         } else {
             OrientationMode::AlignWithPath
         };
-        Some(walk_path_planner.walk_with_obstacle_avoiding_arms(head, orientation_mode, path))
+        Some(walk_path_planner.walk_with_obstacle_avoiding_arms(head, orientation_mode, path, None))
     }
 }
 
@@ -89,11 +89,13 @@ impl<'cycle> WalkPathPlanner<'cycle> {
         head: HeadMotion,
         orientation_mode: OrientationMode,
         path: Vec<PathSegment>,
+        kicking_side: Option<Side>,
     ) -> MotionCommand {
         MotionCommand::Walk {
             head,
             orientation_mode,
             path,
+            kicking_side,
             left_arm: self.arm_motion_with_obstacles(Side::Left),
             right_arm: self.arm_motion_with_obstacles(Side::Right),
         }
@@ -185,6 +187,7 @@ impl<'cycle> WalkAndStand<'cycle> {
                 head,
                 orientation_mode,
                 path,
+                None,
             ))
         }
     }
@@ -7,9 +7,9 @@ use itertools::iproduct;
 use nalgebra::{distance, point, vector, Isometry2, Point2, UnitComplex, Vector2};
 use ordered_float::NotNan;
 use types::{
-    configuration::{FindKickTargets, InWalkKickInfo, InWalkKicks},
+    configuration::{kick_info, FindKickTargets, InWalkKickInfo, InWalkKicks},
     rotate_towards, BallState, Circle, FieldDimensions, KickDecision, KickTarget, KickVariant,
-    LineSegment, Obstacle, Side, TwoLineSegments,
+    LineSegment, Obstacle, ObstacleKind, Side, TwoLineSegments,
 };
 
 pub struct KickSelector {}
@@ -22,10 +22,12 @@ pub struct CycleContext {
     pub robot_to_field: RequiredInput<Option<Isometry2<f32>>, "robot_to_field?">,
     pub ball_state: RequiredInput<Option<BallState>, "ball_state?">,
     pub obstacles: Input<Vec<Obstacle>, "obstacles">,
+    pub network_robot_obstacles: Input<Vec<Point2<f32>>, "network_robot_obstacles">,
 
     pub field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
 
     pub in_walk_kicks: Parameter<InWalkKicks, "in_walk_kicks">,
+    pub standalone_kick: Parameter<InWalkKickInfo, "standalone_kick">,
     pub angle_distance_weight: Parameter<f32, "kick_selector.angle_distance_weight">,
     pub max_kick_around_obstacle_angle:
         Parameter<f32, "kick_selector.max_kick_around_obstacle_angle">,
@@ -37,6 +39,12 @@ pub struct CycleContext {
 
     pub default_kick_strength: Parameter<f32, "kick_selector.default_kick_strength">,
     pub corner_kick_strength: Parameter<f32, "kick_selector.corner_kick_strength">,
+    pub standalone_kick_maximum_ball_velocity:
+        Parameter<f32, "kick_selector.standalone_kick_maximum_ball_velocity">,
+    pub standalone_kick_minimum_opponent_distance:
+        Parameter<f32, "kick_selector.standalone_kick_minimum_opponent_distance">,
+    pub standalone_kick_teammate_matching_distance:
+        Parameter<f32, "kick_selector.standalone_kick_teammate_matching_distance">,
 
     pub kick_targets: AdditionalOutput<Vec<KickTarget>, "kick_targets">,
     pub instant_kick_targets: AdditionalOutput<Vec<Point2<f32>>, "instant_kick_targets">,
@@ -68,6 +76,18 @@ impl KickSelector {
         if context.in_walk_kicks.side.enabled {
             kick_variants.push(KickVariant::Side)
         }
+        if context.standalone_kick.enabled
+            && has_time_for_standalone_kick(
+                context.ball_state,
+                context.obstacles,
+                context.network_robot_obstacles,
+                *context.standalone_kick_maximum_ball_velocity,
+                *context.standalone_kick_minimum_opponent_distance,
+                *context.standalone_kick_teammate_matching_distance,
+            )
+        {
+            kick_variants.push(KickVariant::Standalone)
+        }
 
         let obstacle_circles = generate_obstacle_circles(
             context.obstacles,
@@ -78,6 +98,7 @@ impl KickSelector {
             &sides,
             &kick_variants,
             context.in_walk_kicks,
+            context.standalone_kick,
             ball_position,
             &obstacle_circles,
             context.field_dimensions,
@@ -106,6 +127,7 @@ impl KickSelector {
                 kick_decisions_from_targets(
                     &kick_targets,
                     context.in_walk_kicks,
+                    context.standalone_kick,
                     kick_variant,
                     side,
                     ball_position,
@@ -144,6 +166,28 @@ impl KickSelector {
     }
 }
 
+/// The standalone kick takes long, it is only chosen if the ball rests and no opponent is close to
+/// it, robots at the positions of teammates are no opponents
+fn has_time_for_standalone_kick(
+    ball_state: &BallState,
+    obstacles: &[Obstacle],
+    teammate_positions: &[Point2<f32>],
+    maximum_ball_velocity: f32,
+    minimum_opponent_distance: f32,
+    teammate_matching_distance: f32,
+) -> bool {
+    let is_ball_resting = ball_state.ball_in_ground_velocity.norm() < maximum_ball_velocity;
+    let is_opponent_close = obstacles.iter().any(|obstacle| {
+        let is_teammate = teammate_positions.iter().any(|teammate_position| {
+            distance(&obstacle.position, teammate_position) < teammate_matching_distance
+        });
+        matches!(obstacle.kind, ObstacleKind::Robot | ObstacleKind::Unknown)
+            && !is_teammate
+            && distance(&obstacle.position, &ball_state.ball_in_ground) < minimum_opponent_distance
+    });
+    is_ball_resting && !is_opponent_close
+}
+
 fn generate_obstacle_circles(
     obstacles: &[Obstacle],
     ball_radius_for_kick_target_selection: f32,
@@ -166,6 +210,7 @@ fn generate_decisions_for_instant_kicks(
     sides: &[Side; 2],
     kick_variants: &[KickVariant],
     in_walk_kicks: &InWalkKicks,
+    standalone_kick: &InWalkKickInfo,
     ball_position: Point2<f32>,
     obstacle_circles: &[Circle],
     field_dimensions: &FieldDimensions,
@@ -177,7 +222,7 @@ fn generate_decisions_for_instant_kicks(
     instant_kick_targets.fill_if_subscribed(Default::default);
     iproduct!(sides, kick_variants)
         .filter_map(|(&kicking_side, &variant)| {
-            let kick_info = &in_walk_kicks[variant];
+            let kick_info = kick_info(variant, in_walk_kicks, standalone_kick);
             let shot_angle = match kicking_side {
                 Side::Left => UnitComplex::new(kick_info.shot_angle),
                 Side::Right => UnitComplex::new(kick_info.shot_angle).inverse(),
@@ -363,6 +408,7 @@ fn generate_goal_line_kick_targets(
 fn kick_decisions_from_targets(
     targets_to_kick_to: &[KickTarget],
     in_walk_kicks: &InWalkKicks,
+    standalone_kick: &InWalkKickInfo,
     variant: KickVariant,
     kicking_side: Side,
     ball_position: Point2<f32>,
@@ -372,7 +418,7 @@ fn kick_decisions_from_targets(
         targets_to_kick_to
             .iter()
             .map(|&KickTarget { position, strength }| {
-                let kick_info = &in_walk_kicks[variant];
+                let kick_info = kick_info(variant, in_walk_kicks, standalone_kick);
                 let kick_pose = compute_kick_pose(ball_position, position, kick_info, kicking_side);
                 KickDecision {
                     variant,
@@ -445,3 +491,59 @@ fn is_ball_in_opponents_corners(
         distance(&global_ball, &right_opponent_corner) < parameters.distance_from_corner;
     ball_near_left_opponent_corner || ball_near_right_opponent_corner
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_opponents_prevent_standalone_kick() {
+        let ball_state = BallState {
+            ball_in_ground: point![0.5, 0.0],
+            ball_in_ground_velocity: Vector2::zeros(),
+            ..BallState::new_at_center(Isometry2::identity())
+        };
+        let teammate = Obstacle::robot(point![1.0, 0.5], 0.2, 0.3);
+        let opponent = Obstacle::robot(point![1.0, -0.5], 0.2, 0.3);
+        let goal_post = Obstacle::goal_post(point![0.8, 0.0], 0.05);
+        let teammate_positions = [point![1.1, 0.5]];
+
+        assert!(has_time_for_standalone_kick(
+            &ball_state,
+            &[teammate, goal_post],
+            &teammate_positions,
+            0.1,
+            1.5,
+            0.3,
+        ));
+        assert!(!has_time_for_standalone_kick(
+            &ball_state,
+            &[teammate, opponent],
+            &teammate_positions,
+            0.1,
+            1.5,
+            0.3,
+        ));
+        assert!(!has_time_for_standalone_kick(
+            &ball_state,
+            &[teammate],
+            &[],
+            0.1,
+            1.5,
+            0.3,
+        ));
+
+        let rolling_ball_state = BallState {
+            ball_in_ground_velocity: vector![0.5, 0.0],
+            ..ball_state
+        };
+        assert!(!has_time_for_standalone_kick(
+            &rolling_ball_state,
+            &[],
+            &[],
+            0.1,
+            1.5,
+            0.3,
+        ));
+    }
+}
@@ -1,31 +1,55 @@
-use std::{f32::consts::FRAC_PI_2, mem::take};
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    mem::take,
+    time::{Duration, SystemTime},
+};
 
 use approx::assert_relative_eq;
 use color_eyre::{eyre::WrapErr, Result};
 use context_attribute::context;
-use filtering::pose_filter::PoseFilter;
+use filtering::{particle_filter::Particle, pose_filter::PoseFilter};
 use framework::{AdditionalOutput, HistoricInput, MainOutput, PerceptionInput};
 use nalgebra::{
     distance, matrix, point, vector, Isometry2, Matrix, Matrix2, Matrix3, Point2, Rotation2,
-    Vector2, Vector3,
+    UnitComplex, Vector2, Vector3,
 };
 use ordered_float::NotNan;
-use spl_network_messages::{GamePhase, Penalty, PlayerNumber, Team};
+use rand::{rngs::StdRng, SeedableRng};
+use spl_network_messages::{GamePhase, Penalty, PlayerNumber, SubState, Team};
 use types::{
-    field_marks_from_field_dimensions,
-    localization::{ScoredPose, Update},
+    configuration::ParticleFilter as ParticleFilterConfiguration,
+    detected_goal_posts::DetectedGoalPosts,
+    field_features_from_field_dimensions, field_marks_from_field_dimensions,
+    localization::{ScoredPose, SymmetryResolution, Update},
+    messages::IncomingMessage,
     multivariate_normal_distribution::MultivariateNormalDistribution,
-    CorrespondencePoints, Direction, FieldDimensions, FieldMark, GameControllerState, InitialPose,
-    Line, Line2, LineData, Players, PrimaryState, Side,
+    BallPosition, CorrespondencePoints, CycleTime, Direction, FieldDimensions, FieldFeatures,
+    FieldMark, GameControllerState, InitialPose, IntersectionKind, Line, Line2, LineData, Players,
+    PointMark, PrimaryState, Side,
 };
 
+use self::particle_filter::{Measurements, ParticleLocalization};
+
+mod particle_filter;
+
 pub struct Localization {
     field_marks: Vec<FieldMark>,
+    field_features: Vec<PointMark>,
+    goals: Vec<[Point2<f32>; 2]>,
     last_primary_state: PrimaryState,
     hypotheses: Vec<ScoredPose>,
     hypotheses_when_entered_playing: Vec<ScoredPose>,
     is_penalized_with_motion_in_set: bool,
     was_picked_up_while_penalized_with_motion_in_set: bool,
+    teammate_balls: Players<Option<TeammateBall>>,
+    particle_localization: ParticleLocalization,
+}
+
+/// Ball position in field coordinates as most recently reported by a teammate
+#[derive(Clone, Copy, Debug)]
+struct TeammateBall {
+    position: Point2<f32>,
+    last_seen: SystemTime,
 }
 
 #[context]
@@ -66,18 +90,27 @@ pub struct CycleContext {
     pub fit_errors: AdditionalOutput<Vec<Vec<Vec<Vec<f32>>>>, "localization.fit_errors">,
     pub measured_lines_in_field:
         AdditionalOutput<Vec<Line2>, "localization.measured_lines_in_field">,
+    pub particles: AdditionalOutput<Vec<Particle<Isometry2<f32>>>, "localization.particles">,
     pub pose_hypotheses: AdditionalOutput<Vec<ScoredPose>, "localization.pose_hypotheses">,
+    pub symmetry_resolution:
+        AdditionalOutput<SymmetryResolution, "localization.symmetry_resolution">,
     pub updates: AdditionalOutput<Vec<Vec<Update>>, "localization.updates">,
 
     pub current_odometry_to_last_odometry:
         HistoricInput<Option<Isometry2<f32>>, "current_odometry_to_last_odometry?">,
 
+    pub ball_position: Input<Option<BallPosition>, "ball_position?">,
+    pub cycle_time: Input<CycleTime, "cycle_time">,
     pub game_controller_state: Input<Option<GameControllerState>, "game_controller_state?">,
     pub has_ground_contact: Input<bool, "has_ground_contact">,
     pub primary_state: Input<PrimaryState, "primary_state">,
 
     pub circle_measurement_noise: Parameter<Vector2<f32>, "localization.circle_measurement_noise">,
     pub field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    pub goal_post_measurement_noise:
+        Parameter<Vector2<f32>, "localization.goal_post_measurement_noise">,
+    pub goal_post_pair_measurement_noise:
+        Parameter<Vector3<f32>, "localization.goal_post_pair_measurement_noise">,
     pub good_matching_threshold: Parameter<f32, "localization.good_matching_threshold">,
     pub gradient_convergence_threshold:
         Parameter<f32, "localization.gradient_convergence_threshold">,
@@ -97,14 +130,45 @@ pub struct CycleContext {
         Parameter<usize, "localization.maximum_amount_of_gradient_descent_iterations">,
     pub maximum_amount_of_outer_iterations:
         Parameter<usize, "localization.maximum_amount_of_outer_iterations">,
+    pub maximum_goal_post_association_distance:
+        Parameter<f32, "localization.maximum_goal_post_association_distance">,
+    pub maximum_own_ball_age: Parameter<Duration, "localization.maximum_own_ball_age">,
+    pub maximum_team_ball_age: Parameter<Duration, "localization.maximum_team_ball_age">,
+    pub maximum_field_feature_association_angle:
+        Parameter<f32, "localization.maximum_field_feature_association_angle">,
+    pub maximum_field_feature_association_distance:
+        Parameter<f32, "localization.maximum_field_feature_association_distance">,
     pub minimum_fit_error: Parameter<f32, "localization.minimum_fit_error">,
     pub odometry_noise: Parameter<Vector3<f32>, "localization.odometry_noise">,
+    pub oriented_feature_measurement_noise:
+        Parameter<Vector3<f32>, "localization.oriented_feature_measurement_noise">,
+    pub own_half_tolerance: Parameter<f32, "localization.own_half_tolerance">,
+    pub particle_filter: Parameter<ParticleFilterConfiguration, "localization.particle_filter">,
     pub player_number: Parameter<PlayerNumber, "player_number">,
+    pub point_feature_measurement_noise:
+        Parameter<Vector2<f32>, "localization.point_feature_measurement_noise">,
+    pub quality_reference_deviation:
+        Parameter<Vector2<f32>, "localization.quality_reference_deviation">,
     pub score_per_good_match: Parameter<f32, "localization.score_per_good_match">,
+    pub team_ball_agreement_distance: Parameter<f32, "localization.team_ball_agreement_distance">,
+    pub team_ball_agreement_score: Parameter<f32, "localization.team_ball_agreement_score">,
+    pub use_field_feature_measurements:
+        Parameter<bool, "localization.use_field_feature_measurements">,
+    pub use_goal_post_measurements: Parameter<bool, "localization.use_goal_post_measurements">,
     pub use_line_measurements: Parameter<bool, "localization.use_line_measurements">,
-
+    pub use_particle_filter: Parameter<bool, "localization.use_particle_filter">,
+    pub use_symmetry_resolution: Parameter<bool, "localization.use_symmetry_resolution">,
+
+    pub detected_goal_posts_bottom:
+        PerceptionInput<Option<DetectedGoalPosts>, "VisionBottom", "detected_goal_posts?">,
+    pub detected_goal_posts_top:
+        PerceptionInput<Option<DetectedGoalPosts>, "VisionTop", "detected_goal_posts?">,
+    pub field_features_bottom:
+        PerceptionInput<Option<FieldFeatures>, "VisionBottom", "field_features?">,
+    pub field_features_top: PerceptionInput<Option<FieldFeatures>, "VisionTop", "field_features?">,
     pub line_data_bottom: PerceptionInput<Option<LineData>, "VisionBottom", "line_data?">,
     pub line_data_top: PerceptionInput<Option<LineData>, "VisionTop", "line_data?">,
+    pub network_message: PerceptionInput<IncomingMessage, "SplNetwork", "message">,
 
     pub robot_to_field: PersistentState<Isometry2<f32>, "robot_to_field">,
 }
@@ -112,7 +176,9 @@ pub struct CycleContext {
 #[context]
 #[derive(Default)]
 pub struct MainOutputs {
+    pub localization_quality: MainOutput<Option<f32>>,
     pub robot_to_field: MainOutput<Option<Isometry2<f32>>>,
+    pub robot_to_field_covariance: MainOutput<Option<Matrix3<f32>>>,
 }
 
 impl Localization {
@@ -124,11 +190,15 @@ impl Localization {
                     context.field_dimensions,
                 ))
                 .collect(),
+            field_features: field_features_from_field_dimensions(context.field_dimensions),
+            goals: goals_from_field_dimensions(context.field_dimensions),
             last_primary_state: PrimaryState::Unstiff,
             hypotheses: vec![],
             hypotheses_when_entered_playing: vec![],
             is_penalized_with_motion_in_set: false,
             was_picked_up_while_penalized_with_motion_in_set: false,
+            teammate_balls: Default::default(),
+            particle_localization: ParticleLocalization::new(StdRng::from_entropy()),
         })
     }
 
@@ -251,7 +321,8 @@ impl Localization {
         }
     }
 
-    fn update_state(&mut self, context: &mut CycleContext) -> Result<()> {
+    /// Returns the covariance and the quality of the best hypothesis
+    fn update_state(&mut self, context: &mut CycleContext) -> Result<(Matrix3<f32>, f32)> {
         let mut fit_errors_per_measurement = vec![];
 
         context.measured_lines_in_field.fill_if_subscribed(Vec::new);
@@ -265,10 +336,23 @@ impl Localization {
             .persistent
             .iter()
             .zip(context.line_data_bottom.persistent.iter());
+        let field_features = context
+            .field_features_top
+            .persistent
+            .values()
+            .zip(context.field_features_bottom.persistent.values());
+        let detected_goal_posts = context
+            .detected_goal_posts_top
+            .persistent
+            .values()
+            .zip(context.detected_goal_posts_bottom.persistent.values());
         for (
-            (line_data_top_timestamp, line_data_top),
-            (line_data_bottom_timestamp, line_data_bottom),
-        ) in line_datas
+            (
+                (line_data_top_timestamp, line_data_top),
+                (line_data_bottom_timestamp, line_data_bottom),
+            ),
+            ((field_features_top, field_features_bottom), (goal_posts_top, goal_posts_bottom)),
+        ) in line_datas.zip(field_features.zip(detected_goal_posts))
         {
             assert_eq!(line_data_top_timestamp, line_data_bottom_timestamp);
             let current_odometry_to_last_odometry = context
@@ -286,6 +370,125 @@ impl Localization {
                     .wrap_err("failed to predict pose filter")?;
                     scored_state.score *= *context.hypothesis_prediction_score_reduction_factor;
                 }
+                if *context.use_field_feature_measurements {
+                    let robot_to_field = scored_state.state.as_isometry();
+                    let field_feature_measurements = get_field_feature_measurements(
+                        robot_to_field,
+                        field_features_top
+                            .iter()
+                            .chain(field_features_bottom.iter())
+                            .filter_map(|data| *data),
+                        &self.field_features,
+                        *context.maximum_field_feature_association_distance,
+                        *context.maximum_field_feature_association_angle,
+                    );
+                    for measurement in field_feature_measurements {
+                        let feature_distance_to_robot = distance(
+                            &measurement.measured_point_in_field,
+                            &Point2::from(robot_to_field.translation.vector),
+                        );
+                        let measured_robot_to_field = measurement.robot_to_field;
+                        match measurement.point_mark {
+                            PointMark::Intersection {
+                                kind: IntersectionKind::L | IntersectionKind::T,
+                                ..
+                            } => scored_state
+                                .state
+                                .update_with_2d_translation_and_rotation(
+                                    vector![
+                                        measured_robot_to_field.translation.x,
+                                        measured_robot_to_field.translation.y,
+                                        measured_robot_to_field.rotation.angle()
+                                    ],
+                                    Matrix::from_diagonal(
+                                        context.oriented_feature_measurement_noise,
+                                    ) * feature_distance_to_robot,
+                                    |state| state,
+                                )
+                                .context("Failed to update pose filter")?,
+                            _ => scored_state
+                                .state
+                                .update_with_2d_translation(
+                                    measured_robot_to_field.translation.vector,
+                                    Matrix::from_diagonal(context.point_feature_measurement_noise)
+                                        * feature_distance_to_robot,
+                                    |state| vector![state.x, state.y],
+                                )
+                                .context("Failed to update pose filter")?,
+                        }
+                        scored_state.score += *context.score_per_good_match;
+                        context.updates.mutate_if_subscribed(|updates| {
+                            if let Some(updates) = updates {
+                                updates[hypothesis_index].push(Update {
+                                    robot_to_field: measured_robot_to_field,
+                                    line_center_point: measurement.measured_point_in_field,
+                                    fit_error: 0.0,
+                                    number_of_measurements_weight: 1.0,
+                                    line_distance_to_robot: feature_distance_to_robot,
+                                    line_length_weight: 1.0,
+                                });
+                            }
+                        });
+                    }
+                }
+                if *context.use_goal_post_measurements {
+                    let robot_to_field = scored_state.state.as_isometry();
+                    let goal_post_measurements = get_goal_post_measurements(
+                        robot_to_field,
+                        goal_posts_top
+                            .iter()
+                            .chain(goal_posts_bottom.iter())
+                            .filter_map(|data| *data),
+                        &self.goals,
+                        context.field_dimensions.goal_post_diameter / 2.0,
+                        *context.maximum_goal_post_association_distance,
+                    );
+                    for measurement in goal_post_measurements {
+                        let goal_post_distance_to_robot = distance(
+                            &measurement.measured_goal_post_in_field,
+                            &Point2::from(robot_to_field.translation.vector),
+                        );
+                        let measured_robot_to_field = measurement.robot_to_field;
+                        if measurement.corrects_rotation {
+                            scored_state
+                                .state
+                                .update_with_2d_translation_and_rotation(
+                                    vector![
+                                        measured_robot_to_field.translation.x,
+                                        measured_robot_to_field.translation.y,
+                                        measured_robot_to_field.rotation.angle()
+                                    ],
+                                    Matrix::from_diagonal(context.goal_post_pair_measurement_noise)
+                                        * goal_post_distance_to_robot,
+                                    |state| state,
+                                )
+                                .context("Failed to update pose filter")?;
+                        } else {
+                            scored_state
+                                .state
+                                .update_with_2d_translation(
+                                    measured_robot_to_field.translation.vector,
+                                    Matrix::from_diagonal(context.goal_post_measurement_noise)
+                                        * goal_post_distance_to_robot,
+                                    |state| vector![state.x, state.y],
+                                )
+                                .context("Failed to update pose filter")?;
+                        }
+                        scored_state.score += *context.score_per_good_match;
+                        context.updates.mutate_if_subscribed(|updates| {
+                            if let Some(updates) = updates {
+                                updates[hypothesis_index].push(Update {
+                                    robot_to_field: measured_robot_to_field,
+                                    line_center_point: measurement.measured_goal_post_in_field,
+                                    fit_error: 0.0,
+                                    number_of_measurements_weight: 1.0,
+                                    line_distance_to_robot: goal_post_distance_to_robot,
+                                    line_length_weight: 1.0,
+                                });
+                            }
+                        });
+                    }
+                }
                 if *context.use_line_measurements {
                     let robot_to_field = scored_state.state.as_isometry();
                     let current_measured_lines_in_field: Vec<_> = line_data_top
@@ -451,11 +654,39 @@ impl Localization {
             }
         }
 
-        let best_hypothesis = self
-            .get_best_hypothesis()
+        self.update_teammate_balls(context);
+        let symmetry_resolutions = if *context.use_symmetry_resolution {
+            self.resolve_field_symmetry(context)
+        } else {
+            vec![SymmetryResolution::Unresolved; self.hypotheses.len()]
+        };
+
+        let (best_hypothesis, symmetry_resolution) = self
+            .hypotheses
+            .iter()
+            .zip(symmetry_resolutions)
+            .max_by_key(|(scored_filter, _)| NotNan::new(scored_filter.score).unwrap())
             .expect("Expected at least one hypothesis");
         let best_score = best_hypothesis.score;
         let robot_to_field = best_hypothesis.state.as_isometry();
+        let total_score: f32 = self
+            .hypotheses
+            .iter()
+            .map(|scored_state| scored_state.score)
+            .sum();
+        let covariance = best_hypothesis.state.covariance;
+        let quality = localization_quality(
+            if total_score > 0.0 {
+                best_score / total_score
+            } else {
+                1.0
+            },
+            &covariance,
+            context.quality_reference_deviation,
+        );
+        context
+            .symmetry_resolution
+            .fill_if_subscribed(|| symmetry_resolution);
         self.hypotheses.retain(|scored_state| {
             scored_state.score >= *context.hypothesis_retain_factor * best_score
         });
@@ -469,7 +700,7 @@ impl Localization {
 
         *context.robot_to_field = robot_to_field;
 
-        Ok(())
+        Ok((covariance, quality))
     }
 
     pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
@@ -484,31 +715,257 @@ impl Localization {
             .map(|game_controller_state| game_controller_state.game_phase);
 
         self.reset_state(primary_state, game_phase, &context, &penalty);
+        if *context.use_particle_filter
+            && (primary_state != self.last_primary_state || self.particle_localization.is_empty())
+        {
+            self.particle_localization.reset(
+                &self.hypotheses,
+                context.particle_filter.number_of_particles,
+            );
+        }
         self.last_primary_state = primary_state;
 
         if self.is_penalized_with_motion_in_set && !context.has_ground_contact {
             self.was_picked_up_while_penalized_with_motion_in_set = true;
         }
 
-        let robot_to_field = match primary_state {
+        let (robot_to_field, robot_to_field_covariance, localization_quality) = match primary_state
+        {
             PrimaryState::Ready | PrimaryState::Set | PrimaryState::Playing => {
-                self.update_state(&mut context)?;
-                Some(*context.robot_to_field)
+                let (covariance, quality) = if *context.use_particle_filter {
+                    self.update_particle_filter(&mut context)
+                } else {
+                    self.update_state(&mut context)?
+                };
+                (
+                    Some(*context.robot_to_field),
+                    Some(covariance),
+                    Some(quality),
+                )
             }
-            _ => None,
+            _ => (None, None, None),
         };
         Ok(MainOutputs {
+            localization_quality: localization_quality.into(),
             robot_to_field: robot_to_field.into(),
+            robot_to_field_covariance: robot_to_field_covariance.into(),
         })
     }
 
-    fn get_best_hypothesis(&self) -> Option<&ScoredPose> {
-        self.hypotheses
+    /// Returns the covariance and the quality of the estimated pose
+    fn update_particle_filter(&mut self, context: &mut CycleContext) -> (Matrix3<f32>, f32) {
+        let line_datas = context
+            .line_data_top
+            .persistent
+            .iter()
+            .zip(context.line_data_bottom.persistent.values());
+        let field_features = context
+            .field_features_top
+            .persistent
+            .values()
+            .zip(context.field_features_bottom.persistent.values());
+        let detected_goal_posts = context
+            .detected_goal_posts_top
+            .persistent
+            .values()
+            .zip(context.detected_goal_posts_bottom.persistent.values());
+        for (
+            ((timestamp, line_data_top), line_data_bottom),
+            ((field_features_top, field_features_bottom), (goal_posts_top, goal_posts_bottom)),
+        ) in line_datas.zip(field_features.zip(detected_goal_posts))
+        {
+            if let Some(current_odometry_to_last_odometry) =
+                context.current_odometry_to_last_odometry.get(timestamp)
+            {
+                self.particle_localization
+                    .predict(*current_odometry_to_last_odometry, context.particle_filter);
+            }
+            let measurements = Measurements {
+                lines: line_data_top
+                    .iter()
+                    .chain(line_data_bottom.iter())
+                    .filter_map(|data| data.as_ref())
+                    .flat_map(|line_data| line_data.lines_in_robot.iter().copied())
+                    .collect(),
+                field_features: field_features_top
+                    .iter()
+                    .chain(field_features_bottom.iter())
+                    .filter_map(|data| *data)
+                    .collect(),
+                detected_goal_posts: goal_posts_top
+                    .iter()
+                    .chain(goal_posts_bottom.iter())
+                    .filter_map(|data| *data)
+                    .collect(),
+            };
+            self.particle_localization.update(
+                &measurements,
+                &self.field_marks,
+                &self.field_features,
+                &self.goals,
+                context,
+            );
+        }
+
+        self.update_teammate_balls(context);
+        let estimate = self
+            .particle_localization
+            .estimate(context.particle_filter.cluster_radius);
+        let (covariance, quality, symmetry_resolution) = match estimate {
+            Some((robot_to_field, covariance, cluster_weight)) => {
+                // the estimate is kept as the only hypothesis to reinitialize the particles from it
+                self.hypotheses = vec![ScoredPose::from_isometry(
+                    robot_to_field,
+                    covariance,
+                    cluster_weight,
+                )];
+                let symmetry_resolution = if *context.use_symmetry_resolution {
+                    self.resolve_field_symmetry(context)[0]
+                } else {
+                    SymmetryResolution::Unresolved
+                };
+                // sensor resetting also seeds mirrored poses, hence the particles are flipped
+                // together with the estimate
+                match symmetry_resolution {
+                    SymmetryResolution::FlippedIntoOwnHalf => {
+                        let own_half_tolerance = *context.own_half_tolerance;
+                        self.particle_localization.flip(|robot_to_field| {
+                            robot_to_field.translation.x > own_half_tolerance
+                        });
+                    }
+                    SymmetryResolution::FlippedByTeamBall => {
+                        self.particle_localization.flip(|_| true);
+                    }
+                    SymmetryResolution::Unresolved | SymmetryResolution::ConfirmedByTeamBall => {}
+                }
+                let estimate = self.hypotheses[0].state;
+                *context.robot_to_field = estimate.as_isometry();
+                (
+                    estimate.covariance,
+                    localization_quality(
+                        cluster_weight,
+                        &estimate.covariance,
+                        context.quality_reference_deviation,
+                    ),
+                    symmetry_resolution,
+                )
+            }
+            None => (
+                *context.initial_hypothesis_covariance,
+                0.0,
+                SymmetryResolution::Unresolved,
+            ),
+        };
+        context
+            .particles
+            .fill_if_subscribed(|| self.particle_localization.particles().to_vec());
+        context
+            .symmetry_resolution
+            .fill_if_subscribed(|| symmetry_resolution);
+        context
+            .pose_hypotheses
+            .fill_if_subscribed(|| self.hypotheses.clone());
+        (covariance, quality)
+    }
+
+    fn update_teammate_balls(&mut self, context: &CycleContext) {
+        let spl_messages = context
+            .network_message
+            .persistent
             .iter()
-            .max_by_key(|scored_filter| NotNan::new(scored_filter.score).unwrap())
+            .flat_map(|(&time, messages)| messages.iter().map(move |message| (time, message)))
+            .filter_map(|(time, message)| match message {
+                IncomingMessage::GameController(_) => None,
+                IncomingMessage::Spl(message) => Some((time, message)),
+            });
+        for (time, message) in spl_messages {
+            if message.player_number == *context.player_number
+                || message.fallen
+                || !message.ball_is_own_detection
+            {
+                continue;
+            }
+            if let Some(ball_position) = &message.ball_position {
+                self.teammate_balls[message.player_number] = Some(TeammateBall {
+                    position: message.robot_to_field * ball_position.relative_position,
+                    last_seen: time.checked_sub(ball_position.age).unwrap_or(time),
+                });
+            }
+        }
+    }
+
+    /// Flips hypotheses which contradict the team ball or the game state and returns how the
+    /// symmetry was resolved for each hypothesis
+    fn resolve_field_symmetry(&mut self, context: &CycleContext) -> Vec<SymmetryResolution> {
+        let now = context.cycle_time.start_time;
+        let is_recent = |last_seen: SystemTime, maximum_age: Duration| {
+            now.duration_since(last_seen).unwrap_or_default() <= maximum_age
+        };
+        let teammate_balls: Vec<_> = self
+            .teammate_balls
+            .iter()
+            .filter_map(|(_, teammate_ball)| *teammate_ball)
+            .filter(|teammate_ball| {
+                is_recent(teammate_ball.last_seen, *context.maximum_team_ball_age)
+            })
+            .map(|teammate_ball| teammate_ball.position)
+            .collect();
+        let own_ball = context
+            .ball_position
+            .filter(|ball| is_recent(ball.last_seen, *context.maximum_own_ball_age));
+        let is_in_own_half_required =
+            is_in_own_half_required(*context.primary_state, context.game_controller_state);
+
+        self.hypotheses
+            .iter_mut()
+            .map(|scored_state| {
+                let robot_to_field = scored_state.state.as_isometry();
+                if is_in_own_half_required
+                    && robot_to_field.translation.x > *context.own_half_tolerance
+                {
+                    flip_state(&mut scored_state.state);
+                    return SymmetryResolution::FlippedIntoOwnHalf;
+                }
+                let Some(own_ball) = own_ball else {
+                    return SymmetryResolution::Unresolved;
+                };
+                let resolution = resolve_symmetry_with_team_ball(
+                    robot_to_field * own_ball.position,
+                    &teammate_balls,
+                    *context.team_ball_agreement_distance,
+                );
+                match resolution {
+                    SymmetryResolution::ConfirmedByTeamBall => {
+                        scored_state.score += *context.team_ball_agreement_score;
+                    }
+                    SymmetryResolution::FlippedByTeamBall => {
+                        flip_state(&mut scored_state.state);
+                        scored_state.score += *context.team_ball_agreement_score;
+                    }
+                    SymmetryResolution::Unresolved | SymmetryResolution::FlippedIntoOwnHalf => {}
+                }
+                resolution
+            })
+            .collect()
     }
 }
 
+/// Centers of both posts of each goal
+fn goals_from_field_dimensions(field_dimensions: &FieldDimensions) -> Vec<[Point2<f32>; 2]> {
+    let goal_width = field_dimensions.goal_inner_width + field_dimensions.goal_post_diameter;
+    [-1.0, 1.0]
+        .into_iter()
+        .map(|side_x| {
+            [-1.0, 1.0].map(|side_y| {
+                point![
+                    side_x * field_dimensions.length / 2.0,
+                    side_y * goal_width / 2.0
+                ]
+            })
+        })
+        .collect()
+}
+
 fn goal_support_structure_line_marks_from_field_dimensions(
     field_dimensions: &FieldDimensions,
 ) -> Vec<FieldMark> {
@@ -593,6 +1050,273 @@ impl FieldMarkCorrespondence {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+struct FieldFeatureMeasurement {
+    measured_point_in_field: Point2<f32>,
+    point_mark: PointMark,
+    /// Pose which moves the measured feature onto the field mark, L and T intersections also
+    /// correct the orientation
+    robot_to_field: Isometry2<f32>,
+}
+
+fn get_field_feature_measurements<'a>(
+    robot_to_field: Isometry2<f32>,
+    field_features: impl Iterator<Item = &'a FieldFeatures>,
+    point_marks: &[PointMark],
+    maximum_association_distance: f32,
+    maximum_association_angle: f32,
+) -> Vec<FieldFeatureMeasurement> {
+    field_features
+        .flat_map(|field_features| {
+            field_features
+                .penalty_spots
+                .iter()
+                .map(|&penalty_spot| (penalty_spot, None))
+                .chain(field_features.intersections.iter().map(|intersection| {
+                    (
+                        intersection.point,
+                        Some((intersection.kind, intersection.direction)),
+                    )
+                }))
+        })
+        .filter_map(|(measured_point_in_robot, measured_intersection)| {
+            let measured_point_in_field = robot_to_field * measured_point_in_robot;
+            let measured_direction_in_field = robot_to_field.rotation
+                * measured_intersection
+                    .map(|(_kind, direction)| direction)
+                    .unwrap_or_else(Vector2::x);
+            let (point_mark, correspondences, rotation) = point_marks
+                .iter()
+                .filter_map(|point_mark| {
+                    let is_oriented = match (point_mark, measured_intersection) {
+                        (PointMark::PenaltySpot { .. }, None) => false,
+                        (PointMark::Intersection { kind, .. }, Some((measured_kind, _)))
+                            if *kind == measured_kind =>
+                        {
+                            measured_kind != IntersectionKind::X
+                        }
+                        _ => return None,
+                    };
+                    let correspondences = point_mark.to_point_correspondences(
+                        measured_point_in_field,
+                        measured_direction_in_field,
+                    );
+                    let rotation = if is_oriented {
+                        correspondences.rotation()
+                    } else {
+                        0.0
+                    };
+                    let points = correspondences.correspondence_points.0;
+                    if distance(&points.measured, &points.reference) > maximum_association_distance
+                        || rotation.abs() > maximum_association_angle
+                    {
+                        return None;
+                    }
+                    Some((point_mark, correspondences, rotation))
+                })
+                .min_by_key(|(_point_mark, correspondences, _rotation)| {
+                    let points = correspondences.correspondence_points.0;
+                    NotNan::new(distance(&points.measured, &points.reference)).unwrap()
+                })?;
+            let rotation = UnitComplex::new(robot_to_field.rotation.angle() + rotation);
+            let translation = correspondences.correspondence_points.0.reference.coords
+                - rotation * measured_point_in_robot.coords;
+            Some(FieldFeatureMeasurement {
+                measured_point_in_field,
+                point_mark: *point_mark,
+                robot_to_field: Isometry2::from_parts(translation.into(), rotation),
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GoalPostMeasurement {
+    measured_goal_post_in_field: Point2<f32>,
+    /// Pose which moves the measured goal post onto its reference, both posts of a goal seen at
+    /// once also correct the orientation
+    robot_to_field: Isometry2<f32>,
+    corrects_rotation: bool,
+    /// Detections merged into this measurement, both posts of a goal or one post seen by both
+    /// cameras
+    number_of_detections: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GoalPostAssociation {
+    goal_post_in_robot: Point2<f32>,
+    distance_to_reference: f32,
+    number_of_detections: usize,
+}
+
+fn get_goal_post_measurements<'a>(
+    robot_to_field: Isometry2<f32>,
+    detected_goal_posts: impl Iterator<Item = &'a DetectedGoalPosts>,
+    goals: &[[Point2<f32>; 2]],
+    goal_post_radius: f32,
+    maximum_association_distance: f32,
+) -> Vec<GoalPostMeasurement> {
+    // a post may be detected by both cameras at once, only its closest detection is kept
+    let mut associations: Vec<[Option<GoalPostAssociation>; 2]> = vec![[None, None]; goals.len()];
+    for foot_in_robot in
+        detected_goal_posts.flat_map(|detected_goal_posts| detected_goal_posts.on_ground.iter())
+    {
+        // the foot point is on the surface of the post facing the robot
+        let Some(direction) = foot_in_robot.coords.try_normalize(f32::EPSILON) else {
+            continue;
+        };
+        let goal_post_in_robot = foot_in_robot + direction * goal_post_radius;
+        let goal_post_in_field = robot_to_field * goal_post_in_robot;
+        let Some(((goal_index, post_index), distance_to_reference)) = goals
+            .iter()
+            .enumerate()
+            .flat_map(|(goal_index, posts)| {
+                posts
+                    .iter()
+                    .enumerate()
+                    .map(move |(post_index, reference)| {
+                        (
+                            (goal_index, post_index),
+                            distance(reference, &goal_post_in_field),
+                        )
+                    })
+            })
+            .min_by_key(|(_indices, distance)| NotNan::new(*distance).unwrap())
+        else {
+            continue;
+        };
+        if distance_to_reference > maximum_association_distance {
+            continue;
+        }
+        match &mut associations[goal_index][post_index] {
+            Some(association) => {
+                association.number_of_detections += 1;
+                if distance_to_reference < association.distance_to_reference {
+                    association.goal_post_in_robot = goal_post_in_robot;
+                    association.distance_to_reference = distance_to_reference;
+                }
+            }
+            association @ None => {
+                *association = Some(GoalPostAssociation {
+                    goal_post_in_robot,
+                    distance_to_reference,
+                    number_of_detections: 1,
+                })
+            }
+        }
+    }
+
+    let measurement =
+        |reference: Point2<f32>, association: GoalPostAssociation, rotation: UnitComplex<f32>| {
+            let translation = reference.coords - rotation * association.goal_post_in_robot.coords;
+            GoalPostMeasurement {
+                measured_goal_post_in_field: robot_to_field * association.goal_post_in_robot,
+                robot_to_field: Isometry2::from_parts(translation.into(), rotation),
+                corrects_rotation: false,
+                number_of_detections: association.number_of_detections,
+            }
+        };
+    goals
+        .iter()
+        .zip(associations)
+        .flat_map(|(references, associations)| match associations {
+            [Some(first), Some(second)] => {
+                // the pair is measured once, anchored at the first post
+                let measured = robot_to_field.rotation
+                    * (second.goal_post_in_robot - first.goal_post_in_robot);
+                let reference = references[1] - references[0];
+                let rotation =
+                    UnitComplex::rotation_between(&measured, &reference) * robot_to_field.rotation;
+                vec![GoalPostMeasurement {
+                    corrects_rotation: true,
+                    number_of_detections: first.number_of_detections + second.number_of_detections,
+                    ..measurement(references[0], first, rotation)
+                }]
+            }
+            associations => references
+                .iter()
+                .zip(associations)
+                .filter_map(|(reference, association)| {
+                    Some(measurement(
+                        *reference,
+                        association?,
+                        robot_to_field.rotation,
+                    ))
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Normalized confidence in a pose between 0 and 1, the score share penalizes competing
+/// hypotheses and the covariance penalizes uncertain poses
+fn localization_quality(
+    score_share: f32,
+    covariance: &Matrix3<f32>,
+    reference_deviation: &Vector2<f32>,
+) -> f32 {
+    let position_deviation = (covariance.m11 + covariance.m22).max(0.0).sqrt();
+    let orientation_deviation = covariance.m33.max(0.0).sqrt();
+    let normalized_deviation = (position_deviation / reference_deviation.x).powi(2)
+        + (orientation_deviation / reference_deviation.y).powi(2);
+    (score_share / (1.0 + normalized_deviation)).clamp(0.0, 1.0)
+}
+
+/// Compares the own ball in field coordinates with the balls reported by teammates, a majority of
+/// teammates agreeing with the mirrored ball indicates that the hypothesis is mirrored
+fn resolve_symmetry_with_team_ball(
+    own_ball_in_field: Point2<f32>,
+    teammate_balls: &[Point2<f32>],
+    agreement_distance: f32,
+) -> SymmetryResolution {
+    let mirrored_own_ball_in_field = Point2::from(-own_ball_in_field.coords);
+    let count_agreeing = |ball: Point2<f32>| {
+        teammate_balls
+            .iter()
+            .filter(|teammate_ball| distance(*teammate_ball, &ball) <= agreement_distance)
+            .count()
+    };
+    let direct_agreements = count_agreeing(own_ball_in_field);
+    let mirrored_agreements = count_agreeing(mirrored_own_ball_in_field);
+    if mirrored_agreements > direct_agreements {
+        SymmetryResolution::FlippedByTeamBall
+    } else if direct_agreements > 0 {
+        SymmetryResolution::ConfirmedByTeamBall
+    } else {
+        SymmetryResolution::Unresolved
+    }
+}
+
+/// In the set state of a kick-off every robot has to stand in its own half, penalty kicks and the
+/// penalty shootout place the kicker in the opponent half
+fn is_in_own_half_required(
+    primary_state: PrimaryState,
+    game_controller_state: Option<&GameControllerState>,
+) -> bool {
+    primary_state == PrimaryState::Set
+        && !matches!(
+            game_controller_state,
+            Some(GameControllerState {
+                game_phase: GamePhase::PenaltyShootout { .. },
+                ..
+            }) | Some(GameControllerState {
+                sub_state: Some(SubState::PenaltyKick),
+                ..
+            })
+        )
+}
+
+/// Mirrors the pose at the field center, i.e. rotates it by 180° around the origin
+fn flip_state(state: &mut MultivariateNormalDistribution<3>) {
+    let jacobian = Matrix3::from_diagonal(&vector![-1.0, -1.0, 1.0]);
+    state.mean = vector![
+        -state.mean.x,
+        -state.mean.y,
+        UnitComplex::new(state.mean.z + PI).angle()
+    ];
+    state.covariance = jacobian * state.covariance * jacobian.transpose();
+}
+
 fn predict(
     state: &mut MultivariateNormalDistribution<3>,
     current_odometry_to_last_odometry: &Isometry2<f32>,
@@ -969,9 +1693,13 @@ fn generate_penalized_poses(field_dimensions: &FieldDimensions) -> Vec<Isometry2
 
 #[cfg(test)]
 mod tests {
-    use std::f32::consts::FRAC_PI_4;
+    use std::f32::consts::{FRAC_PI_4, PI};
+
+    use std::time::UNIX_EPOCH;
 
     use nalgebra::point;
+    use spl_network_messages::GameState;
+    use types::Intersection;
 
     use super::*;
 
@@ -1652,4 +2380,254 @@ mod tests {
         let update = get_2d_translation_measurement(robot_to_field, field_mark_correspondence);
         assert_relative_eq!(update, vector![0.0, -2.0], epsilon = 0.0001);
     }
+
+    #[test]
+    fn corner_measurement_corrects_translation_and_rotation() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            width: 6.0,
+            penalty_area_length: 1.65,
+            penalty_area_width: 4.0,
+            goal_box_area_length: 0.6,
+            goal_box_area_width: 2.2,
+            penalty_marker_distance: 1.3,
+            center_circle_diameter: 1.5,
+            ..Default::default()
+        };
+        let field_features = FieldFeatures {
+            penalty_spots: vec![],
+            intersections: vec![Intersection {
+                point: point![1.0, 1.0],
+                kind: IntersectionKind::L,
+                direction: vector![-1.0, -1.0].normalize(),
+            }],
+        };
+        let hypothesis = Isometry2::new(vector![3.4, 2.1], 0.1);
+
+        let measurements = get_field_feature_measurements(
+            hypothesis,
+            [&field_features].into_iter(),
+            &field_features_from_field_dimensions(&field_dimensions),
+            0.5,
+            0.5,
+        );
+
+        assert_eq!(measurements.len(), 1);
+        assert_relative_eq!(
+            measurements[0].robot_to_field,
+            Isometry2::new(vector![3.5, 2.0], 0.0),
+            epsilon = 1e-4
+        );
+    }
+
+    #[test]
+    fn mirrored_hypothesis_does_not_associate_corner() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            width: 6.0,
+            ..Default::default()
+        };
+        let field_features = FieldFeatures {
+            penalty_spots: vec![],
+            intersections: vec![Intersection {
+                point: point![1.0, 1.0],
+                kind: IntersectionKind::L,
+                direction: vector![-1.0, -1.0].normalize(),
+            }],
+        };
+        // with the orientation flipped, the bisector of the corner points out of the field
+        let hypothesis = Isometry2::new(vector![4.5, 3.0], PI);
+        let point_marks: Vec<_> = field_features_from_field_dimensions(&field_dimensions)
+            .into_iter()
+            .filter(|point_mark| {
+                matches!(
+                    point_mark,
+                    PointMark::Intersection {
+                        point,
+                        ..
+                    } if point.coords.abs() == vector![4.5, 3.0]
+                )
+            })
+            .collect();
+
+        let measurements = get_field_feature_measurements(
+            hypothesis,
+            [&field_features].into_iter(),
+            &point_marks,
+            2.0,
+            0.5,
+        );
+
+        assert!(measurements.is_empty());
+    }
+
+    #[test]
+    fn both_goal_posts_correct_translation_and_rotation() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            goal_inner_width: 1.5,
+            goal_post_diameter: 0.1,
+            ..Default::default()
+        };
+        // robot at the center looking at the opponent goal, the feet are on the post surfaces
+        let foot_scale = 1.0 - 0.05 / vector![4.5, 0.8].norm();
+        let detected_goal_posts = DetectedGoalPosts {
+            in_image: vec![],
+            on_ground: vec![
+                point![4.5, -0.8] * foot_scale,
+                point![4.5, 0.8] * foot_scale,
+            ],
+        };
+        let hypothesis = Isometry2::new(vector![0.2, -0.1], 0.05);
+
+        let measurements = get_goal_post_measurements(
+            hypothesis,
+            [&detected_goal_posts].into_iter(),
+            &goals_from_field_dimensions(&field_dimensions),
+            0.05,
+            0.5,
+        );
+
+        assert_eq!(measurements.len(), 1);
+        assert!(measurements[0].corrects_rotation);
+        assert_relative_eq!(
+            measurements[0].robot_to_field,
+            Isometry2::identity(),
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn goal_post_seen_by_both_cameras_is_measured_once() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            goal_inner_width: 1.5,
+            goal_post_diameter: 0.1,
+            ..Default::default()
+        };
+        let foot_scale = 1.0 - 0.05 / vector![4.5, 0.8].norm();
+        let detected_goal_posts_top = DetectedGoalPosts {
+            in_image: vec![],
+            on_ground: vec![point![4.5, 0.8] * foot_scale],
+        };
+        let detected_goal_posts_bottom = DetectedGoalPosts {
+            in_image: vec![],
+            on_ground: vec![point![4.52, 0.8] * foot_scale],
+        };
+
+        let measurements = get_goal_post_measurements(
+            Isometry2::identity(),
+            [&detected_goal_posts_top, &detected_goal_posts_bottom].into_iter(),
+            &goals_from_field_dimensions(&field_dimensions),
+            0.05,
+            0.5,
+        );
+
+        assert_eq!(measurements.len(), 1);
+        assert!(!measurements[0].corrects_rotation);
+        assert_eq!(measurements[0].number_of_detections, 2);
+        assert_relative_eq!(
+            measurements[0].robot_to_field,
+            Isometry2::identity(),
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn team_ball_at_mirrored_position_flips_hypothesis() {
+        let teammate_balls = [point![1.5, 1.0], point![1.4, 1.1], point![-3.0, 0.0]];
+
+        assert_eq!(
+            resolve_symmetry_with_team_ball(point![-1.5, -1.0], &teammate_balls, 0.5),
+            SymmetryResolution::FlippedByTeamBall
+        );
+        assert_eq!(
+            resolve_symmetry_with_team_ball(point![1.5, 1.1], &teammate_balls, 0.5),
+            SymmetryResolution::ConfirmedByTeamBall
+        );
+        assert_eq!(
+            resolve_symmetry_with_team_ball(point![0.0, -2.5], &teammate_balls, 0.5),
+            SymmetryResolution::Unresolved
+        );
+    }
+
+    #[test]
+    fn penalty_kick_does_not_require_own_half() {
+        let mut game_controller_state = GameControllerState {
+            game_state: GameState::Set,
+            game_phase: GamePhase::Normal,
+            kicking_team: Team::Hulks,
+            last_game_state_change: UNIX_EPOCH,
+            penalties: Default::default(),
+            remaining_amount_of_messages: 0,
+            sub_state: None,
+        };
+        assert!(is_in_own_half_required(
+            PrimaryState::Set,
+            Some(&game_controller_state)
+        ));
+        assert!(!is_in_own_half_required(
+            PrimaryState::Playing,
+            Some(&game_controller_state)
+        ));
+
+        game_controller_state.sub_state = Some(SubState::PenaltyKick);
+        assert!(!is_in_own_half_required(
+            PrimaryState::Set,
+            Some(&game_controller_state)
+        ));
+
+        game_controller_state.sub_state = None;
+        game_controller_state.game_phase = GamePhase::PenaltyShootout {
+            kicking_team: Team::Hulks,
+        };
+        assert!(!is_in_own_half_required(
+            PrimaryState::Set,
+            Some(&game_controller_state)
+        ));
+    }
+
+    #[test]
+    fn flipped_state_is_rotated_around_field_center() {
+        let mut state = MultivariateNormalDistribution {
+            mean: vector![2.0, -1.0, FRAC_PI_4],
+            covariance: matrix![
+                0.5, 0.1, 0.2;
+                0.1, 0.4, 0.3;
+                0.2, 0.3, 0.1
+            ],
+        };
+
+        flip_state(&mut state);
+
+        assert_relative_eq!(
+            state.mean,
+            vector![-2.0, 1.0, FRAC_PI_4 - PI],
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            state.covariance,
+            matrix![
+                0.5, 0.1, -0.2;
+                0.1, 0.4, -0.3;
+                -0.2, -0.3, 0.1
+            ]
+        );
+    }
+
+    #[test]
+    fn localization_quality_decreases_with_uncertainty_and_competing_hypotheses() {
+        let reference_deviation = vector![0.5, 0.2];
+        let certain = localization_quality(1.0, &Matrix3::zeros(), &reference_deviation);
+        let uncertain = localization_quality(
+            1.0,
+            &Matrix3::from_diagonal(&vector![0.125, 0.125, 0.04]),
+            &reference_deviation,
+        );
+        let ambiguous = localization_quality(0.5, &Matrix3::zeros(), &reference_deviation);
+
+        assert_relative_eq!(certain, 1.0);
+        assert_relative_eq!(uncertain, 1.0 / 3.0, epsilon = 1e-6);
+        assert_relative_eq!(ambiguous, 0.5);
+    }
 }
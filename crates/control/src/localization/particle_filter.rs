@@ -0,0 +1,527 @@
+use std::f32::consts::PI;
+
+use filtering::particle_filter::{Particle, ParticleFilter};
+use nalgebra::{vector, Isometry2, Matrix3, Point2, UnitComplex, Vector2, Vector3};
+use rand::{rngs::StdRng, Rng};
+use rand_distr::StandardNormal;
+use types::{
+    configuration::ParticleFilter as ParticleFilterConfiguration,
+    detected_goal_posts::DetectedGoalPosts, localization::ScoredPose,
+    multivariate_normal_distribution::MultivariateNormalDistribution, FieldFeatures, FieldMark,
+    IntersectionKind, Line2, PointMark,
+};
+
+use super::{
+    get_field_feature_measurements, get_field_mark_correspondence, get_goal_post_measurements,
+    CycleContext,
+};
+
+/// Measurements of one perception cycle in robot coordinates
+pub struct Measurements<'a> {
+    pub lines: Vec<Line2>,
+    pub field_features: Vec<&'a FieldFeatures>,
+    pub detected_goal_posts: Vec<&'a DetectedGoalPosts>,
+}
+
+impl Measurements<'_> {
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+            && self.field_features.iter().all(|field_features| {
+                field_features.penalty_spots.is_empty() && field_features.intersections.is_empty()
+            })
+            && self
+                .detected_goal_posts
+                .iter()
+                .all(|detected_goal_posts| detected_goal_posts.on_ground.is_empty())
+    }
+}
+
+/// Monte Carlo localization with sensor resetting
+///
+/// Short and long term averages of the measurement log-likelihood detect when the particles do not
+/// explain the measurements anymore, e.g. after the robot was kidnapped. A share of the particles
+/// is then replaced by poses which are consistent with the seen L and T intersections.
+pub struct ParticleLocalization {
+    particle_filter: ParticleFilter<Isometry2<f32>>,
+    short_term_log_likelihood: Option<f32>,
+    long_term_log_likelihood: Option<f32>,
+    random_number_generator: StdRng,
+}
+
+impl ParticleLocalization {
+    pub fn new(random_number_generator: StdRng) -> Self {
+        Self {
+            particle_filter: ParticleFilter::default(),
+            short_term_log_likelihood: None,
+            long_term_log_likelihood: None,
+            random_number_generator,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particle_filter.is_empty()
+    }
+
+    pub fn particles(&self) -> &[Particle<Isometry2<f32>>] {
+        self.particle_filter.particles()
+    }
+
+    /// Replaces all particles by samples drawn evenly from the hypotheses
+    pub fn reset(&mut self, hypotheses: &[ScoredPose], number_of_particles: usize) {
+        let random_number_generator = &mut self.random_number_generator;
+        self.particle_filter = ParticleFilter::new(
+            hypotheses
+                .iter()
+                .cycle()
+                .take(if hypotheses.is_empty() {
+                    0
+                } else {
+                    number_of_particles
+                })
+                .map(|hypothesis| sample_pose(&hypothesis.state, random_number_generator))
+                .collect::<Vec<_>>(),
+        );
+        self.short_term_log_likelihood = None;
+        self.long_term_log_likelihood = None;
+    }
+
+    pub fn predict(
+        &mut self,
+        current_odometry_to_last_odometry: Isometry2<f32>,
+        configuration: &ParticleFilterConfiguration,
+    ) {
+        let odometry = vector![
+            current_odometry_to_last_odometry.translation.x.abs(),
+            current_odometry_to_last_odometry.translation.y.abs(),
+            current_odometry_to_last_odometry.rotation.angle().abs()
+        ];
+        let standard_deviation = configuration.odometry_noise.component_mul(&odometry)
+            + configuration.minimum_odometry_noise;
+        let random_number_generator = &mut self.random_number_generator;
+        self.particle_filter.predict(|robot_to_field| {
+            robot_to_field
+                * current_odometry_to_last_odometry
+                * sample_noise(&standard_deviation, random_number_generator)
+        });
+    }
+
+    pub fn update(
+        &mut self,
+        measurements: &Measurements,
+        field_marks: &[FieldMark],
+        field_features: &[PointMark],
+        goals: &[[Point2<f32>; 2]],
+        context: &CycleContext,
+    ) {
+        if measurements.is_empty() || self.particle_filter.is_empty() {
+            return;
+        }
+        let configuration = context.particle_filter;
+        let log_average_likelihood = self.particle_filter.update(|robot_to_field| {
+            measurement_log_likelihood(
+                *robot_to_field,
+                measurements,
+                field_marks,
+                field_features,
+                goals,
+                context,
+            )
+        });
+        if !log_average_likelihood.is_finite() {
+            return;
+        }
+        let short_term_log_likelihood = self.short_term_log_likelihood.map_or(
+            log_average_likelihood,
+            |short_term_log_likelihood| {
+                short_term_log_likelihood
+                    + configuration.short_term_likelihood_factor
+                        * (log_average_likelihood - short_term_log_likelihood)
+            },
+        );
+        let long_term_log_likelihood = self.long_term_log_likelihood.map_or(
+            log_average_likelihood,
+            |long_term_log_likelihood| {
+                long_term_log_likelihood
+                    + configuration.long_term_likelihood_factor
+                        * (log_average_likelihood - long_term_log_likelihood)
+            },
+        );
+        self.short_term_log_likelihood = Some(short_term_log_likelihood);
+        self.long_term_log_likelihood = Some(long_term_log_likelihood);
+
+        let number_of_particles = self.particle_filter.len();
+        if self.particle_filter.effective_sample_size()
+            >= configuration.minimum_effective_sample_size_ratio * number_of_particles as f32
+        {
+            return;
+        }
+        self.particle_filter
+            .resample(&mut self.random_number_generator);
+
+        let resetting_ratio = (1.0 - (short_term_log_likelihood - long_term_log_likelihood).exp())
+            .clamp(0.0, configuration.maximum_sensor_resetting_ratio);
+        let number_of_reset_particles =
+            (resetting_ratio * number_of_particles as f32).round() as usize;
+        let reset_poses =
+            poses_from_intersections(measurements.field_features.iter().copied(), field_features);
+        if number_of_reset_particles == 0 || reset_poses.is_empty() {
+            return;
+        }
+        let random_number_generator = &mut self.random_number_generator;
+        let reset_particles: Vec<_> = (0..number_of_reset_particles)
+            .map(|_| {
+                let pose = reset_poses[random_number_generator.gen_range(0..reset_poses.len())];
+                pose * sample_noise(
+                    &configuration.sensor_resetting_noise,
+                    random_number_generator,
+                )
+            })
+            .collect();
+        self.particle_filter.replace_least_likely(reset_particles);
+    }
+
+    /// Mirrors the particles matching the predicate at the field center
+    pub fn flip(&mut self, should_flip: impl Fn(&Isometry2<f32>) -> bool) {
+        self.particle_filter.predict(|robot_to_field| {
+            if should_flip(robot_to_field) {
+                Isometry2::rotation(PI) * robot_to_field
+            } else {
+                *robot_to_field
+            }
+        });
+    }
+
+    /// Weighted mean and covariance of the particles around the most likely particle together with
+    /// the share of the weight within this cluster
+    pub fn estimate(&self, cluster_radius: f32) -> Option<(Isometry2<f32>, Matrix3<f32>, f32)> {
+        let best_particle = self
+            .particle_filter
+            .particles()
+            .iter()
+            .max_by(|left, right| left.weight.total_cmp(&right.weight))?;
+        let cluster: Vec<_> = self
+            .particle_filter
+            .particles()
+            .iter()
+            .filter(|particle| {
+                (particle.state.translation.vector - best_particle.state.translation.vector).norm()
+                    <= cluster_radius
+                    && particle
+                        .state
+                        .rotation
+                        .angle_to(&best_particle.state.rotation)
+                        .abs()
+                        <= PI / 2.0
+            })
+            .collect();
+        let cluster_weight: f32 = cluster.iter().map(|particle| particle.weight).sum();
+        if cluster_weight <= 0.0 {
+            return Some((best_particle.state, Matrix3::zeros(), 0.0));
+        }
+        let translation = cluster
+            .iter()
+            .map(|particle| particle.weight * particle.state.translation.vector)
+            .sum::<Vector2<f32>>()
+            / cluster_weight;
+        let (sine, cosine) = cluster.iter().fold((0.0, 0.0), |(sine, cosine), particle| {
+            (
+                sine + particle.weight * particle.state.rotation.sin_angle(),
+                cosine + particle.weight * particle.state.rotation.cos_angle(),
+            )
+        });
+        let rotation = UnitComplex::new(sine.atan2(cosine));
+        let covariance = cluster
+            .iter()
+            .map(|particle| {
+                let deviation = vector![
+                    particle.state.translation.x - translation.x,
+                    particle.state.translation.y - translation.y,
+                    rotation.angle_to(&particle.state.rotation)
+                ];
+                particle.weight * deviation * deviation.transpose()
+            })
+            .sum::<Matrix3<f32>>()
+            / cluster_weight;
+        Some((
+            Isometry2::from_parts(translation.into(), rotation),
+            covariance,
+            cluster_weight,
+        ))
+    }
+}
+
+fn sample_noise(
+    standard_deviation: &Vector3<f32>,
+    random_number_generator: &mut impl Rng,
+) -> Isometry2<f32> {
+    let noise: Vector3<f32> =
+        Vector3::from_fn(|_, _| random_number_generator.sample(StandardNormal));
+    let noise = noise.component_mul(standard_deviation);
+    Isometry2::new(noise.xy(), noise.z)
+}
+
+fn sample_pose(
+    distribution: &MultivariateNormalDistribution<3>,
+    random_number_generator: &mut impl Rng,
+) -> Isometry2<f32> {
+    let standard_normal: Vector3<f32> =
+        Vector3::from_fn(|_, _| random_number_generator.sample(StandardNormal));
+    let sample = match distribution.covariance.cholesky() {
+        Some(cholesky) => distribution.mean + cholesky.l() * standard_normal,
+        None => distribution.mean,
+    };
+    Isometry2::new(sample.xy(), sample.z)
+}
+
+/// Sum of the log-likelihoods of all measurements, measurements without a matching field mark
+/// contribute the outlier likelihood
+fn measurement_log_likelihood(
+    robot_to_field: Isometry2<f32>,
+    measurements: &Measurements,
+    field_marks: &[FieldMark],
+    field_features: &[PointMark],
+    goals: &[[Point2<f32>; 2]],
+    context: &CycleContext,
+) -> f32 {
+    let configuration = context.particle_filter;
+    let outlier_log_likelihood = configuration.outlier_likelihood.ln();
+    let log_gaussian = |error: f32, standard_deviation: f32| {
+        (-0.5 * (error / standard_deviation).powi(2)).max(outlier_log_likelihood)
+    };
+    let pose_log_likelihood = |measured_robot_to_field: Isometry2<f32>, corrects_rotation: bool| {
+        let translation_error =
+            (measured_robot_to_field.translation.vector - robot_to_field.translation.vector).norm();
+        let rotation_log_likelihood = if corrects_rotation {
+            log_gaussian(
+                robot_to_field
+                    .rotation
+                    .angle_to(&measured_robot_to_field.rotation),
+                configuration.angle_measurement_noise,
+            )
+        } else {
+            0.0
+        };
+        log_gaussian(translation_error, configuration.point_measurement_noise)
+            + rotation_log_likelihood
+    };
+    let mut log_likelihood = 0.0;
+
+    if *context.use_line_measurements && !measurements.lines.is_empty() {
+        let measured_lines_in_field: Vec<_> = measurements
+            .lines
+            .iter()
+            .map(|&line| robot_to_field * line)
+            .collect();
+        let correspondences = get_field_mark_correspondence(
+            &measured_lines_in_field,
+            Isometry2::identity(),
+            field_marks,
+            *context.line_length_acceptance_factor,
+        );
+        let number_of_outliers = measured_lines_in_field.len() - correspondences.len();
+        log_likelihood += correspondences
+            .iter()
+            .map(|correspondence| {
+                log_gaussian(
+                    correspondence.fit_error_sum() / 2.0,
+                    configuration.line_measurement_noise,
+                )
+            })
+            .sum::<f32>()
+            + number_of_outliers as f32 * outlier_log_likelihood;
+    }
+
+    if *context.use_field_feature_measurements {
+        let number_of_features: usize = measurements
+            .field_features
+            .iter()
+            .map(|field_features| {
+                field_features.penalty_spots.len() + field_features.intersections.len()
+            })
+            .sum();
+        let field_feature_measurements = get_field_feature_measurements(
+            robot_to_field,
+            measurements.field_features.iter().copied(),
+            field_features,
+            *context.maximum_field_feature_association_distance,
+            *context.maximum_field_feature_association_angle,
+        );
+        let number_of_outliers = number_of_features - field_feature_measurements.len();
+        log_likelihood += field_feature_measurements
+            .iter()
+            .map(|measurement| {
+                let corrects_rotation = matches!(
+                    measurement.point_mark,
+                    PointMark::Intersection {
+                        kind: IntersectionKind::L | IntersectionKind::T,
+                        ..
+                    }
+                );
+                pose_log_likelihood(measurement.robot_to_field, corrects_rotation)
+            })
+            .sum::<f32>()
+            + number_of_outliers as f32 * outlier_log_likelihood;
+    }
+
+    if *context.use_goal_post_measurements {
+        let number_of_goal_posts: usize = measurements
+            .detected_goal_posts
+            .iter()
+            .map(|detected_goal_posts| detected_goal_posts.on_ground.len())
+            .sum();
+        let goal_post_measurements = get_goal_post_measurements(
+            robot_to_field,
+            measurements.detected_goal_posts.iter().copied(),
+            goals,
+            context.field_dimensions.goal_post_diameter / 2.0,
+            *context.maximum_goal_post_association_distance,
+        );
+        let number_of_associated_goal_posts: usize = goal_post_measurements
+            .iter()
+            .map(|measurement| measurement.number_of_detections)
+            .sum();
+        let number_of_outliers = number_of_goal_posts - number_of_associated_goal_posts;
+        log_likelihood += goal_post_measurements
+            .iter()
+            .map(|measurement| {
+                pose_log_likelihood(measurement.robot_to_field, measurement.corrects_rotation)
+            })
+            .sum::<f32>()
+            + number_of_outliers as f32 * outlier_log_likelihood;
+    }
+
+    log_likelihood
+}
+
+/// All poses which move a seen L or T intersection onto a field intersection of the same kind
+fn poses_from_intersections<'a>(
+    measured_field_features: impl Iterator<Item = &'a FieldFeatures>,
+    field_features: &[PointMark],
+) -> Vec<Isometry2<f32>> {
+    measured_field_features
+        .flat_map(|measured_field_features| measured_field_features.intersections.iter())
+        .filter(|intersection| intersection.kind != IntersectionKind::X)
+        .flat_map(|intersection| {
+            field_features
+                .iter()
+                .filter(|point_mark| {
+                    matches!(
+                        point_mark,
+                        PointMark::Intersection { kind, .. } if *kind == intersection.kind
+                    )
+                })
+                .map(|point_mark| {
+                    let correspondences = point_mark
+                        .to_point_correspondences(intersection.point, intersection.direction);
+                    let rotation = UnitComplex::new(correspondences.rotation());
+                    let translation = correspondences.correspondence_points.0.reference.coords
+                        - rotation * intersection.point.coords;
+                    Isometry2::from_parts(translation.into(), rotation)
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::point;
+    use rand::SeedableRng;
+    use types::{field_features_from_field_dimensions, FieldDimensions, Intersection};
+
+    use super::*;
+
+    #[test]
+    fn seen_corner_yields_poses_at_all_corners() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            width: 6.0,
+            penalty_area_length: 0.6,
+            penalty_area_width: 2.2,
+            center_circle_diameter: 1.5,
+            penalty_marker_distance: 1.3,
+            ..Default::default()
+        };
+        let field_features = field_features_from_field_dimensions(&field_dimensions);
+        // the robot in the opponent half looks at the corner at the opponent left
+        let robot_to_field = Isometry2::new(vector![2.5, 1.0], 0.3);
+        let corner_in_robot = robot_to_field.inverse() * point![4.5, 3.0];
+        let bisector_in_robot = robot_to_field.inverse().rotation * vector![-1.0, -1.0].normalize();
+        let measured_field_features = FieldFeatures {
+            penalty_spots: vec![],
+            intersections: vec![Intersection {
+                point: corner_in_robot,
+                kind: IntersectionKind::L,
+                direction: bisector_in_robot,
+            }],
+        };
+
+        let poses =
+            poses_from_intersections([&measured_field_features].into_iter(), &field_features);
+
+        let number_of_l_intersections = field_features
+            .iter()
+            .filter(|point_mark| {
+                matches!(
+                    point_mark,
+                    PointMark::Intersection {
+                        kind: IntersectionKind::L,
+                        ..
+                    }
+                )
+            })
+            .count();
+        assert_eq!(poses.len(), number_of_l_intersections);
+        assert!(poses.iter().any(|pose| {
+            (pose.translation.vector - robot_to_field.translation.vector).norm() < 1e-3
+                && pose.rotation.angle_to(&robot_to_field.rotation).abs() < 1e-3
+        }));
+    }
+
+    #[test]
+    fn estimate_ignores_distant_particles() {
+        let mut particle_localization = ParticleLocalization::new(StdRng::seed_from_u64(0));
+        particle_localization.particle_filter = ParticleFilter::new([
+            Isometry2::new(vector![1.0, 0.0], 0.1),
+            Isometry2::new(vector![1.2, 0.0], -0.1),
+            Isometry2::new(vector![-3.0, 2.0], 2.0),
+        ]);
+        particle_localization.particle_filter.update(|pose| {
+            if pose.translation.x > 0.0 {
+                2.0_f32.ln()
+            } else {
+                0.0
+            }
+        });
+
+        let (pose, _covariance, cluster_weight) = particle_localization.estimate(0.5).unwrap();
+
+        assert_relative_eq!(pose.translation.vector, vector![1.1, 0.0], epsilon = 1e-5);
+        assert_relative_eq!(pose.rotation.angle(), 0.0, epsilon = 1e-5);
+        assert_relative_eq!(cluster_weight, 0.8, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn flip_mirrors_matching_particles_at_field_center() {
+        let mut particle_localization = ParticleLocalization::new(StdRng::seed_from_u64(0));
+        particle_localization.particle_filter = ParticleFilter::new([
+            Isometry2::new(vector![2.0, 1.0], 0.3),
+            Isometry2::new(vector![-2.0, 1.0], 0.3),
+        ]);
+
+        particle_localization.flip(|robot_to_field| robot_to_field.translation.x > 0.0);
+
+        let particles = particle_localization.particles();
+        assert_relative_eq!(
+            particles[0].state,
+            Isometry2::new(vector![-2.0, -1.0], 0.3 - PI),
+            epsilon = 1e-5
+        );
+        assert_relative_eq!(
+            particles[1].state,
+            Isometry2::new(vector![-2.0, 1.0], 0.3),
+            epsilon = 1e-5
+        );
+        assert_relative_eq!(particles[0].weight, 0.5);
+    }
+}
@@ -0,0 +1,93 @@
+use color_eyre::Result;
+use context_attribute::context;
+use framework::MainOutput;
+use log::warn;
+use motionfile::{MotionFile, MotionInterpolator};
+use types::{
+    ConditionInput, CycleTime, Joints, JointsCommand, MotionSafeExits, MotionSelection, MotionType,
+    SensorData,
+};
+
+pub struct Animation {
+    interpolator: Option<MotionInterpolator<Joints<f32>>>,
+}
+
+#[context]
+pub struct CreationContext {
+    pub motion_safe_exits: PersistentState<MotionSafeExits, "motion_safe_exits">,
+}
+
+#[context]
+pub struct CycleContext {
+    pub condition_input: Input<ConditionInput, "condition_input">,
+    pub cycle_time: Input<CycleTime, "cycle_time">,
+    pub motion_selection: Input<MotionSelection, "motion_selection">,
+    pub sensor_data: Input<SensorData, "sensor_data">,
+
+    pub motion_file: Parameter<Option<MotionFile<Joints<f32>>>, "animation.motion_file?">,
+
+    pub motion_safe_exits: PersistentState<MotionSafeExits, "motion_safe_exits">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub animation_joints_command: MainOutput<JointsCommand<f32>>,
+}
+
+impl Animation {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self { interpolator: None })
+    }
+
+    pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
+        if context.motion_selection.current_motion != MotionType::AnimationStiff {
+            // the motion file is only picked up when the playback starts
+            self.interpolator = None;
+            context.motion_safe_exits[MotionType::AnimationStiff] = true;
+            let positions = context
+                .motion_file
+                .map_or(context.sensor_data.positions, |motion_file| {
+                    motion_file.initial_positions
+                });
+            return Ok(MainOutputs {
+                animation_joints_command: JointsCommand {
+                    positions,
+                    stiffnesses: Joints::fill(1.0),
+                }
+                .into(),
+            });
+        }
+
+        if self.interpolator.is_none() {
+            self.interpolator = context.motion_file.cloned().and_then(|motion_file| {
+                MotionInterpolator::try_from(motion_file)
+                    .map_err(|error| warn!("Cannot play animation: {error:#}"))
+                    .ok()
+            });
+        }
+
+        let positions = match &mut self.interpolator {
+            Some(interpolator) => {
+                interpolator.advance_by(
+                    context.cycle_time.last_cycle_duration,
+                    context.condition_input,
+                );
+                context.motion_safe_exits[MotionType::AnimationStiff] = interpolator.is_finished();
+                interpolator.value()
+            }
+            None => {
+                context.motion_safe_exits[MotionType::AnimationStiff] = true;
+                context.sensor_data.positions
+            }
+        };
+
+        Ok(MainOutputs {
+            animation_joints_command: JointsCommand {
+                positions,
+                stiffnesses: Joints::fill(1.0),
+            }
+            .into(),
+        })
+    }
+}
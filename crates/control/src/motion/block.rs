@@ -0,0 +1,59 @@
+use color_eyre::Result;
+use context_attribute::context;
+use framework::MainOutput;
+use motionfile::{MotionFile, MotionInterpolator};
+use types::{
+    ConditionInput, CycleTime, Joints, JointsCommand, MotionSafeExits, MotionSelection, MotionType,
+};
+
+pub struct Block {
+    interpolator: MotionInterpolator<Joints<f32>>,
+}
+
+#[context]
+pub struct CreationContext {
+    pub motion_safe_exits: PersistentState<MotionSafeExits, "motion_safe_exits">,
+}
+
+#[context]
+pub struct CycleContext {
+    pub motion_safe_exits: PersistentState<MotionSafeExits, "motion_safe_exits">,
+
+    pub condition_input: Input<ConditionInput, "condition_input">,
+    pub cycle_time: Input<CycleTime, "cycle_time">,
+    pub motion_selection: Input<MotionSelection, "motion_selection">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub block_joints_command: MainOutput<JointsCommand<f32>>,
+}
+
+impl Block {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            interpolator: MotionFile::from_path("etc/motions/block.json")?.try_into()?,
+        })
+    }
+
+    pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
+        let last_cycle_duration = context.cycle_time.last_cycle_duration;
+        if context.motion_selection.current_motion == MotionType::Block {
+            self.interpolator
+                .advance_by(last_cycle_duration, context.condition_input);
+        } else {
+            self.interpolator.reset();
+        }
+
+        context.motion_safe_exits[MotionType::Block] = self.interpolator.is_finished();
+
+        Ok(MainOutputs {
+            block_joints_command: JointsCommand {
+                positions: self.interpolator.value(),
+                stiffnesses: Joints::fill(0.9),
+            }
+            .into(),
+        })
+    }
+}
@@ -21,7 +21,11 @@ pub struct CreationContext {}
 
 #[context]
 pub struct CycleContext {
+    pub animation_joints_command: Input<JointsCommand<f32>, "animation_joints_command">,
     pub arms_up_squat_joints_command: Input<JointsCommand<f32>, "arms_up_squat_joints_command">,
+    pub block_joints_command: Input<JointsCommand<f32>, "block_joints_command">,
+    pub catch_left_joints_command: Input<JointsCommand<f32>, "catch_left_joints_command">,
+    pub catch_right_joints_command: Input<JointsCommand<f32>, "catch_right_joints_command">,
     pub condition_input: Input<ConditionInput, "condition_input">,
     pub energy_saving_stand: Input<BodyJointsCommand<f32>, "energy_saving_stand_command">,
     pub jump_left_joints_command: Input<JointsCommand<f32>, "jump_left_joints_command">,
@@ -32,6 +36,7 @@ pub struct CycleContext {
     pub sit_down_joints_command: Input<JointsCommand<f32>, "sit_down_joints_command">,
     pub stand_up_back_positions: Input<Joints<f32>, "stand_up_back_positions">,
     pub stand_up_front_positions: Input<Joints<f32>, "stand_up_front_positions">,
+    pub standalone_kick_joints_command: Input<JointsCommand<f32>, "standalone_kick_joints_command">,
     pub walk_joints_command: Input<BodyJointsCommand<f32>, "walk_joints_command">,
 
     pub maximum_velocity: Parameter<JointsVelocity, "maximum_joint_velocities">,
@@ -80,7 +85,12 @@ impl DispatchingInterpolator {
 
         if interpolator_reset_required {
             let target_position = match dispatching_motion {
+                MotionType::Animation => panic!("Dispatching Animation doesn't make sense"),
+                MotionType::AnimationStiff => context.animation_joints_command.positions,
                 MotionType::ArmsUpSquat => context.arms_up_squat_joints_command.positions,
+                MotionType::Block => context.block_joints_command.positions,
+                MotionType::CatchLeft => context.catch_left_joints_command.positions,
+                MotionType::CatchRight => context.catch_right_joints_command.positions,
                 MotionType::Dispatching => panic!("Dispatching cannot dispatch itself"),
                 MotionType::FallProtection => panic!("Is executed immediately"),
                 MotionType::JumpLeft => context.jump_left_joints_command.positions,
@@ -93,6 +103,7 @@ impl DispatchingInterpolator {
                 ),
                 MotionType::StandUpBack => *context.stand_up_back_positions,
                 MotionType::StandUpFront => *context.stand_up_front_positions,
+                MotionType::StandaloneKick => context.standalone_kick_joints_command.positions,
                 MotionType::Unstiff => panic!("Dispatching Unstiff doesn't make sense"),
                 MotionType::Walk => Joints::from_head_and_body(
                     HeadJoints::fill(0.0),
@@ -26,7 +26,11 @@ pub struct CycleContext {
     pub penalized_pose: Parameter<Joints<f32>, "penalized_pose">,
     pub ready_pose: Parameter<Joints<f32>, "ready_pose">,
 
+    pub animation_joints_command: Input<JointsCommand<f32>, "animation_joints_command">,
     pub arms_up_squat_joints_command: Input<JointsCommand<f32>, "arms_up_squat_joints_command">,
+    pub block_joints_command: Input<JointsCommand<f32>, "block_joints_command">,
+    pub catch_left_joints_command: Input<JointsCommand<f32>, "catch_left_joints_command">,
+    pub catch_right_joints_command: Input<JointsCommand<f32>, "catch_right_joints_command">,
     pub dispatching_command: Input<JointsCommand<f32>, "dispatching_command">,
     pub energy_saving_stand_command: Input<BodyJointsCommand<f32>, "energy_saving_stand_command">,
     pub fall_protection_command: Input<JointsCommand<f32>, "fall_protection_command">,
@@ -38,6 +42,7 @@ pub struct CycleContext {
     pub sit_down_joints_command: Input<JointsCommand<f32>, "sit_down_joints_command">,
     pub stand_up_back_positions: Input<Joints<f32>, "stand_up_back_positions">,
     pub stand_up_front_positions: Input<Joints<f32>, "stand_up_front_positions">,
+    pub standalone_kick_joints_command: Input<JointsCommand<f32>, "standalone_kick_joints_command">,
     pub walk_joints_command: Input<BodyJointsCommand<f32>, "walk_joints_command">,
     pub hardware_interface: HardwareInterface,
     pub leds: Input<Leds, "leds">,
@@ -59,16 +64,26 @@ impl JointCommandSender {
         let fall_protection_stiffnesses = context.fall_protection_command.stiffnesses;
         let head_joints_command = context.head_joints_command;
         let motion_selection = context.motion_selection;
+        let animation = context.animation_joints_command;
         let arms_up_squat = context.arms_up_squat_joints_command;
+        let block = context.block_joints_command;
+        let catch_left = context.catch_left_joints_command;
+        let catch_right = context.catch_right_joints_command;
         let jump_left = context.jump_left_joints_command;
         let jump_right = context.jump_right_joints_command;
         let sit_down = context.sit_down_joints_command;
         let stand_up_back_positions = context.stand_up_back_positions;
         let stand_up_front_positions = context.stand_up_front_positions;
+        let standalone_kick = context.standalone_kick_joints_command;
         let walk = context.walk_joints_command;
 
         let (positions, stiffnesses) = match motion_selection.current_motion {
+            MotionType::Animation => (current_positions, Joints::fill(0.0)),
+            MotionType::AnimationStiff => (animation.positions, animation.stiffnesses),
             MotionType::ArmsUpSquat => (arms_up_squat.positions, arms_up_squat.stiffnesses),
+            MotionType::Block => (block.positions, block.stiffnesses),
+            MotionType::CatchLeft => (catch_left.positions, catch_left.stiffnesses),
+            MotionType::CatchRight => (catch_right.positions, catch_right.stiffnesses),
             MotionType::Dispatching => (
                 dispatching_command.positions,
                 dispatching_command.stiffnesses,
@@ -84,6 +99,7 @@ impl JointCommandSender {
             ),
             MotionType::StandUpBack => (*stand_up_back_positions, Joints::fill(1.0)),
             MotionType::StandUpFront => (*stand_up_front_positions, Joints::fill(1.0)),
+            MotionType::StandaloneKick => (standalone_kick.positions, standalone_kick.stiffnesses),
             MotionType::Unstiff => (current_positions, Joints::fill(0.0)),
             MotionType::Walk => (
                 Joints::from_head_and_body(head_joints_command.positions, walk.positions),
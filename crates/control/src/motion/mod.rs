@@ -1,4 +1,8 @@
+pub mod animation;
 pub mod arms_up_squat;
+pub mod block;
+pub mod catch_left;
+pub mod catch_right;
 pub mod condition_input_provider;
 pub mod dispatching_interpolator;
 pub mod energy_saving_stand;
@@ -13,6 +17,7 @@ pub mod motion_selector;
 pub mod sit_down;
 pub mod stand_up_back;
 pub mod stand_up_front;
+pub mod standalone_kick;
 pub mod step_planner;
 pub mod walk_manager;
 pub mod walking_engine;
@@ -50,7 +50,10 @@ impl MotionSelector {
         );
 
         self.dispatching_motion = if self.current_motion == MotionType::Dispatching {
-            if requested_motion == MotionType::Unstiff {
+            if matches!(
+                requested_motion,
+                MotionType::Unstiff | MotionType::Animation
+            ) {
                 Some(MotionType::SitDown)
             } else {
                 Some(requested_motion)
@@ -74,7 +77,19 @@ fn motion_type_from_command(
     enable_energy_saving_stand: bool,
 ) -> MotionType {
     match command {
+        MotionCommand::Animation { stiff } => {
+            if *stiff {
+                MotionType::AnimationStiff
+            } else {
+                MotionType::Animation
+            }
+        }
         MotionCommand::ArmsUpSquat => MotionType::ArmsUpSquat,
+        MotionCommand::Block => MotionType::Block,
+        MotionCommand::Catch { direction } => match direction {
+            JumpDirection::Left => MotionType::CatchLeft,
+            JumpDirection::Right => MotionType::CatchRight,
+        },
         MotionCommand::FallProtection { .. } => MotionType::FallProtection,
         MotionCommand::Jump { direction } => match direction {
             JumpDirection::Left => MotionType::JumpLeft,
@@ -95,6 +110,7 @@ fn motion_type_from_command(
             Facing::Down => MotionType::StandUpFront,
             Facing::Up => MotionType::StandUpBack,
         },
+        MotionCommand::StandaloneKick { .. } => MotionType::StandaloneKick,
         MotionCommand::Unstiff => MotionType::Unstiff,
         MotionCommand::Walk { .. } => MotionType::Walk,
         MotionCommand::InWalkKick { .. } => MotionType::Walk,
@@ -108,13 +124,18 @@ fn transition_motion(
     has_ground_contact: bool,
 ) -> MotionType {
     match (from, motion_safe_to_exit, to, has_ground_contact) {
-        (MotionType::SitDown, true, MotionType::Unstiff, _) => MotionType::Unstiff,
-        (_, _, MotionType::Unstiff, false) => MotionType::Unstiff,
-        (MotionType::Dispatching, true, MotionType::Unstiff, true) => MotionType::SitDown,
+        (MotionType::SitDown, true, MotionType::Unstiff | MotionType::Animation, _) => to,
+        (_, _, MotionType::Unstiff | MotionType::Animation, false) => to,
+        (MotionType::Dispatching, true, MotionType::Unstiff | MotionType::Animation, true) => {
+            MotionType::SitDown
+        }
         (MotionType::StandUpFront, _, MotionType::FallProtection, _) => MotionType::StandUpFront,
         (MotionType::StandUpBack, _, MotionType::FallProtection, _) => MotionType::StandUpBack,
         (MotionType::StandUpFront, true, MotionType::StandUpFront, _) => MotionType::Dispatching,
         (MotionType::StandUpBack, true, MotionType::StandUpBack, _) => MotionType::Dispatching,
+        (MotionType::StandaloneKick, true, MotionType::StandaloneKick, _) => {
+            MotionType::Dispatching
+        }
         (_, _, MotionType::FallProtection, _) => MotionType::FallProtection,
         (MotionType::Dispatching, true, _, _) => to,
         (MotionType::Stand, _, MotionType::Walk, _) => MotionType::Walk,
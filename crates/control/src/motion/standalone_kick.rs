@@ -0,0 +1,78 @@
+use color_eyre::Result;
+use context_attribute::context;
+use framework::MainOutput;
+use motionfile::{MotionFile, MotionInterpolator};
+use types::{
+    ConditionInput, CycleTime, Joints, JointsCommand, MotionCommand, MotionSafeExits,
+    MotionSelection, MotionType, Side,
+};
+
+pub struct StandaloneKick {
+    left_interpolator: MotionInterpolator<Joints<f32>>,
+    right_interpolator: MotionInterpolator<Joints<f32>>,
+    kicking_side: Side,
+}
+
+#[context]
+pub struct CreationContext {
+    pub motion_safe_exits: PersistentState<MotionSafeExits, "motion_safe_exits">,
+}
+
+#[context]
+pub struct CycleContext {
+    pub condition_input: Input<ConditionInput, "condition_input">,
+    pub cycle_time: Input<CycleTime, "cycle_time">,
+    pub motion_command: Input<MotionCommand, "motion_command">,
+    pub motion_selection: Input<MotionSelection, "motion_selection">,
+
+    pub motion_safe_exits: PersistentState<MotionSafeExits, "motion_safe_exits">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub standalone_kick_joints_command: MainOutput<JointsCommand<f32>>,
+}
+
+impl StandaloneKick {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            left_interpolator: MotionFile::from_path("etc/motions/kick_left.json")?.try_into()?,
+            right_interpolator: MotionFile::from_path("etc/motions/kick_right.json")?.try_into()?,
+            kicking_side: Side::Left,
+        })
+    }
+
+    pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
+        if context.motion_selection.current_motion == MotionType::StandaloneKick {
+            let last_cycle_duration = context.cycle_time.last_cycle_duration;
+            self.interpolator()
+                .advance_by(last_cycle_duration, context.condition_input);
+        } else {
+            // the side may only change before the kick starts
+            if let MotionCommand::StandaloneKick { kicking_side } = context.motion_command {
+                self.kicking_side = *kicking_side;
+            }
+            self.left_interpolator.reset();
+            self.right_interpolator.reset();
+        }
+
+        let interpolator = self.interpolator();
+        context.motion_safe_exits[MotionType::StandaloneKick] = interpolator.is_finished();
+
+        Ok(MainOutputs {
+            standalone_kick_joints_command: JointsCommand {
+                positions: interpolator.value(),
+                stiffnesses: Joints::fill(1.0),
+            }
+            .into(),
+        })
+    }
+
+    fn interpolator(&mut self) -> &mut MotionInterpolator<Joints<f32>> {
+        match self.kicking_side {
+            Side::Left => &mut self.left_interpolator,
+            Side::Right => &mut self.right_interpolator,
+        }
+    }
+}
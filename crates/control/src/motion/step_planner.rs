@@ -1,8 +1,12 @@
+use std::f32::consts::TAU;
+
 use color_eyre::{eyre::eyre, Result};
 use context_attribute::context;
-use framework::MainOutput;
-use nalgebra::{Isometry2, UnitComplex};
-use types::{MotionCommand, OrientationMode, PathSegment, SensorData, Step, SupportFoot};
+use framework::{AdditionalOutput, MainOutput};
+use nalgebra::{vector, Isometry2, Point2, UnitComplex, Vector2};
+use types::{
+    MotionCommand, Orientation, OrientationMode, PathSegment, SensorData, Side, Step, SupportFoot,
+};
 
 pub struct StepPlanner {}
 
@@ -11,13 +15,18 @@ pub struct CreationContext {}
 
 #[context]
 pub struct CycleContext {
+    pub planned_steps: AdditionalOutput<Vec<Step>, "planned_steps">,
+
     pub motion_command: Input<MotionCommand, "motion_command">,
     pub sensor_data: Input<SensorData, "sensor_data">,
     pub support_foot: Input<SupportFoot, "support_foot">,
 
+    pub arrival_angle: Parameter<f32, "step_planner.arrival_angle">,
+    pub arrival_distance: Parameter<f32, "step_planner.arrival_distance">,
     pub injected_step: Parameter<Option<Step>, "step_planner.injected_step?">,
     pub max_step_size: Parameter<Step, "step_planner.max_step_size">,
     pub max_step_size_backwards: Parameter<f32, "step_planner.max_step_size_backwards">,
+    pub number_of_planned_steps: Parameter<usize, "step_planner.number_of_planned_steps">,
     pub rotation_exponent: Parameter<f32, "step_planner.rotation_exponent">,
     pub translation_exponent: Parameter<f32, "step_planner.translation_exponent">,
 
@@ -35,13 +44,14 @@ impl StepPlanner {
         Ok(Self {})
     }
 
-    pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
-        let (path, orientation_mode) = match context.motion_command {
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        let (path, orientation_mode, kicking_side) = match context.motion_command {
             MotionCommand::Walk {
                 path,
                 orientation_mode,
+                kicking_side,
                 ..
-            } => (path, orientation_mode),
+            } => (path, orientation_mode, kicking_side),
             _ => {
                 return Ok(MainOutputs {
                     step_plan: Step {
@@ -53,69 +63,262 @@ impl StepPlanner {
                 })
             }
         };
+        if path.is_empty() {
+            return Err(eyre!("empty path provided"));
+        }
 
-        let segment = path
-            .iter()
-            .scan(0.0f32, |distance, segment| {
-                let result = if *distance < context.max_step_size.forward {
-                    Some(segment)
-                } else {
-                    None
-                };
-                *distance += segment.length();
-                result
-            })
-            .last()
-            .ok_or_else(|| eyre!("empty path provided"))?;
+        if let Some(injected_step) = context.injected_step {
+            let step = compensate_with_return_offset(*injected_step, *context.walk_return_offset);
+            let step = clamp_step_to_walk_volume(
+                step,
+                context.max_step_size,
+                *context.max_step_size_backwards,
+                *context.translation_exponent,
+                *context.rotation_exponent,
+            );
+            return Ok(MainOutputs {
+                step_plan: step.into(),
+            });
+        }
+
+        let planner = PathStepPlanner {
+            path,
+            orientation_mode: *orientation_mode,
+            walk_return_offset: *context.walk_return_offset,
+            arrival_angle: *context.arrival_angle,
+            arrival_distance: *context.arrival_distance,
+            max_step_size: context.max_step_size,
+            max_step_size_backwards: *context.max_step_size_backwards,
+            translation_exponent: *context.translation_exponent,
+            rotation_exponent: *context.rotation_exponent,
+        };
+        let lookahead = context
+            .max_step_size
+            .forward
+            .max(context.max_step_size.left);
+        let next_swing_side = context
+            .support_foot
+            .support_side
+            .map(|side| side.opposite());
+        let steps = planner.plan_with_free_kicking_foot(
+            lookahead,
+            *context.number_of_planned_steps,
+            *kicking_side,
+            next_swing_side,
+        );
+
+        context.planned_steps.fill_if_subscribed(|| steps.clone());
+
+        Ok(MainOutputs {
+            step_plan: steps.first().copied().unwrap_or_else(Step::zero).into(),
+        })
+    }
+}
+
+struct PathStepPlanner<'cycle> {
+    path: &'cycle [PathSegment],
+    orientation_mode: OrientationMode,
+    walk_return_offset: Step,
+    arrival_angle: f32,
+    arrival_distance: f32,
+    max_step_size: &'cycle Step,
+    max_step_size_backwards: f32,
+    translation_exponent: f32,
+    rotation_exponent: f32,
+}
+
+impl PathStepPlanner<'_> {
+    /// Plans steps along the path by aiming each step at the point the lookahead distance further
+    /// along the path, returns whether the end of the path is reached within the planned steps
+    fn plan(&self, lookahead: f32, number_of_steps: usize) -> (Vec<Step>, bool) {
+        let path_length = self.path.iter().map(PathSegment::length).sum::<f32>();
+        let target_orientation = |tangent: Vector2<f32>| match self.orientation_mode {
+            OrientationMode::AlignWithPath => rotation_of(tangent),
+            OrientationMode::Override(orientation) => orientation,
+        };
+        let (end_position, end_tangent) = pose_at_distance(self.path, path_length);
+        let end_pose = Isometry2::from_parts(end_position.into(), target_orientation(end_tangent));
+
+        let mut pose = Isometry2::identity();
+        let mut distance_along_path = 0.0;
+        let mut steps = Vec::with_capacity(number_of_steps);
+        while steps.len() < number_of_steps {
+            if self.has_arrived(pose.inverse() * end_pose) {
+                return (steps, true);
+            }
+            let target_distance = (distance_along_path + lookahead).min(path_length);
+            let (position, tangent) = pose_at_distance(self.path, target_distance);
+            let target_pose = Isometry2::from_parts(position.into(), target_orientation(tangent));
+            let target_in_pose = pose.inverse() * target_pose;
+            let step = Step {
+                forward: target_in_pose.translation.x,
+                left: target_in_pose.translation.y,
+                turn: target_in_pose.rotation.angle(),
+            };
+            let step = if steps.is_empty() {
+                compensate_with_return_offset(step, self.walk_return_offset)
+            } else {
+                step
+            };
+            let step = clamp_step_to_walk_volume(
+                step,
+                self.max_step_size,
+                self.max_step_size_backwards,
+                self.translation_exponent,
+                self.rotation_exponent,
+            );
+            pose *= Isometry2::new(vector![step.forward, step.left], step.turn);
+            distance_along_path =
+                distance_along_path.max(distance_along_path_to(self.path, pose * Point2::origin()));
+            steps.push(step);
+        }
+        let has_arrived = self.has_arrived(pose.inverse() * end_pose);
+        (steps, has_arrived)
+    }
 
-        let target_pose = match segment {
+    /// The kicking foot has to be free to swing once the robot arrived at the kick pose, otherwise
+    /// the walking engine inserts an additional step in place. If the plan arrives with the wrong
+    /// foot, the path is replanned with shorter steps to arrive one step later.
+    fn plan_with_free_kicking_foot(
+        &self,
+        lookahead: f32,
+        number_of_steps: usize,
+        kicking_side: Option<Side>,
+        next_swing_side: Option<Side>,
+    ) -> Vec<Step> {
+        let (steps, has_arrived) = self.plan(lookahead, number_of_steps);
+        let (Some(kicking_side), Some(next_swing_side)) = (kicking_side, next_swing_side) else {
+            return steps;
+        };
+        if !has_arrived
+            || steps.is_empty()
+            || swing_side_on_arrival(steps.len(), next_swing_side) == kicking_side
+        {
+            return steps;
+        }
+        let path_length = self.path.iter().map(PathSegment::length).sum::<f32>();
+        let shorter_lookahead = path_length / (steps.len() + 1) as f32;
+        let (replanned_steps, has_replanned_arrived) =
+            self.plan(shorter_lookahead, number_of_steps);
+        // clamped steps may keep the number of steps, then the additional step is unavoidable
+        if has_replanned_arrived
+            && swing_side_on_arrival(replanned_steps.len(), next_swing_side) == kicking_side
+        {
+            replanned_steps
+        } else {
+            steps
+        }
+    }
+
+    fn has_arrived(&self, end_pose_in_robot: Isometry2<f32>) -> bool {
+        end_pose_in_robot.translation.vector.norm() < self.arrival_distance
+            && end_pose_in_robot.rotation.angle().abs() < self.arrival_angle
+    }
+}
+
+/// Side of the foot swinging after the given number of steps
+fn swing_side_on_arrival(number_of_steps: usize, next_swing_side: Side) -> Side {
+    if number_of_steps % 2 == 0 {
+        next_swing_side
+    } else {
+        next_swing_side.opposite()
+    }
+}
+
+fn rotation_of(direction: Vector2<f32>) -> UnitComplex<f32> {
+    if direction.norm_squared() < f32::EPSILON {
+        UnitComplex::identity()
+    } else {
+        UnitComplex::new(direction.y.atan2(direction.x))
+    }
+}
+
+fn arc_direction(orientation: Orientation) -> f32 {
+    match orientation {
+        Orientation::Clockwise => -1.0,
+        Orientation::Counterclockwise | Orientation::Colinear => 1.0,
+    }
+}
+
+/// Position and walking direction at the given distance along the path, the end of the path is
+/// returned for distances beyond it
+fn pose_at_distance(path: &[PathSegment], distance: f32) -> (Point2<f32>, Vector2<f32>) {
+    let mut remaining_distance = distance.max(0.0);
+    for (index, segment) in path.iter().enumerate() {
+        let length = segment.length();
+        let is_last_segment = index + 1 == path.len();
+        if remaining_distance > length && !is_last_segment {
+            remaining_distance -= length;
+            continue;
+        }
+        let remaining_distance = remaining_distance.min(length);
+        return match segment {
             PathSegment::LineSegment(line_segment) => {
-                let direction = line_segment.1;
-                let rotation = if direction.coords.norm_squared() < f32::EPSILON {
-                    UnitComplex::identity()
-                } else {
-                    UnitComplex::from_cos_sin_unchecked(direction.x, direction.y)
-                };
-                Isometry2::from_parts(line_segment.1.into(), rotation)
+                let direction = (line_segment.1 - line_segment.0)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(|| line_segment.1.coords);
+                (line_segment.0 + direction * remaining_distance, direction)
             }
             PathSegment::Arc(arc, orientation) => {
-                let direction = orientation
-                    .rotate_vector_90_degrees(arc.start - arc.circle.center)
-                    .normalize();
-                Isometry2::from_parts(
-                    (arc.start + direction * 1.0).into(),
-                    UnitComplex::from_cos_sin_unchecked(direction.x, direction.y),
+                let start = arc.start - arc.circle.center;
+                let angle = start.y.atan2(start.x)
+                    + arc_direction(*orientation) * remaining_distance / arc.circle.radius;
+                let radial = vector![angle.cos(), angle.sin()];
+                (
+                    arc.circle.center + radial * arc.circle.radius,
+                    orientation.rotate_vector_90_degrees(radial),
                 )
             }
         };
+    }
+    (Point2::origin(), Vector2::x())
+}
 
-        let mut step = Step {
-            forward: target_pose.translation.x,
-            left: target_pose.translation.y,
-            turn: match orientation_mode {
-                OrientationMode::AlignWithPath => target_pose.rotation,
-                OrientationMode::Override(orientation) => *orientation,
+/// Distance along the path to the point on the path closest to the given point
+fn distance_along_path_to(path: &[PathSegment], point: Point2<f32>) -> f32 {
+    let mut distance_to_segment_start = 0.0;
+    let mut closest = (f32::INFINITY, 0.0);
+    for segment in path {
+        let length = segment.length();
+        let (closest_point, distance_on_segment) = match segment {
+            PathSegment::LineSegment(line_segment) => {
+                let factor = if line_segment.norm_squared() < f32::EPSILON {
+                    0.0
+                } else {
+                    line_segment.projection_factor(point).clamp(0.0, 1.0)
+                };
+                (line_segment.closest_point(point), factor * length)
+            }
+            PathSegment::Arc(arc, orientation) => {
+                let start = arc.start - arc.circle.center;
+                let to_point = point - arc.circle.center;
+                let swept_angle = (arc_direction(*orientation)
+                    * (to_point.y.atan2(to_point.x) - start.y.atan2(start.x)))
+                .rem_euclid(TAU);
+                let distance_on_arc = swept_angle * arc.circle.radius;
+                // beyond the arc's end the point is closer to one of the ends
+                let distance_on_arc = if distance_on_arc <= length {
+                    distance_on_arc
+                } else if distance_on_arc - length < TAU * arc.circle.radius - distance_on_arc {
+                    length
+                } else {
+                    0.0
+                };
+                let (closest_point, _) =
+                    pose_at_distance(std::slice::from_ref(segment), distance_on_arc);
+                (closest_point, distance_on_arc)
             }
-            .angle(),
         };
-
-        if let Some(injected_step) = context.injected_step {
-            step = *injected_step;
+        let distance_to_path = (point - closest_point).norm();
+        if distance_to_path < closest.0 {
+            closest = (
+                distance_to_path,
+                distance_to_segment_start + distance_on_segment,
+            );
         }
-
-        let step = compensate_with_return_offset(step, *context.walk_return_offset);
-        let step = clamp_step_to_walk_volume(
-            step,
-            context.max_step_size,
-            *context.max_step_size_backwards,
-            *context.translation_exponent,
-            *context.rotation_exponent,
-        );
-
-        Ok(MainOutputs {
-            step_plan: step.into(),
-        })
+        distance_to_segment_start += length;
     }
+    closest.1
 }
 
 fn compensate_with_return_offset(step: Step, walk_return_offset: Step) -> Step {
@@ -206,3 +409,156 @@ fn calculate_max_step_size_in_walk_volume(
     .powf(1.0 / translation_exponent);
     (request.forward * scale, request.left * scale)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4, PI};
+
+    use approx::assert_relative_eq;
+    use nalgebra::point;
+    use types::{Arc, Circle, LineSegment};
+
+    use super::*;
+
+    const MAX_STEP_SIZE: Step = Step {
+        forward: 0.055,
+        left: 0.12,
+        turn: 1.2,
+    };
+
+    fn planner(path: &[PathSegment]) -> PathStepPlanner {
+        PathStepPlanner {
+            path,
+            orientation_mode: OrientationMode::AlignWithPath,
+            walk_return_offset: Step::zero(),
+            arrival_angle: 0.05,
+            arrival_distance: 0.01,
+            max_step_size: &MAX_STEP_SIZE,
+            max_step_size_backwards: 0.03,
+            translation_exponent: 1.5,
+            rotation_exponent: 2.0,
+        }
+    }
+
+    fn straight_path(length: f32) -> Vec<PathSegment> {
+        vec![PathSegment::LineSegment(LineSegment(
+            point![0.0, 0.0],
+            point![length, 0.0],
+        ))]
+    }
+
+    fn corner_path() -> Vec<PathSegment> {
+        vec![
+            PathSegment::LineSegment(LineSegment(point![0.0, 0.0], point![1.0, 0.0])),
+            PathSegment::LineSegment(LineSegment(point![1.0, 0.0], point![1.0, 1.0])),
+        ]
+    }
+
+    /// Quarter circle counterclockwise from the origin to (1, 1)
+    fn arc_path() -> Vec<PathSegment> {
+        vec![PathSegment::Arc(
+            Arc {
+                circle: Circle {
+                    center: point![0.0, 1.0],
+                    radius: 1.0,
+                },
+                start: point![0.0, 0.0],
+                end: point![1.0, 1.0],
+            },
+            Orientation::Counterclockwise,
+        )]
+    }
+
+    #[test]
+    fn pose_at_distance_follows_line_segments() {
+        let path = corner_path();
+
+        let (position, direction) = pose_at_distance(&path, 0.5);
+        assert_relative_eq!(position, point![0.5, 0.0]);
+        assert_relative_eq!(direction, Vector2::x());
+
+        let (position, direction) = pose_at_distance(&path, 1.5);
+        assert_relative_eq!(position, point![1.0, 0.5]);
+        assert_relative_eq!(direction, Vector2::y());
+    }
+
+    #[test]
+    fn pose_at_distance_follows_arcs() {
+        let (position, direction) = pose_at_distance(&arc_path(), FRAC_PI_4);
+
+        assert_relative_eq!(
+            position,
+            point![FRAC_1_SQRT_2, 1.0 - FRAC_1_SQRT_2],
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            direction,
+            vector![FRAC_1_SQRT_2, FRAC_1_SQRT_2],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn pose_at_distance_beyond_path_is_path_end() {
+        let (position, direction) = pose_at_distance(&corner_path(), 3.0);
+        assert_relative_eq!(position, point![1.0, 1.0]);
+        assert_relative_eq!(direction, Vector2::y());
+
+        let (position, direction) = pose_at_distance(&arc_path(), PI);
+        assert_relative_eq!(position, point![1.0, 1.0], epsilon = 1e-6);
+        assert_relative_eq!(direction, Vector2::y(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn distance_along_path_is_measured_to_closest_segment() {
+        let path = corner_path();
+
+        assert_relative_eq!(distance_along_path_to(&path, point![0.5, 0.3]), 0.5);
+        assert_relative_eq!(distance_along_path_to(&path, point![1.2, 0.5]), 1.5);
+        assert_relative_eq!(distance_along_path_to(&path, point![1.0, 2.0]), 2.0);
+    }
+
+    #[test]
+    fn distance_along_arc_is_clamped_to_closer_end() {
+        let path = arc_path();
+
+        assert_relative_eq!(
+            distance_along_path_to(&path, point![0.5, 0.5]),
+            FRAC_PI_4,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            distance_along_path_to(&path, point![FRAC_1_SQRT_2, 1.0 + FRAC_1_SQRT_2]),
+            FRAC_PI_2,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(distance_along_path_to(&path, point![-1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn kicking_foot_is_free_on_arrival() {
+        let path = straight_path(0.2);
+        let planner = planner(&path);
+
+        let steps =
+            planner.plan_with_free_kicking_foot(0.12, 6, Some(Side::Left), Some(Side::Left));
+        assert_eq!(steps.len(), 4);
+
+        let steps =
+            planner.plan_with_free_kicking_foot(0.12, 6, Some(Side::Right), Some(Side::Left));
+        assert_eq!(steps.len(), 5);
+        assert_eq!(swing_side_on_arrival(steps.len(), Side::Left), Side::Right);
+    }
+
+    #[test]
+    fn replan_is_discarded_if_it_does_not_arrive() {
+        let path = straight_path(0.2);
+        let planner = planner(&path);
+
+        let steps =
+            planner.plan_with_free_kicking_foot(0.12, 4, Some(Side::Right), Some(Side::Left));
+
+        assert_eq!(steps.len(), 4);
+        assert_relative_eq!(steps[0].forward, 0.055, epsilon = 1e-6);
+    }
+}
@@ -9,14 +9,17 @@ use nalgebra::{Isometry3, Point3, Vector2, Vector3};
 use serde::{Deserialize, Serialize};
 use types::{
     configuration::{KickSteps, WalkingEngine as WalkingEngineConfiguration},
-    ArmJoints, BodyJoints, BodyJointsCommand, CycleTime, InertialMeasurementUnitData, Joints,
-    KickVariant, LegJoints, MotionCommand, MotionSafeExits, MotionType, RobotKinematics,
-    SensorData, Side, Step, StepAdjustment, WalkCommand,
+    ArmJoints, BodyJoints, BodyJointsCommand, CapturePointAdjustment, CycleTime, FootTargets,
+    InertialMeasurementUnitData, Joints, LegJoints, MotionCommand, MotionSafeExits, MotionType,
+    RobotKinematics, SensorData, Side, SolePressure, Step, StepAdjustment, WalkCommand,
 };
 
 use self::{
     arms::SwingingArm,
-    balancing::{step_adjustment, support_leg_gyro_balancing, swing_leg_foot_leveling},
+    balancing::{
+        capture_point, capture_point_error, capture_point_step_adjustment, step_adjustment,
+        support_leg_gyro_balancing, swing_leg_foot_leveling,
+    },
     engine::{calculate_foot_to_robot, parabolic_return, parabolic_step},
     foot_offsets::FootOffsets,
     kicking::apply_joint_overrides,
@@ -82,6 +85,10 @@ pub struct WalkingEngine {
     filtered_imu_pitch: LowPassFilter<f32>,
     /// Low pass filter the robot tilt for step adjustments
     filtered_robot_tilt_shift: LowPassFilter<f32>,
+    /// Low pass filter the center of mass velocity for the capture point
+    filtered_center_of_mass_velocity: LowPassFilter<Vector2<f32>>,
+    /// support side and center of mass over the support sole of the last cycle
+    last_center_of_mass: Option<(Side, Vector2<f32>)>,
     /// Foot offsets for the left foot the walking engine interpolation generated for the last cycle
     last_left_walk_request: FootOffsets,
     /// Foot offsets for the right foot the walking engine interpolation generated for the last cycle
@@ -116,6 +123,9 @@ pub struct CreationContext {
 #[derive(Debug)]
 pub struct CycleContext {
     pub step_adjustment: AdditionalOutput<StepAdjustment, "step_adjustment">,
+    pub capture_point_adjustment:
+        AdditionalOutput<CapturePointAdjustment, "capture_point_adjustment">,
+    pub foot_targets: AdditionalOutput<FootTargets, "walking_engine.foot_targets">,
     pub planned_step_duration: AdditionalOutput<Duration, "walking_engine.planned_step_duration">,
     pub t: AdditionalOutput<Duration, "walking_engine.t">,
     pub t_on_last_phase_end: AdditionalOutput<Duration, "walking_engine.t_on_last_phase_end">,
@@ -128,10 +138,12 @@ pub struct CycleContext {
     pub motion_safe_exits: PersistentState<MotionSafeExits, "motion_safe_exits">,
     pub walk_return_offset: PersistentState<Step, "walk_return_offset">,
 
+    pub center_of_mass: Input<Point3<f32>, "center_of_mass">,
     pub motion_command: Input<MotionCommand, "motion_command">,
     pub robot_kinematics: Input<RobotKinematics, "robot_kinematics">,
     pub sensor_data: Input<SensorData, "sensor_data">,
     pub cycle_time: Input<CycleTime, "cycle_time">,
+    pub sole_pressure: Input<SolePressure, "sole_pressure">,
     pub has_ground_contact: Input<bool, "has_ground_contact">,
     pub walk_command: Input<WalkCommand, "walk_command">,
 }
@@ -157,6 +169,13 @@ impl WalkingEngine {
                 0.0,
                 context.config.tilt_shift_low_pass_factor,
             ),
+            filtered_center_of_mass_velocity: LowPassFilter::with_smoothing_factor(
+                Vector2::default(),
+                context
+                    .config
+                    .capture_point_balancing
+                    .velocity_low_pass_factor,
+            ),
             left_arm: SwingingArm::new(Side::Left),
             right_arm: SwingingArm::new(Side::Right),
             ..Default::default()
@@ -178,6 +197,13 @@ impl WalkingEngine {
             context.robot_kinematics,
             &context.sensor_data.inertial_measurement_unit,
         );
+        let mut capture_point_adjustment = self.estimate_capture_point(
+            *context.center_of_mass,
+            context.robot_kinematics,
+            &context.sensor_data.inertial_measurement_unit,
+            context.sole_pressure,
+            last_cycle_duration,
+        );
 
         let is_step_started_this_cycle = self.t.is_zero();
         if *context.has_ground_contact {
@@ -200,6 +226,7 @@ impl WalkingEngine {
                     context.cycle_time.last_cycle_duration,
                     context.config,
                     &mut context.step_adjustment,
+                    &mut capture_point_adjustment,
                 );
             }
             WalkState::Kicking(..) => self.kick_cycle(last_cycle_duration),
@@ -248,10 +275,17 @@ impl WalkingEngine {
                 .right_arm
                 .torso_tilt_compensation(&context.config.swinging_arms)?;
 
-        let (mut left_leg, mut right_leg) = self.calculate_leg_joints(
+        let foot_targets = self.calculate_foot_targets(
             context.config.torso_shift_offset,
             context.config.walk_hip_height,
         );
+        let (is_reachable, mut left_leg, mut right_leg) = kinematics::leg_angles(
+            foot_targets.left_foot_to_robot,
+            foot_targets.right_foot_to_robot,
+        );
+        if !is_reachable {
+            warn!("Not reachable!");
+        }
         left_leg.hip_pitch += arm_compensation - context.config.torso_tilt_offset;
         right_leg.hip_pitch += arm_compensation - context.config.torso_tilt_offset;
 
@@ -260,11 +294,7 @@ impl WalkingEngine {
                 Side::Left => &mut left_leg,
                 Side::Right => &mut right_leg,
             };
-            let kick_steps = match kick_variant {
-                KickVariant::Forward => &context.kick_steps.forward,
-                KickVariant::Turn => &context.kick_steps.turn,
-                KickVariant::Side => &context.kick_steps.side,
-            };
+            let kick_steps = &context.kick_steps[kick_variant];
             let kick_step = &kick_steps[kick_step_i];
             apply_joint_overrides(kick_step, swing_leg, self.t, strength);
         }
@@ -305,10 +335,14 @@ impl WalkingEngine {
             context.config.max_leg_adjustment_velocity,
         );
 
+        context.foot_targets.fill_if_subscribed(|| foot_targets);
         context
             .planned_step_duration
             .fill_if_subscribed(|| self.planned_step_duration);
         context.t.fill_if_subscribed(|| self.t);
+        context
+            .capture_point_adjustment
+            .fill_if_subscribed(|| capture_point_adjustment);
         context
             .t_on_last_phase_end
             .fill_if_subscribed(|| self.t_on_last_phase_end);
@@ -377,6 +411,47 @@ impl WalkingEngine {
             .update(measured_robot_tilt_shift);
     }
 
+    fn estimate_capture_point(
+        &mut self,
+        center_of_mass: Point3<f32>,
+        robot_kinematics: &RobotKinematics,
+        imu: &InertialMeasurementUnitData,
+        sole_pressure: &SolePressure,
+        cycle_duration: Duration,
+    ) -> CapturePointAdjustment {
+        let support_side = if sole_pressure.left > sole_pressure.right {
+            Side::Left
+        } else {
+            Side::Right
+        };
+        let support_sole = match support_side {
+            Side::Left => robot_kinematics.left_sole_to_robot,
+            Side::Right => robot_kinematics.right_sole_to_robot,
+        } * Point3::origin();
+        let robot_rotation = Isometry3::rotation(Vector3::y() * imu.roll_pitch.y)
+            * Isometry3::rotation(Vector3::x() * imu.roll_pitch.x);
+        let center_of_mass = robot_rotation * (center_of_mass - support_sole);
+
+        if let Some((last_support_side, last_center_of_mass)) = self.last_center_of_mass {
+            // the reference sole changes with the support side, which is no motion of the mass
+            if last_support_side == support_side && !cycle_duration.is_zero() {
+                self.filtered_center_of_mass_velocity.update(
+                    (center_of_mass.xy() - last_center_of_mass) / cycle_duration.as_secs_f32(),
+                );
+            }
+        }
+        self.last_center_of_mass = Some((support_side, center_of_mass.xy()));
+
+        let center_of_mass_velocity = self.filtered_center_of_mass_velocity.state();
+        CapturePointAdjustment {
+            center_of_mass: center_of_mass.xy(),
+            center_of_mass_velocity,
+            capture_point: capture_point(center_of_mass, center_of_mass_velocity),
+            step_time_scale: 1.0,
+            ..Default::default()
+        }
+    }
+
     fn initialize_step_states_from_request(
         &mut self,
         walk_command: WalkCommand,
@@ -463,11 +538,7 @@ impl WalkingEngine {
                 self.max_swing_foot_lift = config.base_foot_lift;
             }
             WalkState::Kicking(kick_variant, kick_side, kick_step_i, _) => {
-                let kick_steps = match kick_variant {
-                    KickVariant::Forward => &kick_steps.forward,
-                    KickVariant::Turn => &kick_steps.turn,
-                    KickVariant::Side => &kick_steps.side,
-                };
+                let kick_steps = &kick_steps[kick_variant];
                 let base_step = kick_steps[kick_step_i].base_step;
                 self.current_step = match kick_side {
                     Side::Left => base_step,
@@ -499,6 +570,9 @@ impl WalkingEngine {
         self.filtered_gyro.reset(Vector2::default());
         self.filtered_imu_pitch.reset(0.0);
         self.filtered_robot_tilt_shift.reset(0.0);
+        self.filtered_center_of_mass_velocity
+            .reset(Vector2::default());
+        self.last_center_of_mass = None;
         self.last_left_walk_request = FootOffsets::zero();
         self.last_right_walk_request = FootOffsets::zero();
         self.last_left_leg_adjustment = LegJoints::default();
@@ -599,8 +673,22 @@ impl WalkingEngine {
         cycle_duration: Duration,
         config: &WalkingEngineConfiguration,
         step_adjustment_output: &mut AdditionalOutput<StepAdjustment>,
+        capture_point_adjustment: &mut CapturePointAdjustment,
     ) {
-        self.t += cycle_duration;
+        let balancing = &config.capture_point_balancing;
+        if balancing.enabled {
+            capture_point_adjustment.capture_point_error = capture_point_error(
+                capture_point_adjustment.capture_point,
+                self.swing_side,
+                balancing,
+            );
+            // finish the step earlier to catch the robot before the capture point leaves reach
+            capture_point_adjustment.step_time_scale = 1.0
+                + (capture_point_adjustment.capture_point_error.norm()
+                    * balancing.step_acceleration_factor)
+                    .min(balancing.max_step_acceleration);
+        }
+        self.t += cycle_duration.mul_f32(capture_point_adjustment.step_time_scale);
         let (
             next_left_walk_request,
             next_right_walk_request,
@@ -608,20 +696,37 @@ impl WalkingEngine {
             next_left_foot_lift,
             next_right_foot_lift,
         ) = self.next_foot_offsets(self.current_step);
-        let (adjusted_left_foot, adjusted_right_foot) = step_adjustment(
-            self.swing_side,
-            self.filtered_robot_tilt_shift.state(),
-            self.left_foot,
-            self.right_foot,
-            next_left_walk_request,
-            next_right_walk_request,
-            self.last_left_walk_request,
-            self.last_right_walk_request,
-            config.forward_foot_support_offset,
-            config.backward_foot_support_offset,
-            config.max_step_adjustment,
-            step_adjustment_output,
-        );
+        let (adjusted_left_foot, adjusted_right_foot) = if balancing.enabled {
+            let (adjusted_left_foot, adjusted_right_foot, limited_adjustment) =
+                capture_point_step_adjustment(
+                    self.swing_side,
+                    capture_point_adjustment.capture_point_error,
+                    self.left_foot,
+                    self.right_foot,
+                    next_left_walk_request,
+                    next_right_walk_request,
+                    self.last_left_walk_request,
+                    self.last_right_walk_request,
+                    balancing,
+                );
+            capture_point_adjustment.limited_adjustment = limited_adjustment;
+            (adjusted_left_foot, adjusted_right_foot)
+        } else {
+            step_adjustment(
+                self.swing_side,
+                self.filtered_robot_tilt_shift.state(),
+                self.left_foot,
+                self.right_foot,
+                next_left_walk_request,
+                next_right_walk_request,
+                self.last_left_walk_request,
+                self.last_right_walk_request,
+                config.forward_foot_support_offset,
+                config.backward_foot_support_offset,
+                config.max_step_adjustment,
+                step_adjustment_output,
+            )
+        };
         self.last_left_walk_request = next_left_walk_request;
         self.last_right_walk_request = next_right_walk_request;
         self.left_foot = adjusted_left_foot;
@@ -647,11 +752,7 @@ impl WalkingEngine {
         self.right_foot_lift = next_right_foot_lift;
     }
 
-    fn calculate_leg_joints(
-        &self,
-        torso_shift_offset: f32,
-        walk_hip_height: f32,
-    ) -> (LegJoints<f32>, LegJoints<f32>) {
+    fn calculate_foot_targets(&self, torso_shift_offset: f32, walk_hip_height: f32) -> FootTargets {
         let left_foot_to_robot = calculate_foot_to_robot(
             Side::Left,
             self.left_foot,
@@ -668,12 +769,10 @@ impl WalkingEngine {
             torso_shift_offset,
             walk_hip_height,
         );
-        let (is_reachable, left_leg, right_leg) =
-            kinematics::leg_angles(left_foot_to_robot, right_foot_to_robot);
-        if !is_reachable {
-            warn!("Not reachable!");
+        FootTargets {
+            left_foot_to_robot,
+            right_foot_to_robot,
         }
-        (left_leg, right_leg)
     }
 }
 
@@ -1,9 +1,10 @@
 use std::time::Duration;
 
 use framework::AdditionalOutput;
-use nalgebra::Vector2;
+use nalgebra::{vector, Vector2, Vector3};
 use types::{
-    configuration::WalkingEngine as WalkingEngineConfiguration, LegJoints, Side, StepAdjustment,
+    configuration::{CapturePointBalancing, WalkingEngine as WalkingEngineConfiguration},
+    LegJoints, Side, StepAdjustment,
 };
 
 use super::foot_offsets::FootOffsets;
@@ -114,3 +115,189 @@ pub fn step_adjustment(
         },
     )
 }
+
+const GRAVITATIONAL_ACCELERATION: f32 = 9.81;
+
+/// Point on the ground the center of mass comes to rest above if the robot steps onto it, the
+/// robot is modeled as linear inverted pendulum over the support sole
+pub fn capture_point(
+    center_of_mass: Vector3<f32>,
+    center_of_mass_velocity: Vector2<f32>,
+) -> Vector2<f32> {
+    let natural_frequency =
+        (GRAVITATIONAL_ACCELERATION / center_of_mass.z.max(f32::EPSILON)).sqrt();
+    center_of_mass.xy() + center_of_mass_velocity / natural_frequency
+}
+
+/// Distance of the capture point to the support region of the support sole, sideways only towards
+/// the swing side since stepping cannot catch pushes towards the support side
+pub fn capture_point_error(
+    capture_point: Vector2<f32>,
+    swing_side: Side,
+    config: &CapturePointBalancing,
+) -> Vector2<f32> {
+    let forward_error = capture_point.x
+        - capture_point.x.clamp(
+            config.backward_support_offset,
+            config.forward_support_offset,
+        );
+    let outward_error = match swing_side {
+        Side::Left => (capture_point.y - config.sideways_support_offset).max(0.0),
+        Side::Right => (capture_point.y + config.sideways_support_offset).min(0.0),
+    };
+    vector![forward_error, outward_error]
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn capture_point_step_adjustment(
+    swing_side: Side,
+    capture_point_error: Vector2<f32>,
+    current_left_foot: FootOffsets,
+    current_right_foot: FootOffsets,
+    next_left_walk_request: FootOffsets,
+    next_right_walk_request: FootOffsets,
+    last_left_walk_request: FootOffsets,
+    last_right_walk_request: FootOffsets,
+    config: &CapturePointBalancing,
+) -> (FootOffsets, FootOffsets, Vector2<f32>) {
+    let next_left_foot = FootOffsets {
+        forward: current_left_foot.forward + next_left_walk_request.forward
+            - last_left_walk_request.forward,
+        left: current_left_foot.left + next_left_walk_request.left - last_left_walk_request.left,
+    };
+    let next_right_foot = FootOffsets {
+        forward: current_right_foot.forward + next_right_walk_request.forward
+            - last_right_walk_request.forward,
+        left: current_right_foot.left + next_right_walk_request.left - last_right_walk_request.left,
+    };
+    let limited_adjustment = vector![
+        capture_point_error.x.clamp(
+            -config.max_forward_step_adjustment,
+            config.max_forward_step_adjustment
+        ),
+        capture_point_error.y.clamp(
+            -config.max_sideways_step_adjustment,
+            config.max_sideways_step_adjustment
+        )
+    ];
+    let (next_swing_foot, next_support_foot) = match swing_side {
+        Side::Left => (next_left_foot, next_right_foot),
+        Side::Right => (next_right_foot, next_left_foot),
+    };
+    let adjusted_swing_foot = FootOffsets {
+        forward: next_swing_foot.forward + limited_adjustment.x,
+        left: next_swing_foot.left + limited_adjustment.y,
+    };
+    let adjusted_support_foot = FootOffsets {
+        forward: next_support_foot.forward - 0.5 * limited_adjustment.x,
+        left: next_support_foot.left - 0.5 * limited_adjustment.y,
+    };
+    let (adjusted_left_foot, adjusted_right_foot) = match swing_side {
+        Side::Left => (adjusted_swing_foot, adjusted_support_foot),
+        Side::Right => (adjusted_support_foot, adjusted_swing_foot),
+    };
+    (adjusted_left_foot, adjusted_right_foot, limited_adjustment)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    const CONFIG: CapturePointBalancing = CapturePointBalancing {
+        enabled: true,
+        velocity_low_pass_factor: 0.3,
+        forward_support_offset: 0.08,
+        backward_support_offset: -0.04,
+        sideways_support_offset: 0.03,
+        max_forward_step_adjustment: 0.002,
+        max_sideways_step_adjustment: 0.0015,
+        step_acceleration_factor: 5.0,
+        max_step_acceleration: 0.5,
+    };
+
+    fn offsets(forward: f32, left: f32) -> FootOffsets {
+        FootOffsets { forward, left }
+    }
+
+    #[test]
+    fn resting_center_of_mass_is_captured_below_itself() {
+        let capture_point = capture_point(vector![0.01, -0.02, 0.25], Vector2::zeros());
+
+        assert_relative_eq!(capture_point, vector![0.01, -0.02]);
+    }
+
+    #[test]
+    fn capture_point_leads_center_of_mass_by_velocity_over_natural_frequency() {
+        let height = 9.81 / 16.0;
+        let capture_point = capture_point(vector![0.0, 0.0, height], vector![0.2, -0.4]);
+
+        assert_relative_eq!(capture_point, vector![0.05, -0.1], epsilon = 1e-6);
+    }
+
+    #[test]
+    fn capture_point_inside_support_region_has_no_error() {
+        let error = capture_point_error(vector![0.05, 0.02], Side::Left, &CONFIG);
+
+        assert_relative_eq!(error, Vector2::zeros());
+    }
+
+    #[test]
+    fn capture_point_outside_support_region_is_measured_from_its_border() {
+        let error = capture_point_error(vector![0.1, 0.05], Side::Left, &CONFIG);
+        assert_relative_eq!(error, vector![0.02, 0.02], epsilon = 1e-6);
+
+        let error = capture_point_error(vector![-0.07, -0.05], Side::Right, &CONFIG);
+        assert_relative_eq!(error, vector![-0.03, -0.02], epsilon = 1e-6);
+    }
+
+    #[test]
+    fn sideways_error_towards_support_side_is_ignored() {
+        let error = capture_point_error(vector![0.0, -0.05], Side::Left, &CONFIG);
+        assert_relative_eq!(error, Vector2::zeros());
+
+        let error = capture_point_error(vector![0.0, 0.05], Side::Right, &CONFIG);
+        assert_relative_eq!(error, Vector2::zeros());
+    }
+
+    #[test]
+    fn step_adjustment_moves_swing_foot_towards_capture_point() {
+        let (left, right, adjustment) = capture_point_step_adjustment(
+            Side::Left,
+            vector![0.001, 0.001],
+            offsets(0.01, 0.05),
+            offsets(-0.01, -0.05),
+            offsets(0.03, 0.05),
+            offsets(0.0, -0.05),
+            offsets(0.01, 0.05),
+            offsets(-0.01, -0.05),
+            &CONFIG,
+        );
+
+        assert_relative_eq!(adjustment, vector![0.001, 0.001]);
+        assert_relative_eq!(left.forward, 0.031, epsilon = 1e-6);
+        assert_relative_eq!(left.left, 0.051, epsilon = 1e-6);
+        assert_relative_eq!(right.forward, 0.0 - 0.0005, epsilon = 1e-6);
+        assert_relative_eq!(right.left, -0.05 - 0.0005, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn step_adjustment_is_limited() {
+        let (_, right, adjustment) = capture_point_step_adjustment(
+            Side::Right,
+            vector![-0.1, -0.1],
+            FootOffsets::zero(),
+            FootOffsets::zero(),
+            FootOffsets::zero(),
+            FootOffsets::zero(),
+            FootOffsets::zero(),
+            FootOffsets::zero(),
+            &CONFIG,
+        );
+
+        assert_relative_eq!(adjustment, vector![-0.002, -0.0015]);
+        assert_relative_eq!(right.forward, -0.002);
+        assert_relative_eq!(right.left, -0.0015);
+    }
+}
@@ -1,13 +1,13 @@
 use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
-use types::{configuration::KickSteps, KickVariant, Side, Step, WalkCommand};
+use types::{configuration::KickSteps, InWalkKickVariant, Side, Step, WalkCommand};
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, SerializeHierarchy)]
 pub enum WalkState {
     Standing,
     Starting(Step),
     Walking(Step),
-    Kicking(KickVariant, Side, usize, f32),
+    Kicking(InWalkKickVariant, Side, usize, f32),
     Stopping,
 }
 
@@ -49,12 +49,7 @@ impl WalkState {
                 }
             }
             (WalkState::Kicking(kick_variant, kick_side, step_i, strength), WalkCommand::Stand) => {
-                let num_steps = match kick_variant {
-                    KickVariant::Forward => &kick_steps.forward,
-                    KickVariant::Turn => &kick_steps.turn,
-                    KickVariant::Side => &kick_steps.side,
-                }
-                .len();
+                let num_steps = kick_steps[kick_variant].len();
                 if step_i + 1 < num_steps {
                     WalkState::Kicking(kick_variant, kick_side, step_i + 1, strength)
                 } else {
@@ -65,12 +60,7 @@ impl WalkState {
                 WalkState::Kicking(kick_variant, kick_side, step_i, strength),
                 WalkCommand::Walk(step),
             ) => {
-                let num_steps = match kick_variant {
-                    KickVariant::Forward => &kick_steps.forward,
-                    KickVariant::Turn => &kick_steps.turn,
-                    KickVariant::Side => &kick_steps.side,
-                }
-                .len();
+                let num_steps = kick_steps[kick_variant].len();
                 if step_i + 1 < num_steps {
                     WalkState::Kicking(kick_variant, kick_side, step_i + 1, strength)
                 } else {
@@ -81,12 +71,7 @@ impl WalkState {
                 WalkState::Kicking(current_kick_variant, current_kick_side, step_i, strength),
                 WalkCommand::Kick(..),
             ) => {
-                let num_steps = match current_kick_variant {
-                    KickVariant::Forward => &kick_steps.forward,
-                    KickVariant::Turn => &kick_steps.turn,
-                    KickVariant::Side => &kick_steps.side,
-                }
-                .len();
+                let num_steps = kick_steps[current_kick_variant].len();
                 if step_i + 1 < num_steps {
                     WalkState::Kicking(
                         current_kick_variant,
@@ -3,7 +3,7 @@ use std::time::{Duration, SystemTime};
 use color_eyre::{eyre::WrapErr, Result};
 use context_attribute::context;
 use framework::{MainOutput, PerceptionInput};
-use nalgebra::{Isometry2, Point2, Vector2};
+use nalgebra::{Isometry2, Matrix3, Point2, Vector2};
 use spl_network_messages::{
     GameControllerReturnMessage, GamePhase, HulkMessage, Penalty, PlayerNumber, Team,
 };
@@ -38,8 +38,10 @@ pub struct CycleContext {
     pub ball_position: Input<Option<BallPosition>, "ball_position?">,
     pub fall_state: Input<FallState, "fall_state">,
     pub game_controller_state: Input<Option<GameControllerState>, "game_controller_state?">,
+    pub localization_quality: Input<Option<f32>, "localization_quality?">,
     pub primary_state: Input<PrimaryState, "primary_state">,
     pub robot_to_field: Input<Option<Isometry2<f32>>, "robot_to_field?">,
+    pub robot_to_field_covariance: Input<Option<Matrix3<f32>>, "robot_to_field_covariance?">,
     pub cycle_time: Input<CycleTime, "cycle_time">,
 
     pub field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
@@ -56,7 +58,6 @@ pub struct CycleContext {
 #[context]
 #[derive(Default)]
 pub struct MainOutputs {
-    pub team_ball: MainOutput<Option<BallPosition>>,
     pub network_robot_obstacles: MainOutput<Vec<Point2<f32>>>,
     pub role: MainOutput<Role>,
 }
@@ -156,6 +157,11 @@ impl RoleAssignment {
         }
 
         let mut team_ball = self.team_ball;
+        let robot_to_field_covariance = context
+            .robot_to_field_covariance
+            .copied()
+            .unwrap_or_default();
+        let localization_quality = context.localization_quality.copied().unwrap_or_default();
 
         if spl_striker_message_timeout {
             match role {
@@ -202,6 +208,8 @@ impl RoleAssignment {
                 context.game_controller_state,
                 *context.player_number,
                 context.spl_network.striker_trusts_team_ball,
+                localization_quality,
+                context.spl_network.minimum_teammate_localization_quality,
                 context.optional_roles,
             );
         } else {
@@ -209,7 +217,10 @@ impl RoleAssignment {
                 self.last_received_spl_striker_message = Some(cycle_start_time);
                 let sender_position =
                     (robot_to_field.inverse() * spl_message.robot_to_field) * Point2::origin();
-                if spl_message.player_number != *context.player_number {
+                if spl_message.player_number != *context.player_number
+                    && spl_message.localization_quality
+                        >= context.spl_network.minimum_teammate_localization_quality
+                {
                     network_robot_obstacles.push(sender_position);
                 }
                 (role, send_spl_striker_message, team_ball) = process_role_state_machine(
@@ -224,6 +235,8 @@ impl RoleAssignment {
                     context.game_controller_state,
                     *context.player_number,
                     context.spl_network.striker_trusts_team_ball,
+                    localization_quality,
+                    context.spl_network.minimum_teammate_localization_quality,
                     context.optional_roles,
                 );
             }
@@ -248,11 +261,14 @@ impl RoleAssignment {
                                 player_number: *context.player_number,
                                 fallen: matches!(context.fall_state, FallState::Fallen { .. }),
                                 robot_to_field,
+                                robot_to_field_covariance,
+                                localization_quality,
                                 ball_position: team_ball_to_network_ball_position(
                                     team_ball,
                                     robot_to_field,
                                     cycle_start_time,
                                 ),
+                                ball_is_own_detection: false,
                             }))?;
                     } else {
                         context
@@ -261,10 +277,13 @@ impl RoleAssignment {
                                 player_number: *context.player_number,
                                 fallen: matches!(context.fall_state, FallState::Fallen { .. }),
                                 robot_to_field,
+                                robot_to_field_covariance,
+                                localization_quality,
                                 ball_position: seen_ball_to_network_ball_position(
                                     context.ball_position,
                                     cycle_start_time,
                                 ),
+                                ball_is_own_detection: context.ball_position.is_some(),
                             }))?;
                     }
                 }
@@ -280,7 +299,6 @@ impl RoleAssignment {
 
         Ok(MainOutputs {
             role: self.role.into(),
-            team_ball: self.team_ball.into(),
             network_robot_obstacles: network_robot_obstacles.into(),
         })
     }
@@ -299,6 +317,8 @@ fn process_role_state_machine(
     game_controller_state: Option<&GameControllerState>,
     player_number: PlayerNumber,
     striker_trusts_team_ball: Duration,
+    localization_quality: f32,
+    minimum_teammate_localization_quality: f32,
     optional_roles: &[Role],
 ) -> (Role, bool, Option<BallPosition>) {
     if let Some(game_controller_state) = game_controller_state {
@@ -375,6 +395,8 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
@@ -399,6 +421,8 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
@@ -415,6 +439,8 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
@@ -443,6 +469,8 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
@@ -459,6 +487,8 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
@@ -484,6 +514,8 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
@@ -507,6 +539,8 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
@@ -539,12 +573,15 @@ fn process_role_state_machine(
                 player_number,
                 cycle_start_time,
                 game_controller_state,
+                localization_quality,
+                minimum_teammate_localization_quality,
                 optional_roles,
             ),
         },
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn decide_if_claiming_striker_or_other_role(
     current_pose: Isometry2<f32>,
     spl_message: &HulkMessage,
@@ -552,18 +589,23 @@ fn decide_if_claiming_striker_or_other_role(
     player_number: PlayerNumber,
     cycle_start_time: SystemTime,
     game_controller_state: Option<&GameControllerState>,
+    localization_quality: f32,
+    minimum_teammate_localization_quality: f32,
     optional_roles: &[Role],
 ) -> (Role, bool, Option<BallPosition>) {
+    let team_ball = team_ball_from_spl_message(
+        cycle_start_time,
+        spl_message,
+        minimum_teammate_localization_quality,
+    );
     if am_better_striker(
         current_pose,
-        spl_message.robot_to_field,
+        localization_quality,
+        spl_message,
         spl_message_ball_position,
+        minimum_teammate_localization_quality,
     ) {
-        (
-            Role::Striker,
-            true,
-            team_ball_from_spl_message(cycle_start_time, spl_message),
-        )
+        (Role::Striker, true, team_ball)
     } else {
         (
             generate_role(
@@ -573,7 +615,7 @@ fn decide_if_claiming_striker_or_other_role(
                 optional_roles,
             ),
             false,
-            team_ball_from_spl_message(cycle_start_time, spl_message),
+            team_ball,
         )
     }
 }
@@ -601,10 +643,15 @@ fn team_ball_to_network_ball_position(
     })
 }
 
+/// Balls of poorly localized teammates are not trustworthy in field coordinates
 fn team_ball_from_spl_message(
     cycle_start_time: SystemTime,
     spl_message: &HulkMessage,
+    minimum_localization_quality: f32,
 ) -> Option<BallPosition> {
+    if spl_message.localization_quality < minimum_localization_quality {
+        return None;
+    }
     spl_message
         .ball_position
         .as_ref()
@@ -627,12 +674,23 @@ fn team_ball_from_seen_ball(
     })
 }
 
+/// A well localized robot is preferred over a poorly localized one, otherwise the robot closer to
+/// the ball is the better striker
 fn am_better_striker(
     current_pose: Isometry2<f32>,
-    origin_pose: Isometry2<f32>,
+    localization_quality: f32,
+    spl_message: &HulkMessage,
     spl_message_ball_position: &spl_network_messages::BallPosition,
+    minimum_localization_quality: f32,
 ) -> bool {
-    (current_pose.inverse() * origin_pose * spl_message_ball_position.relative_position)
+    let am_well_localized = localization_quality >= minimum_localization_quality;
+    let is_sender_well_localized = spl_message.localization_quality >= minimum_localization_quality;
+    if am_well_localized != is_sender_well_localized {
+        return am_well_localized;
+    }
+    (current_pose.inverse()
+        * spl_message.robot_to_field
+        * spl_message_ball_position.relative_position)
         .coords
         .norm()
         < spl_message_ball_position.relative_position.coords.norm()
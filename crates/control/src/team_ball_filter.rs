@@ -0,0 +1,373 @@
+use std::time::{Duration, SystemTime};
+
+use color_eyre::Result;
+use context_attribute::context;
+use filtering::kalman_filter::KalmanFilter;
+use framework::{AdditionalOutput, MainOutput, PerceptionInput};
+use nalgebra::{
+    matrix, vector, Isometry2, Matrix2, Matrix2x4, Matrix3, Matrix4, Point2, Vector2, Vector4,
+};
+use spl_network_messages::PlayerNumber;
+use types::{
+    ball_filter::Hypothesis, messages::IncomingMessage,
+    multivariate_normal_distribution::MultivariateNormalDistribution, BallPosition, CycleTime,
+    FieldDimensions,
+};
+
+pub struct TeamBallFilter {
+    hypotheses: Vec<Hypothesis>,
+    last_own_ball_update: Option<SystemTime>,
+}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    pub team_ball_filter_hypotheses:
+        AdditionalOutput<Vec<Hypothesis>, "team_ball_filter_hypotheses">,
+
+    pub ball_position: Input<Option<BallPosition>, "ball_position?">,
+    pub cycle_time: Input<CycleTime, "cycle_time">,
+    pub robot_to_field: Input<Option<Isometry2<f32>>, "robot_to_field?">,
+    pub robot_to_field_covariance: Input<Option<Matrix3<f32>>, "robot_to_field_covariance?">,
+
+    pub field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    pub hypothesis_merge_distance: Parameter<f32, "team_ball_filter.hypothesis_merge_distance">,
+    pub hypothesis_timeout: Parameter<Duration, "team_ball_filter.hypothesis_timeout">,
+    pub initial_covariance: Parameter<Vector4<f32>, "team_ball_filter.initial_covariance">,
+    pub maximum_message_ball_age: Parameter<Duration, "team_ball_filter.maximum_message_ball_age">,
+    pub measurement_matching_distance:
+        Parameter<f32, "team_ball_filter.measurement_matching_distance">,
+    pub measurement_noise: Parameter<Vector2<f32>, "team_ball_filter.measurement_noise">,
+    pub message_age_noise: Parameter<f32, "team_ball_filter.message_age_noise">,
+    pub minimum_teammate_localization_quality:
+        Parameter<f32, "spl_network.minimum_teammate_localization_quality">,
+    pub player_number: Parameter<PlayerNumber, "player_number">,
+    pub process_noise: Parameter<Vector4<f32>, "team_ball_filter.process_noise">,
+    pub validity_discard_threshold: Parameter<f32, "team_ball_filter.validity_discard_threshold">,
+    pub validity_exponential_decay_factor:
+        Parameter<f32, "team_ball_filter.validity_exponential_decay_factor">,
+    pub velocity_decay_factor: Parameter<f32, "team_ball_filter.velocity_decay_factor">,
+
+    pub network_message: PerceptionInput<IncomingMessage, "SplNetwork", "message">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub team_ball: MainOutput<Option<BallPosition>>,
+}
+
+/// Ball observation of any robot of the team in field coordinates, relayed team balls are not
+/// measurements since they would fuse the filter's own output again
+#[derive(Clone, Copy, Debug)]
+struct TeamBallMeasurement {
+    position: Point2<f32>,
+    covariance: Matrix2<f32>,
+    detection_time: SystemTime,
+}
+
+impl TeamBallFilter {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            hypotheses: Vec::new(),
+            last_own_ball_update: None,
+        })
+    }
+
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        let now = context.cycle_time.start_time;
+        self.predict_hypotheses(
+            context.cycle_time.last_cycle_duration,
+            *context.velocity_decay_factor,
+            Matrix4::from_diagonal(context.process_noise),
+            *context.validity_exponential_decay_factor,
+        );
+
+        let mut measurements = Vec::new();
+        if let (Some(ball_position), Some(robot_to_field)) =
+            (context.ball_position, context.robot_to_field)
+        {
+            let is_new_detection = !matches!(
+                self.last_own_ball_update,
+                Some(last_update) if last_update >= ball_position.last_seen
+            );
+            if is_new_detection {
+                self.last_own_ball_update = Some(ball_position.last_seen);
+                measurements.push(TeamBallMeasurement {
+                    position: robot_to_field * ball_position.position,
+                    covariance: ball_in_field_covariance(
+                        *robot_to_field,
+                        &context
+                            .robot_to_field_covariance
+                            .copied()
+                            .unwrap_or_default(),
+                        ball_position.position,
+                        *context.measurement_noise,
+                    ),
+                    detection_time: ball_position.last_seen,
+                });
+            }
+        }
+        let teammate_measurements = context
+            .network_message
+            .persistent
+            .iter()
+            .flat_map(|(time, messages)| messages.iter().map(move |message| (*time, message)))
+            .filter_map(|(time, message)| match message {
+                IncomingMessage::Spl(message) => Some((time, message)),
+                _ => None,
+            })
+            .filter(|(_time, message)| {
+                message.player_number != *context.player_number
+                    && !message.fallen
+                    && message.ball_is_own_detection
+                    && message.localization_quality
+                        >= *context.minimum_teammate_localization_quality
+            })
+            .filter_map(|(time, message)| {
+                let ball_position = message.ball_position?;
+                if ball_position.age > *context.maximum_message_ball_age {
+                    return None;
+                }
+                let detection_time = time.checked_sub(ball_position.age)?;
+                let age = now.duration_since(detection_time).unwrap_or_default();
+                let covariance =
+                    ball_in_field_covariance(
+                        message.robot_to_field,
+                        &message.robot_to_field_covariance,
+                        ball_position.relative_position,
+                        *context.measurement_noise,
+                    ) + Matrix2::identity() * *context.message_age_noise * age.as_secs_f32();
+                Some(TeamBallMeasurement {
+                    position: message.robot_to_field * ball_position.relative_position,
+                    covariance,
+                    detection_time,
+                })
+            });
+        measurements.extend(teammate_measurements);
+
+        for measurement in measurements {
+            self.update_hypotheses_with_measurement(
+                measurement,
+                *context.measurement_matching_distance,
+                Matrix4::from_diagonal(context.initial_covariance),
+            );
+        }
+
+        self.remove_hypotheses(
+            now,
+            *context.hypothesis_merge_distance,
+            *context.hypothesis_timeout,
+            *context.validity_discard_threshold,
+            context.field_dimensions,
+        );
+
+        let team_ball = self
+            .hypotheses
+            .iter()
+            .max_by(|a, b| a.validity.total_cmp(&b.validity))
+            .map(|hypothesis| BallPosition {
+                position: Point2::from(hypothesis.state.mean.xy()),
+                velocity: vector![hypothesis.state.mean.z, hypothesis.state.mean.w],
+                last_seen: hypothesis.last_update,
+            });
+        context
+            .team_ball_filter_hypotheses
+            .fill_if_subscribed(|| self.hypotheses.clone());
+
+        Ok(MainOutputs {
+            team_ball: team_ball.into(),
+        })
+    }
+
+    fn predict_hypotheses(
+        &mut self,
+        cycle_duration: Duration,
+        velocity_decay_factor: f32,
+        process_noise: Matrix4<f32>,
+        validity_exponential_decay_factor: f32,
+    ) {
+        let time_step = cycle_duration.as_secs_f32();
+        let constant_velocity_prediction = matrix![
+            1.0, 0.0, time_step, 0.0;
+            0.0, 1.0, 0.0, time_step;
+            0.0, 0.0, velocity_decay_factor, 0.0;
+            0.0, 0.0, 0.0, velocity_decay_factor;
+        ];
+        for hypothesis in self.hypotheses.iter_mut() {
+            hypothesis.state.predict(
+                constant_velocity_prediction,
+                Matrix4::zeros(),
+                Vector4::zeros(),
+                process_noise * time_step,
+            );
+            hypothesis.validity *= validity_exponential_decay_factor;
+        }
+    }
+
+    fn update_hypotheses_with_measurement(
+        &mut self,
+        measurement: TeamBallMeasurement,
+        matching_distance: f32,
+        initial_covariance: Matrix4<f32>,
+    ) {
+        let mut matching_hypotheses = self
+            .hypotheses
+            .iter_mut()
+            .filter(|hypothesis| {
+                (hypothesis.state.mean.xy() - measurement.position.coords).norm()
+                    < matching_distance
+            })
+            .peekable();
+        if matching_hypotheses.peek().is_none() {
+            self.hypotheses.push(Hypothesis {
+                state: MultivariateNormalDistribution {
+                    mean: vector![measurement.position.x, measurement.position.y, 0.0, 0.0],
+                    covariance: initial_covariance,
+                },
+                validity: 1.0,
+                last_update: measurement.detection_time,
+            });
+            return;
+        }
+        matching_hypotheses.for_each(|hypothesis| {
+            hypothesis.state.update(
+                Matrix2x4::identity(),
+                measurement.position.coords,
+                measurement.covariance,
+            );
+            hypothesis.validity += 1.0;
+            hypothesis.last_update = hypothesis.last_update.max(measurement.detection_time);
+        });
+    }
+
+    fn remove_hypotheses(
+        &mut self,
+        now: SystemTime,
+        merge_distance: f32,
+        hypothesis_timeout: Duration,
+        validity_discard_threshold: f32,
+        field_dimensions: &FieldDimensions,
+    ) {
+        self.hypotheses.retain(|hypothesis| {
+            let position = hypothesis.state.mean.xy();
+            let is_inside_field = position.x.abs()
+                < field_dimensions.length / 2.0 + field_dimensions.border_strip_width
+                && position.y.abs()
+                    < field_dimensions.width / 2.0 + field_dimensions.border_strip_width;
+            now.duration_since(hypothesis.last_update)
+                .unwrap_or_default()
+                < hypothesis_timeout
+                && hypothesis.validity > validity_discard_threshold
+                && is_inside_field
+        });
+        let mut deduplicated_hypotheses = Vec::<Hypothesis>::new();
+        for hypothesis in self.hypotheses.drain(..) {
+            let hypothesis_in_merge_distance =
+                deduplicated_hypotheses
+                    .iter_mut()
+                    .find(|existing_hypothesis| {
+                        (existing_hypothesis.state.mean.xy() - hypothesis.state.mean.xy()).norm()
+                            < merge_distance
+                    });
+            match hypothesis_in_merge_distance {
+                Some(existing_hypothesis) => {
+                    existing_hypothesis.state.update(
+                        Matrix4::identity(),
+                        hypothesis.state.mean,
+                        hypothesis.state.covariance,
+                    );
+                    existing_hypothesis.validity =
+                        existing_hypothesis.validity.max(hypothesis.validity);
+                    existing_hypothesis.last_update =
+                        existing_hypothesis.last_update.max(hypothesis.last_update);
+                }
+                None => deduplicated_hypotheses.push(hypothesis),
+            }
+        }
+        self.hypotheses = deduplicated_hypotheses;
+    }
+}
+
+/// Covariance of a ball seen relative to a robot in field coordinates, the uncertainty of the
+/// robot's orientation grows with the distance to the ball
+fn ball_in_field_covariance(
+    robot_to_field: Isometry2<f32>,
+    robot_to_field_covariance: &Matrix3<f32>,
+    relative_ball_position: Point2<f32>,
+    measurement_noise: Vector2<f32>,
+) -> Matrix2<f32> {
+    let rotation = robot_to_field.rotation.to_rotation_matrix().into_inner();
+    let orientation_derivative =
+        rotation * vector![-relative_ball_position.y, relative_ball_position.x];
+    let pose_jacobian = matrix![
+        1.0, 0.0, orientation_derivative.x;
+        0.0, 1.0, orientation_derivative.y;
+    ];
+    let relative_covariance =
+        Matrix2::from_diagonal(&measurement_noise) * relative_ball_position.coords.norm_squared();
+    pose_jacobian * robot_to_field_covariance * pose_jacobian.transpose()
+        + rotation * relative_covariance * rotation.transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use approx::assert_relative_eq;
+    use nalgebra::point;
+
+    use super::*;
+
+    #[test]
+    fn orientation_uncertainty_spreads_perpendicular_to_the_ball() {
+        let robot_to_field = Isometry2::new(vector![1.0, 0.0], 0.0);
+        let robot_to_field_covariance = Matrix3::from_diagonal(&vector![0.0, 0.0, 0.01]);
+
+        let covariance = ball_in_field_covariance(
+            robot_to_field,
+            &robot_to_field_covariance,
+            point![2.0, 0.0],
+            Vector2::zeros(),
+        );
+
+        assert_relative_eq!(covariance, matrix![0.0, 0.0; 0.0, 0.04], epsilon = 1e-6);
+    }
+
+    #[test]
+    fn matching_measurements_are_fused_into_one_hypothesis() {
+        let mut filter = TeamBallFilter {
+            hypotheses: Vec::new(),
+            last_own_ball_update: None,
+        };
+        let initial_covariance = Matrix4::from_diagonal(&vector![1.0, 1.0, 1.0, 1.0]);
+        let detection_time = UNIX_EPOCH + Duration::from_secs(10);
+        for position in [point![1.0, 0.0], point![1.2, 0.0]] {
+            filter.update_hypotheses_with_measurement(
+                TeamBallMeasurement {
+                    position,
+                    covariance: Matrix2::identity() * 0.01,
+                    detection_time,
+                },
+                0.5,
+                initial_covariance,
+            );
+        }
+        filter.update_hypotheses_with_measurement(
+            TeamBallMeasurement {
+                position: point![-2.0, 1.0],
+                covariance: Matrix2::identity() * 0.01,
+                detection_time,
+            },
+            0.5,
+            initial_covariance,
+        );
+
+        assert_eq!(filter.hypotheses.len(), 2);
+        let fused = &filter.hypotheses[0];
+        assert_relative_eq!(fused.validity, 2.0);
+        assert!(fused.state.mean.x > 1.1 && fused.state.mean.x < 1.2);
+    }
+}
@@ -4,8 +4,8 @@ use framework::MainOutput;
 use nalgebra::{Isometry2, Point2};
 use spl_network_messages::PlayerNumber;
 use types::{
-    BallState, FallState, FilteredGameState, GameControllerState, KickDecision, Obstacle,
-    PenaltyShotDirection, PrimaryState, RobotState, Role, RuleObstacle, WorldState,
+    BallState, BallTrajectory, FallState, FilteredGameState, GameControllerState, KickDecision,
+    Obstacle, PenaltyShotDirection, PrimaryState, RobotState, Role, RuleObstacle, WorldState,
 };
 
 pub struct WorldStateComposer {}
@@ -18,11 +18,13 @@ pub struct CreationContext {
 #[context]
 pub struct CycleContext {
     pub ball: Input<Option<BallState>, "ball_state?">,
+    pub ball_trajectory: Input<Option<BallTrajectory>, "ball_trajectory?">,
     pub rule_ball: Input<Option<BallState>, "rule_ball_state?">,
     pub filtered_game_state: Input<Option<FilteredGameState>, "filtered_game_state?">,
     pub game_controller_state: Input<Option<GameControllerState>, "game_controller_state?">,
     pub penalty_shot_direction: Input<Option<PenaltyShotDirection>, "penalty_shot_direction?">,
     pub robot_to_field: Input<Option<Isometry2<f32>>, "robot_to_field?">,
+    pub localization_quality: Input<Option<f32>, "localization_quality?">,
     pub kick_decisions: Input<Option<Vec<KickDecision>>, "kick_decisions?">,
     pub instant_kick_decisions: Input<Option<Vec<KickDecision>>, "instant_kick_decisions?">,
 
@@ -51,6 +53,7 @@ impl WorldStateComposer {
     pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
         let robot = RobotState {
             robot_to_field: context.robot_to_field.copied(),
+            localization_quality: context.localization_quality.copied(),
             role: *context.role,
             primary_state: *context.primary_state,
             fall_state: *context.fall_state,
@@ -60,6 +63,7 @@ impl WorldStateComposer {
 
         let world_state = WorldState {
             ball: context.ball.copied(),
+            ball_trajectory: context.ball_trajectory.copied(),
             rule_ball: context.rule_ball.copied(),
             filtered_game_state: context.filtered_game_state.copied(),
             obstacles: context.obstacles.clone(),
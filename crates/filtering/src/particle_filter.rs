@@ -0,0 +1,263 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Particle<State> {
+    pub state: State,
+    pub weight: f32,
+}
+
+/// Set of weighted state samples, the weights are kept normalized to a sum of one
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ParticleFilter<State> {
+    particles: Vec<Particle<State>>,
+}
+
+impl<State> Default for ParticleFilter<State> {
+    fn default() -> Self {
+        Self { particles: vec![] }
+    }
+}
+
+impl<State> ParticleFilter<State>
+where
+    State: Clone,
+{
+    pub fn new(states: impl IntoIterator<Item = State>) -> Self {
+        let mut particle_filter = Self {
+            particles: states
+                .into_iter()
+                .map(|state| Particle { state, weight: 1.0 })
+                .collect(),
+        };
+        particle_filter.normalize_weights();
+        particle_filter
+    }
+
+    pub fn particles(&self) -> &[Particle<State>] {
+        &self.particles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Moves every particle with the (usually randomized) motion model
+    pub fn predict(&mut self, mut state_prediction_function: impl FnMut(&State) -> State) {
+        for particle in self.particles.iter_mut() {
+            particle.state = state_prediction_function(&particle.state);
+        }
+    }
+
+    /// Weights every particle with the log-likelihood of the measurement and returns the
+    /// logarithm of the average likelihood of the measurement under the previous weights
+    ///
+    /// Weights are combined in the log domain and normalized with log-sum-exp, such that products
+    /// of many small likelihoods do not underflow.
+    pub fn update(&mut self, log_likelihood_function: impl Fn(&State) -> f32) -> f32 {
+        let log_weights: Vec<_> = self
+            .particles
+            .iter()
+            .map(|particle| particle.weight.ln() + log_likelihood_function(&particle.state))
+            .collect();
+        let log_average_likelihood = log_sum_exp(&log_weights);
+        let number_of_particles = self.particles.len() as f32;
+        for (particle, log_weight) in self.particles.iter_mut().zip(log_weights) {
+            particle.weight = if log_average_likelihood.is_finite() {
+                (log_weight - log_average_likelihood).exp()
+            } else {
+                1.0 / number_of_particles
+            };
+        }
+        log_average_likelihood
+    }
+
+    /// Number of particles which would carry the same information with uniform weights
+    pub fn effective_sample_size(&self) -> f32 {
+        let sum_of_squared_weights: f32 = self
+            .particles
+            .iter()
+            .map(|particle| particle.weight.powi(2))
+            .sum();
+        if sum_of_squared_weights > 0.0 {
+            1.0 / sum_of_squared_weights
+        } else {
+            0.0
+        }
+    }
+
+    /// Draws the same number of particles proportional to their weights with low variance
+    /// resampling, all resulting particles have equal weights
+    pub fn resample(&mut self, random_number_generator: &mut impl Rng) {
+        let number_of_particles = self.particles.len();
+        if number_of_particles == 0 {
+            return;
+        }
+        let step = 1.0 / number_of_particles as f32;
+        let offset = random_number_generator.gen_range(0.0..step);
+        let mut resampled_particles = Vec::with_capacity(number_of_particles);
+        let mut index = 0;
+        let mut cumulative_weight = self.particles[0].weight;
+        for sample in 0..number_of_particles {
+            let threshold = offset + sample as f32 * step;
+            while threshold > cumulative_weight && index + 1 < number_of_particles {
+                index += 1;
+                cumulative_weight += self.particles[index].weight;
+            }
+            resampled_particles.push(Particle {
+                state: self.particles[index].state.clone(),
+                weight: step,
+            });
+        }
+        self.particles = resampled_particles;
+    }
+
+    /// Replaces the particles with the lowest weights by the given states, e.g. to reset the
+    /// filter from sensor measurements
+    pub fn replace_least_likely(&mut self, states: impl IntoIterator<Item = State>) {
+        if self.particles.is_empty() {
+            return;
+        }
+        let mean_weight = 1.0 / self.particles.len() as f32;
+        self.particles
+            .sort_unstable_by(|left, right| left.weight.total_cmp(&right.weight));
+        for (particle, state) in self.particles.iter_mut().zip(states) {
+            *particle = Particle {
+                state,
+                weight: mean_weight,
+            };
+        }
+        self.normalize_weights();
+    }
+
+    /// Weights are reset to uniform if all particles became impossible
+    fn normalize_weights(&mut self) {
+        let sum_of_weights: f32 = self.particles.iter().map(|particle| particle.weight).sum();
+        let number_of_particles = self.particles.len() as f32;
+        for particle in self.particles.iter_mut() {
+            particle.weight = if sum_of_weights > 0.0 && sum_of_weights.is_finite() {
+                particle.weight / sum_of_weights
+            } else {
+                1.0 / number_of_particles
+            };
+        }
+    }
+}
+
+/// Logarithm of the sum of the exponentials, shifted by the maximum to avoid underflow
+fn log_sum_exp(values: &[f32]) -> f32 {
+    let maximum = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if !maximum.is_finite() {
+        return maximum;
+    }
+    maximum
+        + values
+            .iter()
+            .map(|value| (value - maximum).exp())
+            .sum::<f32>()
+            .ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn update_concentrates_weights_on_likely_particles() {
+        let mut particle_filter = ParticleFilter::new([0.0_f32, 1.0, 2.0, 3.0]);
+        assert_relative_eq!(particle_filter.effective_sample_size(), 4.0);
+
+        let log_average_likelihood = particle_filter.update(|state| {
+            if *state >= 2.0 {
+                0.0
+            } else {
+                f32::NEG_INFINITY
+            }
+        });
+
+        assert_relative_eq!(log_average_likelihood, 0.5_f32.ln());
+        assert_relative_eq!(particle_filter.effective_sample_size(), 2.0);
+        assert_relative_eq!(particle_filter.particles()[0].weight, 0.0);
+        assert_relative_eq!(particle_filter.particles()[3].weight, 0.5);
+    }
+
+    #[test]
+    fn impossible_measurement_resets_to_uniform_weights() {
+        let mut particle_filter = ParticleFilter::new([0.0_f32, 1.0]);
+
+        particle_filter.update(|_state| f32::NEG_INFINITY);
+
+        assert_relative_eq!(particle_filter.particles()[0].weight, 0.5);
+        assert_relative_eq!(particle_filter.particles()[1].weight, 0.5);
+    }
+
+    #[test]
+    fn tiny_likelihoods_do_not_underflow() {
+        let mut particle_filter = ParticleFilter::new([0.0_f32, 1.0]);
+
+        let log_average_likelihood =
+            particle_filter.update(|state| if *state == 0.0 { -200.0 } else { -201.0 });
+
+        let expected_weight = 1.0 / (1.0 + (-1.0_f32).exp());
+        assert_relative_eq!(particle_filter.particles()[0].weight, expected_weight);
+        assert_relative_eq!(particle_filter.particles()[1].weight, 1.0 - expected_weight);
+        assert_relative_eq!(
+            log_average_likelihood,
+            -200.0 + (0.5 * (1.0 + (-1.0_f32).exp())).ln()
+        );
+    }
+
+    #[test]
+    fn resampling_keeps_only_likely_particles() {
+        let mut random_number_generator = StdRng::seed_from_u64(0);
+        let mut particle_filter = ParticleFilter::new([0.0_f32, 1.0, 2.0, 3.0]);
+        particle_filter.update(|state| {
+            if *state == 1.0 {
+                3.0_f32.ln()
+            } else {
+                -3.0_f32.ln()
+            }
+        });
+        particle_filter.update(|state| {
+            if *state == 1.0 {
+                3.0_f32.ln()
+            } else {
+                -3.0_f32.ln()
+            }
+        });
+
+        particle_filter.resample(&mut random_number_generator);
+
+        assert_eq!(particle_filter.len(), 4);
+        let number_of_likely_particles = particle_filter
+            .particles()
+            .iter()
+            .filter(|particle| particle.state == 1.0)
+            .count();
+        assert!(number_of_likely_particles >= 3);
+        assert_relative_eq!(particle_filter.effective_sample_size(), 4.0);
+    }
+
+    #[test]
+    fn least_likely_particles_are_replaced() {
+        let mut particle_filter = ParticleFilter::new([0.0_f32, 1.0, 2.0]);
+        particle_filter.update(|state| state.ln());
+
+        particle_filter.replace_least_likely([10.0]);
+
+        let states: Vec<_> = particle_filter
+            .particles()
+            .iter()
+            .map(|particle| particle.state)
+            .collect();
+        assert!(states.contains(&10.0));
+        assert!(!states.contains(&0.0));
+    }
+}
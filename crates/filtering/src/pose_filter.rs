@@ -40,6 +40,15 @@ pub trait PoseFilter {
     where
         MeasurementPredictionFunction: Fn(Vector3<f32>) -> Vector2<f32>;
 
+    fn update_with_2d_translation_and_rotation<MeasurementPredictionFunction>(
+        &mut self,
+        measurement: Vector3<f32>,
+        measurement_noise: Matrix3<f32>,
+        measurement_prediction_function: MeasurementPredictionFunction,
+    ) -> Result<(), Error>
+    where
+        MeasurementPredictionFunction: Fn(Vector3<f32>) -> Vector3<f32>;
+
     fn as_isometry(&self) -> Isometry2<f32>;
 }
 
@@ -154,6 +163,50 @@ impl PoseFilter for MultivariateNormalDistribution<3> {
         Ok(())
     }
 
+    fn update_with_2d_translation_and_rotation<MeasurementPredictionFunction>(
+        &mut self,
+        measurement: Vector3<f32>,
+        measurement_noise: Matrix3<f32>,
+        measurement_prediction_function: MeasurementPredictionFunction,
+    ) -> Result<(), Error>
+    where
+        MeasurementPredictionFunction: Fn(Vector3<f32>) -> Vector3<f32>,
+    {
+        let sigma_points = sample_sigma_points(self.mean, self.covariance)?;
+        let predicted_measurements: Vec<_> = sigma_points
+            .iter()
+            .copied()
+            .map(measurement_prediction_function)
+            .collect();
+        let predicted_measurement_mean = mean_from_3d_sigma_points(&predicted_measurements);
+        let predicted_measurement_covariance =
+            covariance_from_3d_sigma_points(predicted_measurement_mean, &predicted_measurements);
+
+        let predicted_measurements_cross_covariance = cross_covariance_from_3d_sigma_points(
+            self.mean,
+            &sigma_points,
+            &predicted_measurement_mean,
+            &predicted_measurements,
+        );
+        let kalman_gain = predicted_measurements_cross_covariance
+            * (predicted_measurement_covariance + measurement_noise)
+                .try_inverse()
+                .ok_or(Error::Inverse)?;
+
+        let residuum = vector![
+            measurement.x - predicted_measurement_mean.x,
+            measurement.y - predicted_measurement_mean.y,
+            (UnitComplex::new(measurement.z) / UnitComplex::new(predicted_measurement_mean.z))
+                .angle()
+        ];
+        self.mean += kalman_gain * residuum;
+        let updated_state_covariance = self.covariance
+            - kalman_gain * predicted_measurement_covariance * kalman_gain.transpose();
+        self.covariance = into_symmetric(updated_state_covariance);
+
+        Ok(())
+    }
+
     fn as_isometry(&self) -> Isometry2<f32> {
         Isometry2::new(vector![self.mean.x, self.mean.y], self.mean.z)
     }
@@ -285,6 +338,32 @@ fn cross_covariance_from_1d_translation_and_rotation_sigma_points(
         * (1.0 / 6.0)
 }
 
+fn cross_covariance_from_3d_sigma_points(
+    state_mean: Vector3<f32>,
+    state_sigma_points: &[Vector3<f32>],
+    &measurement_mean: &Vector3<f32>,
+    measurement_sigma_points: &[Vector3<f32>],
+) -> Matrix3<f32> {
+    assert!(state_sigma_points.len() == measurement_sigma_points.len());
+    state_sigma_points
+        .iter()
+        .zip(measurement_sigma_points.iter())
+        .map(|(state, measurement)| {
+            vector![
+                state.x - state_mean.x,
+                state.y - state_mean.y,
+                (UnitComplex::new(state.z) / UnitComplex::new(state_mean.z)).angle()
+            ] * vector![
+                measurement.x - measurement_mean.x,
+                measurement.y - measurement_mean.y,
+                (UnitComplex::new(measurement.z) / UnitComplex::new(measurement_mean.z)).angle()
+            ]
+            .transpose()
+        })
+        .sum::<Matrix3<f32>>()
+        * (1.0 / 6.0)
+}
+
 fn cross_covariance_from_2d_translation_sigma_points(
     state_mean: Vector3<f32>,
     state_sigma_points: &[Vector3<f32>],
@@ -1,18 +1,28 @@
-use std::{fs::File, sync::Arc};
+use std::{fs::File, path::PathBuf, sync::Arc};
 
+use clap::Parser;
 use color_eyre::{
     eyre::{Result, WrapErr},
     install,
 };
 use cyclers::run;
-use hulk::{nao, setup_logger};
+use hulk::{nao, recording::Recorder, setup_logger};
 use serde_json::from_reader;
 use tokio_util::sync::CancellationToken;
 use types::hardware::Interface;
 
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct CommandlineArguments {
+    /// Record all hardware inputs into this directory to replay them later
+    #[clap(long)]
+    record: Option<PathBuf>,
+}
+
 fn main() -> Result<()> {
     setup_logger(true)?;
     install()?;
+    let arguments = CommandlineArguments::parse();
     let keep_running = CancellationToken::new();
     ctrlc::set_handler({
         let keep_running = keep_running.clone();
@@ -25,6 +35,19 @@ fn main() -> Result<()> {
     let hardware_parameters = from_reader(file).wrap_err("failed to parse hardware parameters")?;
     let hardware_interface = nao::Interface::new(keep_running.clone(), hardware_parameters)
         .wrap_err("failed to create hardware interface")?;
+    match arguments.record {
+        Some(directory) => start(
+            Recorder::new(hardware_interface, directory).wrap_err("failed to create recorder")?,
+            keep_running,
+        ),
+        None => start(hardware_interface, keep_running),
+    }
+}
+
+fn start(
+    hardware_interface: impl Interface + Send + Sync + 'static,
+    keep_running: CancellationToken,
+) -> Result<()> {
     let ids = hardware_interface.get_ids();
     run(
         Arc::new(hardware_interface),
@@ -0,0 +1,45 @@
+use std::{path::PathBuf, sync::Arc};
+
+use clap::Parser;
+use color_eyre::{
+    eyre::{Result, WrapErr},
+    install,
+};
+use cyclers::run;
+use hulk::{recording::Replayer, setup_logger};
+use tokio_util::sync::CancellationToken;
+use types::hardware::Interface;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct CommandlineArguments {
+    /// Directory written by `--record` of the nao or webots binary
+    recording: PathBuf,
+    /// Directory containing the parameters the cyclers are started with
+    #[clap(long, default_value = "etc/configuration")]
+    parameters: PathBuf,
+}
+
+fn main() -> Result<()> {
+    setup_logger(true)?;
+    install()?;
+    let arguments = CommandlineArguments::parse();
+    let keep_running = CancellationToken::new();
+    ctrlc::set_handler({
+        let keep_running = keep_running.clone();
+        move || {
+            keep_running.cancel();
+        }
+    })?;
+    let hardware_interface = Replayer::new(&arguments.recording, keep_running.clone())
+        .wrap_err("failed to open recording")?;
+    let ids = hardware_interface.get_ids();
+    run(
+        Arc::new(hardware_interface),
+        Some("[::]:1337"),
+        arguments.parameters,
+        ids.body_id,
+        ids.head_id,
+        keep_running,
+    )
+}
@@ -4,6 +4,7 @@ use color_eyre::eyre::Result;
 
 #[cfg(feature = "nao")]
 pub mod nao;
+pub mod recording;
 #[cfg(feature = "webots")]
 pub mod webots;
 
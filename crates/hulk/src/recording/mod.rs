@@ -0,0 +1,6 @@
+mod recorder;
+mod replayer;
+mod stream;
+
+pub use recorder::Recorder;
+pub use replayer::Replayer;
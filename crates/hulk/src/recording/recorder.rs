@@ -0,0 +1,168 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::{create_dir_all, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use log::error;
+use parking_lot::Mutex;
+use serde_json::to_writer_pretty;
+use types::{
+    hardware::{self, Ids},
+    messages::{IncomingMessage, OutgoingMessage},
+    samples::Samples,
+    ycbcr422_image::YCbCr422Image,
+    CameraPosition, Joints, Leds, SensorData,
+};
+
+use super::stream::{
+    now_file_name_of_current_thread, PerceptionCycles, PerceptionInput, StreamWriter,
+    CAMERA_BOTTOM_FILE_NAME, CAMERA_TOP_FILE_NAME, IDS_FILE_NAME, MICROPHONES_FILE_NAME,
+    NETWORK_FILE_NAME, PERCEPTION_CYCLES_FILE_NAME, SENSOR_DATA_FILE_NAME,
+};
+
+/// Hardware interface wrapper that records every input of the wrapped interface into a directory
+///
+/// Each input (clock, sensors, microphones, network, cameras) is written into its own stream
+/// because the cyclers access them from different threads. The clock is requested by several
+/// cyclers and is therefore recorded into one stream per cycler instance. Every record is stamped
+/// with the time of the wrapped interface when the call returned. Along with the sensor data of
+/// each Control cycle, the number of cycles each perception cycler has finished is recorded, which
+/// allows the [`super::Replayer`] to pair the Control cycle with the same perception results.
+pub struct Recorder<Interface> {
+    interface: Interface,
+    directory: PathBuf,
+    now: Mutex<HashMap<String, StreamWriter>>,
+    requested_perception_inputs: Mutex<PerceptionCycles>,
+    sensor_data: StreamWriter,
+    perception_cycles: StreamWriter,
+    microphones: StreamWriter,
+    network: StreamWriter,
+    camera_top: StreamWriter,
+    camera_bottom: StreamWriter,
+}
+
+impl<Interface> Recorder<Interface>
+where
+    Interface: hardware::Interface,
+{
+    pub fn new(interface: Interface, directory: impl AsRef<Path>) -> Result<Self> {
+        let directory = directory.as_ref();
+        create_dir_all(directory).wrap_err("failed to create recording directory")?;
+        let ids_file =
+            File::create(directory.join(IDS_FILE_NAME)).wrap_err("failed to create ids file")?;
+        to_writer_pretty(ids_file, &interface.get_ids()).wrap_err("failed to write ids")?;
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            now: Default::default(),
+            requested_perception_inputs: Default::default(),
+            sensor_data: StreamWriter::create(directory.join(SENSOR_DATA_FILE_NAME))?,
+            perception_cycles: StreamWriter::create(directory.join(PERCEPTION_CYCLES_FILE_NAME))?,
+            microphones: StreamWriter::create(directory.join(MICROPHONES_FILE_NAME))?,
+            network: StreamWriter::create(directory.join(NETWORK_FILE_NAME))?,
+            camera_top: StreamWriter::create(directory.join(CAMERA_TOP_FILE_NAME))?,
+            camera_bottom: StreamWriter::create(directory.join(CAMERA_BOTTOM_FILE_NAME))?,
+            interface,
+        })
+    }
+
+    fn record_now(&self, now: SystemTime) -> Result<()> {
+        let mut streams = self.now.lock();
+        let stream = match streams.entry(now_file_name_of_current_thread()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let stream = StreamWriter::create(self.directory.join(entry.key()))?;
+                entry.insert(stream)
+            }
+        };
+        stream.write(now, &())
+    }
+
+    fn request_perception_input(&self, input: PerceptionInput) {
+        self.requested_perception_inputs.lock().increment(input);
+    }
+}
+
+impl<Interface> hardware::Interface for Recorder<Interface>
+where
+    Interface: hardware::Interface,
+{
+    fn read_from_microphones(&self) -> Result<Samples> {
+        self.request_perception_input(PerceptionInput::Microphones);
+        let samples = self.interface.read_from_microphones()?;
+        self.microphones
+            .write(self.interface.get_now(), &samples)
+            .wrap_err("failed to record microphone samples")?;
+        Ok(samples)
+    }
+
+    fn get_now(&self) -> SystemTime {
+        let now = self.interface.get_now();
+        if let Err(error) = self.record_now(now) {
+            error!("failed to record time: {error:?}");
+        }
+        now
+    }
+
+    fn get_ids(&self) -> Ids {
+        self.interface.get_ids()
+    }
+
+    fn read_from_sensors(&self) -> Result<SensorData> {
+        let sensor_data = self.interface.read_from_sensors()?;
+        let finished_perception_cycles = self
+            .requested_perception_inputs
+            .lock()
+            .finished_cycles_of_requested_inputs();
+        let now = self.interface.get_now();
+        self.sensor_data
+            .write(now, &sensor_data)
+            .wrap_err("failed to record sensor data")?;
+        self.perception_cycles
+            .write(now, &finished_perception_cycles)
+            .wrap_err("failed to record perception cycles")?;
+        Ok(sensor_data)
+    }
+
+    fn write_to_actuators(
+        &self,
+        positions: Joints<f32>,
+        stiffnesses: Joints<f32>,
+        leds: Leds,
+    ) -> Result<()> {
+        self.interface
+            .write_to_actuators(positions, stiffnesses, leds)
+    }
+
+    fn read_from_network(&self) -> Result<IncomingMessage> {
+        self.request_perception_input(PerceptionInput::Network);
+        let message = self.interface.read_from_network()?;
+        self.network
+            .write(self.interface.get_now(), &message)
+            .wrap_err("failed to record network message")?;
+        Ok(message)
+    }
+
+    fn write_to_network(&self, message: OutgoingMessage) -> Result<()> {
+        self.interface.write_to_network(message)
+    }
+
+    fn read_from_camera(&self, camera_position: CameraPosition) -> Result<YCbCr422Image> {
+        self.request_perception_input(match camera_position {
+            CameraPosition::Top => PerceptionInput::CameraTop,
+            CameraPosition::Bottom => PerceptionInput::CameraBottom,
+        });
+        let image = self.interface.read_from_camera(camera_position)?;
+        let stream = match camera_position {
+            CameraPosition::Top => &self.camera_top,
+            CameraPosition::Bottom => &self.camera_bottom,
+        };
+        stream
+            .write(self.interface.get_now(), &image)
+            .wrap_err("failed to record camera image")?;
+        Ok(image)
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result,
+};
+use log::error;
+use parking_lot::{Condvar, Mutex};
+use serde::de::DeserializeOwned;
+use serde_json::from_reader;
+use tokio_util::sync::CancellationToken;
+use types::{
+    hardware::{self, Ids},
+    messages::{IncomingMessage, OutgoingMessage},
+    samples::Samples,
+    ycbcr422_image::YCbCr422Image,
+    CameraPosition, Joints, Leds, SensorData,
+};
+
+use super::stream::{
+    now_file_name_of_current_thread, PerceptionCycles, PerceptionInput, Record, StreamReader,
+    CAMERA_BOTTOM_FILE_NAME, CAMERA_TOP_FILE_NAME, IDS_FILE_NAME, MICROPHONES_FILE_NAME,
+    NETWORK_FILE_NAME, PERCEPTION_CYCLES_FILE_NAME, SENSOR_DATA_FILE_NAME,
+};
+
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct Pairing {
+    /// Perception cycles the current Control cycle consumed in the recording
+    finished: PerceptionCycles,
+    requested: PerceptionCycles,
+}
+
+/// Hardware interface that replays a directory written by the [`super::Recorder`]
+///
+/// The sensor data drive the replay: before it is returned to Control, every perception cycler
+/// has to finish exactly the cycles the recorded Control cycle consumed. Microphone samples,
+/// network messages and camera images are held back until the Control cycle consuming their
+/// perception cycle has started. Thereby each Control cycle consumes the same perception results
+/// in every replay, independent of thread scheduling. The clock returns the timestamps recorded
+/// for the calling cycler instance. Actuator and network writes are discarded. Once the clock or
+/// the sensor data stream is exhausted, the replay cancels `keep_running`.
+pub struct Replayer {
+    ids: Ids,
+    keep_running: CancellationToken,
+    /// Latest replayed timestamp, returned once the clock stream of a cycler is exhausted
+    clock: Mutex<SystemTime>,
+    pairing: Mutex<Pairing>,
+    pairing_changed: Condvar,
+    directory: PathBuf,
+    now: Mutex<HashMap<String, StreamReader>>,
+    sensor_data: StreamReader,
+    perception_cycles: StreamReader,
+    microphones: StreamReader,
+    network: StreamReader,
+    camera_top: StreamReader,
+    camera_bottom: StreamReader,
+}
+
+impl Replayer {
+    pub fn new(directory: impl AsRef<Path>, keep_running: CancellationToken) -> Result<Self> {
+        let directory = directory.as_ref();
+        let ids_file =
+            File::open(directory.join(IDS_FILE_NAME)).wrap_err("failed to open ids file")?;
+        let ids = from_reader(ids_file).wrap_err("failed to parse ids")?;
+
+        Ok(Self {
+            ids,
+            keep_running,
+            clock: Mutex::new(UNIX_EPOCH),
+            pairing: Default::default(),
+            pairing_changed: Condvar::new(),
+            directory: directory.to_path_buf(),
+            now: Default::default(),
+            sensor_data: StreamReader::open(directory.join(SENSOR_DATA_FILE_NAME))?,
+            perception_cycles: StreamReader::open(directory.join(PERCEPTION_CYCLES_FILE_NAME))?,
+            microphones: StreamReader::open(directory.join(MICROPHONES_FILE_NAME))?,
+            network: StreamReader::open(directory.join(NETWORK_FILE_NAME))?,
+            camera_top: StreamReader::open(directory.join(CAMERA_TOP_FILE_NAME))?,
+            camera_bottom: StreamReader::open(directory.join(CAMERA_BOTTOM_FILE_NAME))?,
+        })
+    }
+
+    fn finish(&self) {
+        self.keep_running.cancel();
+        self.pairing_changed.notify_all();
+    }
+
+    fn read_now(&self) -> Result<Option<Record<()>>> {
+        let mut streams = self.now.lock();
+        let stream = match streams.entry(now_file_name_of_current_thread()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let stream = StreamReader::open(self.directory.join(entry.key()))?;
+                entry.insert(stream)
+            }
+        };
+        stream.read()
+    }
+
+    /// Releases the inputs of the perception cycles the Control cycle consumed and blocks until
+    /// these cycles are finished
+    fn wait_for_perception_cycles(&self, finished: PerceptionCycles) -> Result<()> {
+        let mut pairing = self.pairing.lock();
+        pairing.finished = finished;
+        self.pairing_changed.notify_all();
+        loop {
+            if self.keep_running.is_cancelled() {
+                bail!("termination requested");
+            }
+            if pairing
+                .requested
+                .finished_cycles_of_requested_inputs()
+                .reached(&finished)
+            {
+                return Ok(());
+            }
+            self.pairing_changed
+                .wait_for(&mut pairing, CANCELLATION_POLL_INTERVAL);
+        }
+    }
+
+    /// Blocks until the Control cycle consuming the perception cycle of the input has started
+    fn read_paired<T>(&self, input: PerceptionInput, stream: &StreamReader) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        {
+            let mut pairing = self.pairing.lock();
+            pairing.requested.increment(input);
+            self.pairing_changed.notify_all();
+            let cycle = pairing.requested.get(input);
+            loop {
+                if self.keep_running.is_cancelled() {
+                    bail!("termination requested");
+                }
+                if pairing.finished.get(input) >= cycle {
+                    break;
+                }
+                self.pairing_changed
+                    .wait_for(&mut pairing, CANCELLATION_POLL_INTERVAL);
+            }
+        }
+        stream
+            .read()?
+            .map(|record: Record<T>| record.value)
+            .ok_or_else(|| eyre!("end of recording reached"))
+    }
+}
+
+impl hardware::Interface for Replayer {
+    fn read_from_microphones(&self) -> Result<Samples> {
+        self.read_paired(PerceptionInput::Microphones, &self.microphones)
+            .wrap_err("failed to replay microphone samples")
+    }
+
+    fn get_now(&self) -> SystemTime {
+        match self.read_now() {
+            Ok(Some(record)) => {
+                let mut clock = self.clock.lock();
+                *clock = (*clock).max(record.timestamp);
+                record.timestamp
+            }
+            Ok(None) => {
+                self.finish();
+                *self.clock.lock()
+            }
+            Err(error) => {
+                error!("failed to replay time: {error:?}");
+                self.finish();
+                *self.clock.lock()
+            }
+        }
+    }
+
+    fn get_ids(&self) -> Ids {
+        self.ids.clone()
+    }
+
+    fn read_from_sensors(&self) -> Result<SensorData> {
+        let Some(perception_cycles) = self
+            .perception_cycles
+            .read()
+            .wrap_err("failed to replay perception cycles")?
+        else {
+            self.finish();
+            bail!("end of recording reached");
+        };
+        self.wait_for_perception_cycles(perception_cycles.value)?;
+        match self
+            .sensor_data
+            .read()
+            .wrap_err("failed to replay sensor data")?
+        {
+            Some(record) => Ok(record.value),
+            None => {
+                self.finish();
+                bail!("end of recording reached");
+            }
+        }
+    }
+
+    fn write_to_actuators(
+        &self,
+        _positions: Joints<f32>,
+        _stiffnesses: Joints<f32>,
+        _leds: Leds,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_from_network(&self) -> Result<IncomingMessage> {
+        self.read_paired(PerceptionInput::Network, &self.network)
+            .wrap_err("failed to replay network message")
+    }
+
+    fn write_to_network(&self, _message: OutgoingMessage) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_from_camera(&self, camera_position: CameraPosition) -> Result<YCbCr422Image> {
+        let (input, stream) = match camera_position {
+            CameraPosition::Top => (PerceptionInput::CameraTop, &self.camera_top),
+            CameraPosition::Bottom => (PerceptionInput::CameraBottom, &self.camera_bottom),
+        };
+        self.read_paired(input, stream)
+            .wrap_err("failed to replay camera image")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use types::hardware::Interface;
+
+    use crate::recording::Recorder;
+
+    use super::*;
+
+    struct FakeInterface;
+
+    impl Interface for FakeInterface {
+        fn read_from_microphones(&self) -> Result<Samples> {
+            Ok(Default::default())
+        }
+
+        fn get_now(&self) -> SystemTime {
+            UNIX_EPOCH
+        }
+
+        fn get_ids(&self) -> Ids {
+            Ids {
+                body_id: "body".to_string(),
+                head_id: "head".to_string(),
+            }
+        }
+
+        fn read_from_sensors(&self) -> Result<SensorData> {
+            Ok(Default::default())
+        }
+
+        fn write_to_actuators(
+            &self,
+            _positions: Joints<f32>,
+            _stiffnesses: Joints<f32>,
+            _leds: Leds,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_from_network(&self) -> Result<IncomingMessage> {
+            Ok(Default::default())
+        }
+
+        fn write_to_network(&self, _message: OutgoingMessage) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_from_camera(&self, _camera_position: CameraPosition) -> Result<YCbCr422Image> {
+            Ok(YCbCr422Image::zero(4, 2))
+        }
+    }
+
+    #[test]
+    fn control_cycles_consume_recorded_perception_cycles() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let recorder = Recorder::new(FakeInterface, directory.path()).unwrap();
+            for number_of_images in [0, 1, 2, 1] {
+                for _ in 0..number_of_images {
+                    recorder.read_from_camera(CameraPosition::Top).unwrap();
+                }
+                recorder.read_from_sensors().unwrap();
+            }
+        }
+
+        let replayer = Arc::new(Replayer::new(directory.path(), CancellationToken::new()).unwrap());
+        assert_eq!(replayer.get_ids().body_id, "body");
+        let returned_images = Arc::new(Mutex::new(0));
+        let vision = thread::spawn({
+            let replayer = replayer.clone();
+            let returned_images = returned_images.clone();
+            move || {
+                while replayer.read_from_camera(CameraPosition::Top).is_ok() {
+                    *returned_images.lock() += 1;
+                }
+            }
+        });
+
+        let mut consumed_images = Vec::new();
+        while replayer.read_from_sensors().is_ok() {
+            consumed_images.push(*returned_images.lock());
+        }
+        vision.join().unwrap();
+
+        assert_eq!(consumed_images, vec![0, 0, 2, 3]);
+        assert!(replayer.keep_running.is_cancelled());
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter},
+    path::Path,
+    thread,
+    time::SystemTime,
+};
+
+use bincode::{deserialize_from, serialize_into};
+use color_eyre::{eyre::WrapErr, Result};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub const IDS_FILE_NAME: &str = "ids.json";
+pub const SENSOR_DATA_FILE_NAME: &str = "sensor_data.bincode";
+pub const MICROPHONES_FILE_NAME: &str = "microphones.bincode";
+pub const NETWORK_FILE_NAME: &str = "network.bincode";
+pub const CAMERA_TOP_FILE_NAME: &str = "camera_top.bincode";
+pub const CAMERA_BOTTOM_FILE_NAME: &str = "camera_bottom.bincode";
+pub const PERCEPTION_CYCLES_FILE_NAME: &str = "perception_cycles.bincode";
+
+/// Each cycler instance reads the clock from its own thread, the thread name identifies the
+/// instance and thereby its clock stream
+pub fn now_file_name_of_current_thread() -> String {
+    let instance_name = thread::current().name().unwrap_or("unnamed").to_lowercase();
+    format!("now_{instance_name}.bincode")
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PerceptionInput {
+    Microphones,
+    Network,
+    CameraTop,
+    CameraBottom,
+}
+
+/// Count per perception input, e.g. of the inputs requested by the perception cyclers
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PerceptionCycles {
+    pub microphones: usize,
+    pub network: usize,
+    pub camera_top: usize,
+    pub camera_bottom: usize,
+}
+
+impl PerceptionCycles {
+    pub fn get(&self, input: PerceptionInput) -> usize {
+        match input {
+            PerceptionInput::Microphones => self.microphones,
+            PerceptionInput::Network => self.network,
+            PerceptionInput::CameraTop => self.camera_top,
+            PerceptionInput::CameraBottom => self.camera_bottom,
+        }
+    }
+
+    pub fn increment(&mut self, input: PerceptionInput) {
+        match input {
+            PerceptionInput::Microphones => self.microphones += 1,
+            PerceptionInput::Network => self.network += 1,
+            PerceptionInput::CameraTop => self.camera_top += 1,
+            PerceptionInput::CameraBottom => self.camera_bottom += 1,
+        }
+    }
+
+    /// A perception cycler finished its previous cycle once it requests the input of the next one
+    pub fn finished_cycles_of_requested_inputs(&self) -> Self {
+        Self {
+            microphones: self.microphones.saturating_sub(1),
+            network: self.network.saturating_sub(1),
+            camera_top: self.camera_top.saturating_sub(1),
+            camera_bottom: self.camera_bottom.saturating_sub(1),
+        }
+    }
+
+    pub fn reached(&self, other: &Self) -> bool {
+        self.microphones >= other.microphones
+            && self.network >= other.network
+            && self.camera_top >= other.camera_top
+            && self.camera_bottom >= other.camera_bottom
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Record<T> {
+    pub timestamp: SystemTime,
+    pub value: T,
+}
+
+pub struct StreamWriter {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl StreamWriter {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::create(&path)
+            .wrap_err_with(|| format!("failed to create {}", path.as_ref().display()))?;
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    pub fn write<T>(&self, timestamp: SystemTime, value: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let mut writer = self.writer.lock();
+        serialize_into(&mut *writer, &Record { timestamp, value })
+            .wrap_err("failed to serialize record")
+    }
+}
+
+pub struct StreamReader {
+    reader: Mutex<BufReader<File>>,
+}
+
+impl StreamReader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(&path)
+            .wrap_err_with(|| format!("failed to open {}", path.as_ref().display()))?;
+        Ok(Self {
+            reader: Mutex::new(BufReader::new(file)),
+        })
+    }
+
+    /// Returns the next record or `None` if the end of the stream has been reached
+    pub fn read<T>(&self) -> Result<Option<Record<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let mut reader = self.reader.lock();
+        if reader
+            .fill_buf()
+            .wrap_err("failed to read from stream")?
+            .is_empty()
+        {
+            return Ok(None);
+        }
+        deserialize_from(&mut *reader)
+            .map(Some)
+            .wrap_err("failed to deserialize record")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn written_records_are_read_back_in_order() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join(SENSOR_DATA_FILE_NAME);
+        {
+            let writer = StreamWriter::create(&path).unwrap();
+            writer.write(UNIX_EPOCH, &vec![1.0f32, 2.0]).unwrap();
+            writer
+                .write(UNIX_EPOCH + Duration::from_millis(12), &vec![3.0f32])
+                .unwrap();
+        }
+
+        let reader = StreamReader::open(&path).unwrap();
+        let first = reader.read::<Vec<f32>>().unwrap().unwrap();
+        assert_eq!(first.timestamp, UNIX_EPOCH);
+        assert_eq!(first.value, vec![1.0, 2.0]);
+        let second = reader.read::<Vec<f32>>().unwrap().unwrap();
+        assert_eq!(second.timestamp, UNIX_EPOCH + Duration::from_millis(12));
+        assert_eq!(second.value, vec![3.0]);
+        assert!(reader.read::<Vec<f32>>().unwrap().is_none());
+    }
+}
@@ -1,10 +1,12 @@
 mod condition;
 pub mod fallen_abort_condition;
+mod motion2;
 pub mod motion_file;
 pub mod motion_interpolator;
 pub mod spline_interpolator;
 pub mod stabilized_condition;
 pub mod timed_spline;
+pub mod validation;
 
 pub use condition::{Condition, ContinuousConditionType, DiscreteConditionType, Response, TimeOut};
 pub use fallen_abort_condition::FallenAbort;
@@ -13,3 +15,4 @@ pub use motion_interpolator::MotionInterpolator;
 pub use spline_interpolator::SplineInterpolator;
 pub use stabilized_condition::StabilizedCondition;
 pub use timed_spline::TimedSpline;
+pub use validation::{named_joints, nao_joint_limits, ValidationError};
@@ -0,0 +1,364 @@
+use std::{fs::read_to_string, path::Path, time::Duration};
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use serde::Deserialize;
+use splines::Interpolation;
+use types::{ArmJoints, HeadJoints, Joints, LegJoints};
+
+use crate::{KeyFrame, MotionFile, MotionFileFrame};
+
+const NUMBER_OF_JOINTS: usize = 26;
+
+/// Motion exported by NAOqi tools, positions are listed in the order of `header.joints`
+///
+/// Older files list the keyframes in `position`, newer files wrap them into `commands`.
+#[derive(Debug, Deserialize)]
+struct Motion2 {
+    header: Motion2Header,
+    #[serde(default)]
+    position: Vec<Motion2Position>,
+    #[serde(default)]
+    commands: Vec<Motion2CommandEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Motion2Header {
+    joints: Vec<usize>,
+    /// Total duration in milliseconds
+    time: f32,
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Motion2Position {
+    parameters: Vec<f32>,
+    /// Relative duration to reach these positions, scaled to the total duration of the header
+    time: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Motion2CommandEntry {
+    command: Motion2Command,
+}
+
+#[derive(Debug, Deserialize)]
+struct Motion2Command {
+    #[serde(rename = "type")]
+    command_type: u32,
+    parameters: Vec<f32>,
+    /// Relative duration to reach these positions, scaled to the total duration of the header
+    time: f32,
+}
+
+/// Command type which moves the joints to the positions of the parameters
+const POSITION_COMMAND_TYPE: u32 = 0;
+
+impl Motion2 {
+    fn into_positions(self) -> Result<Vec<Motion2Position>> {
+        if !self.position.is_empty() && !self.commands.is_empty() {
+            bail!("motion contains both positions and commands");
+        }
+        if self.commands.is_empty() {
+            return Ok(self.position);
+        }
+        self.commands
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let command = entry.command;
+                if command.command_type != POSITION_COMMAND_TYPE {
+                    bail!(
+                        "unsupported type {} of command {index}",
+                        command.command_type
+                    );
+                }
+                Ok(Motion2Position {
+                    parameters: command.parameters,
+                    time: command.time,
+                })
+            })
+            .collect()
+    }
+}
+
+impl MotionFile<Joints<f32>> {
+    /// Loads a motion file depending on its extension, poses are approached within the given
+    /// duration
+    pub fn from_any_path(path: impl AsRef<Path>, pose_duration: Duration) -> Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_path(path),
+            Some("motion2") => Self::from_motion2_path(path),
+            Some("pose") => Self::from_pose_path(path, pose_duration),
+            _ => bail!("unsupported motion file extension of {path:?}"),
+        }
+    }
+
+    /// The first position of the motion becomes the initial positions, all other positions
+    /// become keyframes of a single frame
+    pub fn from_motion2_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = read_to_string(path)
+            .wrap_err_with(|| format!("failed to read motion2 file {path:?}"))?;
+        parse_motion2(&contents).wrap_err_with(|| format!("failed to parse motion2 file {path:?}"))
+    }
+
+    /// Creates a motion which moves to the pose within the given duration
+    pub fn from_pose_path(path: impl AsRef<Path>, duration: Duration) -> Result<Self> {
+        let path = path.as_ref();
+        let contents =
+            read_to_string(path).wrap_err_with(|| format!("failed to read pose file {path:?}"))?;
+        let pose = parse_pose(&contents)
+            .wrap_err_with(|| format!("failed to parse pose file {path:?}"))?;
+        Ok(Self {
+            interpolation_mode: Interpolation::Linear,
+            initial_positions: pose,
+            motion: vec![MotionFileFrame {
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned()),
+                entry_condition: None,
+                interrupt_conditions: vec![],
+                keyframes: vec![KeyFrame {
+                    duration,
+                    positions: pose,
+                }],
+                exit_condition: None,
+            }],
+        })
+    }
+}
+
+fn parse_motion2(contents: &str) -> Result<MotionFile<Joints<f32>>> {
+    let motion: Motion2 = serde_json::from_str(contents).wrap_err("failed to parse JSON")?;
+    if motion.header.joints.len() != NUMBER_OF_JOINTS {
+        bail!(
+            "expected {NUMBER_OF_JOINTS} joints in header but got {}",
+            motion.header.joints.len()
+        );
+    }
+    let mut order = [None; NUMBER_OF_JOINTS];
+    for (position_index, &joint_index) in motion.header.joints.iter().enumerate() {
+        let slot = order
+            .get_mut(joint_index)
+            .ok_or_else(|| eyre!("joint index {joint_index} in header is out of range"))?;
+        if slot.replace(position_index).is_some() {
+            bail!("joint index {joint_index} appears twice in header");
+        }
+    }
+
+    let total_time = motion.header.time;
+    if Duration::try_from_secs_f32(total_time / 1000.0).is_err() {
+        bail!("header time {total_time} is not a valid duration");
+    }
+    let title = motion.header.title.clone();
+    let motion_positions = motion.into_positions()?;
+    let total_relative_time: f32 = motion_positions.iter().map(|position| position.time).sum();
+    if total_relative_time <= 0.0 {
+        bail!("positions have no duration");
+    }
+    let mut positions = motion_positions
+        .iter()
+        .enumerate()
+        .map(|(index, position)| {
+            if position.parameters.len() != NUMBER_OF_JOINTS {
+                bail!(
+                    "expected {NUMBER_OF_JOINTS} parameters in position {index} but got {}",
+                    position.parameters.len()
+                );
+            }
+            let mut angles = [0.0; NUMBER_OF_JOINTS];
+            for (joint_index, angle) in angles.iter_mut().enumerate() {
+                // all slots are filled because the header has as many unique indices as joints
+                *angle = position.parameters[order[joint_index].unwrap()];
+            }
+            let Ok(duration) = Duration::try_from_secs_f32(
+                position.time / total_relative_time * total_time / 1000.0,
+            ) else {
+                bail!(
+                    "time {} of position {index} is not a valid duration",
+                    position.time
+                );
+            };
+            Ok((duration, joints_from_naoqi_order(angles)))
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter();
+
+    let (_, initial_positions) = positions
+        .next()
+        .ok_or_else(|| eyre!("motion has no positions"))?;
+    Ok(MotionFile {
+        interpolation_mode: Interpolation::Linear,
+        initial_positions,
+        motion: vec![MotionFileFrame {
+            name: title,
+            entry_condition: None,
+            interrupt_conditions: vec![],
+            keyframes: positions
+                .map(|(duration, positions)| KeyFrame {
+                    duration,
+                    positions,
+                })
+                .collect(),
+            exit_condition: None,
+        }],
+    })
+}
+
+/// Pose files contain one angle per line in the NAOqi joint order
+fn parse_pose(contents: &str) -> Result<Joints<f32>> {
+    let angles = contents
+        .split_whitespace()
+        .map(|angle| {
+            angle
+                .parse()
+                .wrap_err_with(|| format!("failed to parse angle {angle:?}"))
+        })
+        .collect::<Result<Vec<f32>>>()?;
+    let angles: [f32; NUMBER_OF_JOINTS] = angles.try_into().map_err(|angles: Vec<f32>| {
+        eyre!(
+            "expected {NUMBER_OF_JOINTS} angles but got {}",
+            angles.len()
+        )
+    })?;
+    Ok(joints_from_naoqi_order(angles))
+}
+
+fn joints_from_naoqi_order(angles: [f32; NUMBER_OF_JOINTS]) -> Joints<f32> {
+    Joints {
+        head: HeadJoints {
+            yaw: angles[0],
+            pitch: angles[1],
+        },
+        left_arm: ArmJoints {
+            shoulder_pitch: angles[2],
+            shoulder_roll: angles[3],
+            elbow_yaw: angles[4],
+            elbow_roll: angles[5],
+            wrist_yaw: angles[6],
+            hand: angles[7],
+        },
+        left_leg: LegJoints {
+            hip_yaw_pitch: angles[8],
+            hip_roll: angles[9],
+            hip_pitch: angles[10],
+            knee_pitch: angles[11],
+            ankle_pitch: angles[12],
+            ankle_roll: angles[13],
+        },
+        right_leg: LegJoints {
+            hip_yaw_pitch: angles[14],
+            hip_roll: angles[15],
+            hip_pitch: angles[16],
+            knee_pitch: angles[17],
+            ankle_pitch: angles[18],
+            ankle_roll: angles[19],
+        },
+        right_arm: ArmJoints {
+            shoulder_pitch: angles[20],
+            shoulder_roll: angles[21],
+            elbow_yaw: angles[22],
+            elbow_roll: angles[23],
+            wrist_yaw: angles[24],
+            hand: angles[25],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_joint_order_is_resolved() {
+        let mut joints: Vec<usize> = (0..NUMBER_OF_JOINTS).collect();
+        joints.swap(0, 25);
+        let parameters: Vec<f32> = (0..NUMBER_OF_JOINTS).map(|index| index as f32).collect();
+        let contents = serde_json::json!({
+            "header": { "joints": joints, "time": 3000, "title": "test", "version": "2.0" },
+            "position": [
+                { "parameters": parameters, "time": 1000 },
+                { "parameters": parameters, "time": 2000 },
+            ],
+        })
+        .to_string();
+
+        let motion_file = parse_motion2(&contents).unwrap();
+
+        assert_eq!(motion_file.initial_positions.head.yaw, 25.0);
+        assert_eq!(motion_file.initial_positions.right_arm.hand, 0.0);
+        assert_eq!(motion_file.initial_positions.left_leg.hip_yaw_pitch, 8.0);
+        assert_eq!(motion_file.motion[0].keyframes.len(), 1);
+        assert_eq!(
+            motion_file.motion[0].keyframes[0].duration,
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn commands_are_read_like_positions() {
+        let joints: Vec<usize> = (0..NUMBER_OF_JOINTS).collect();
+        let parameters: Vec<f32> = (0..NUMBER_OF_JOINTS).map(|index| index as f32).collect();
+        let contents = serde_json::json!({
+            "header": { "joints": joints, "time": 3000, "title": "test", "version": "2.0" },
+            "commands": [
+                { "command": { "type": 0, "parameters": parameters, "time": 1 } },
+                { "command": { "type": 0, "parameters": parameters, "time": 2 } },
+            ],
+        })
+        .to_string();
+
+        let motion_file = parse_motion2(&contents).unwrap();
+
+        assert_eq!(motion_file.initial_positions.right_arm.hand, 25.0);
+        assert_eq!(motion_file.motion[0].keyframes.len(), 1);
+        assert_eq!(
+            motion_file.motion[0].keyframes[0].duration,
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn negative_times_are_rejected() {
+        let joints: Vec<usize> = (0..NUMBER_OF_JOINTS).collect();
+        let parameters: Vec<f32> = vec![0.0; NUMBER_OF_JOINTS];
+        let contents_with_times = |header_time: f32, position_times: [f32; 3]| {
+            serde_json::json!({
+                "header": { "joints": joints, "time": header_time, "title": "test" },
+                "position": position_times
+                    .map(|time| serde_json::json!({ "parameters": parameters, "time": time })),
+            })
+            .to_string()
+        };
+
+        assert!(parse_motion2(&contents_with_times(3000.0, [1.0, 1.0, 1.0])).is_ok());
+        assert!(parse_motion2(&contents_with_times(3000.0, [1.0, -1.0, 3.0])).is_err());
+        assert!(parse_motion2(&contents_with_times(-3000.0, [1.0, 1.0, 1.0])).is_err());
+    }
+
+    #[test]
+    fn all_shipped_motion2_files_are_converted() {
+        let motions_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../etc/motions");
+        let mut number_of_files = 0;
+        for entry in std::fs::read_dir(&motions_directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("motion2") {
+                continue;
+            }
+            if let Err(error) = MotionFile::from_motion2_path(&path) {
+                panic!("{error:?}");
+            }
+            number_of_files += 1;
+        }
+        assert!(number_of_files > 0);
+    }
+
+    #[test]
+    fn pose_needs_all_joints() {
+        let pose = parse_pose(&"0.5\n".repeat(NUMBER_OF_JOINTS)).unwrap();
+        assert_eq!(pose.right_arm.hand, 0.5);
+
+        assert!(parse_pose(&"0.5\n".repeat(NUMBER_OF_JOINTS - 1)).is_err());
+    }
+}
@@ -4,13 +4,16 @@ use std::{fs::File, path::Path, time::Duration};
 use color_eyre::eyre::{Result, WrapErr};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::from_reader;
+use serialize_hierarchy::SerializeHierarchy;
 use splines::{Interpolate, Interpolation};
 
 use crate::condition::{ContinuousConditionType, DiscreteConditionType};
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+#[serialize_hierarchy(bound = "T: SerializeHierarchy + Serialize, for<'de> T: Deserialize<'de>")]
 pub struct MotionFile<T> {
     #[serde(default)]
+    #[serialize_hierarchy(leaf)]
     pub interpolation_mode: Interpolation<Duration, T>,
     pub initial_positions: T,
     pub motion: Vec<MotionFileFrame<T>>,
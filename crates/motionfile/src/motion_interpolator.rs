@@ -5,7 +5,7 @@ use crate::condition::{ContinuousConditionType, DiscreteConditionType, Response,
 use crate::timed_spline::{InterpolatorError, TimedSpline};
 use crate::Condition;
 use crate::MotionFile;
-use color_eyre::{Report, Result};
+use color_eyre::{eyre::eyre, Report, Result};
 use itertools::Itertools;
 use splines::Interpolate;
 use types::ConditionInput;
@@ -232,7 +232,10 @@ impl<T: Debug + Interpolate<f32>> TryFrom<MotionFile<T>> for MotionInterpolator<
     fn try_from(motion_file: MotionFile<T>) -> Result<Self> {
         let interpolation_mode = motion_file.interpolation_mode;
 
-        let first_frame = motion_file.motion.first().unwrap();
+        let first_frame = motion_file
+            .motion
+            .first()
+            .ok_or_else(|| eyre!("motion file has no frames"))?;
 
         let mut motion_frames = vec![ConditionedSpline {
             entry_condition: first_frame.entry_condition.clone(),
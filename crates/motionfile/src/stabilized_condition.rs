@@ -29,6 +29,14 @@ where
     Ok(Duration::from_secs_f32(f32::deserialize(deserializer)?))
 }
 
+impl StabilizedCondition {
+    /// The angular velocity never drops below a non-positive tolerance and a zero timeout aborts
+    /// before the condition is evaluated
+    pub fn is_satisfiable(&self) -> bool {
+        self.tolerance > 0.0 && !self.timeout_duration.is_zero()
+    }
+}
+
 impl Condition for StabilizedCondition {
     fn evaluate(&self, condition_input: &ConditionInput) -> Response {
         if condition_input.filtered_angular_velocity.norm() < self.tolerance {
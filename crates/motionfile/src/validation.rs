@@ -0,0 +1,350 @@
+use std::{ops::RangeInclusive, time::Duration};
+
+use thiserror::Error;
+use types::{ArmJoints, HeadJoints, Joints, LegJoints};
+
+use crate::{condition::DiscreteConditionType, MotionFile};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ValidationError {
+    #[error("motion has no frames")]
+    NoFrames,
+    #[error("frame {frame} has no keyframes")]
+    NoKeyFrames { frame: usize },
+    #[error("keyframe {keyframe} of frame {frame} has zero duration")]
+    ZeroDuration { frame: usize, keyframe: usize },
+    #[error("{joint} is {angle} in {location}, exceeding the limits {limits:?}")]
+    JointLimitExceeded {
+        location: String,
+        joint: &'static str,
+        angle: f32,
+        limits: RangeInclusive<f32>,
+    },
+    #[error("{kind} condition of frame {frame} can never be satisfied")]
+    UnsatisfiableCondition { frame: usize, kind: &'static str },
+}
+
+/// Joint limits of the NAO V6 in radians, hands are opened between 0 and 1
+pub fn nao_joint_limits() -> Joints<RangeInclusive<f32>> {
+    let leg = |side_sign: f32| LegJoints {
+        hip_yaw_pitch: -1.145303..=0.740810,
+        hip_roll: if side_sign > 0.0 {
+            -0.379472..=0.790477
+        } else {
+            -0.790477..=0.379472
+        },
+        hip_pitch: -1.535889..=0.484090,
+        knee_pitch: -0.092346..=2.112528,
+        ankle_pitch: -1.189516..=0.922747,
+        ankle_roll: if side_sign > 0.0 {
+            -0.397880..=0.769001
+        } else {
+            -0.769001..=0.397880
+        },
+    };
+    let arm = |side_sign: f32| ArmJoints {
+        shoulder_pitch: -2.0857..=2.0857,
+        shoulder_roll: if side_sign > 0.0 {
+            -0.3142..=1.3265
+        } else {
+            -1.3265..=0.3142
+        },
+        elbow_yaw: -2.0857..=2.0857,
+        elbow_roll: if side_sign > 0.0 {
+            -1.5446..=-0.0349
+        } else {
+            0.0349..=1.5446
+        },
+        wrist_yaw: -1.8238..=1.8238,
+        hand: 0.0..=1.0,
+    };
+    Joints {
+        head: HeadJoints {
+            yaw: -2.0857..=2.0857,
+            pitch: -0.706858..=0.637045,
+        },
+        left_arm: arm(1.0),
+        right_arm: arm(-1.0),
+        left_leg: leg(1.0),
+        right_leg: leg(-1.0),
+    }
+}
+
+impl MotionFile<Joints<f32>> {
+    /// Joint angles may exceed the limits by the tolerance because the joints are clamped by the
+    /// hardware anyway
+    pub fn validate(
+        &self,
+        joint_limits: &Joints<RangeInclusive<f32>>,
+        tolerance: f32,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if self.motion.is_empty() {
+            errors.push(ValidationError::NoFrames);
+        }
+        check_joint_limits(
+            "initial positions".to_string(),
+            &self.initial_positions,
+            joint_limits,
+            tolerance,
+            &mut errors,
+        );
+        for (frame_index, frame) in self.motion.iter().enumerate() {
+            if frame.keyframes.is_empty() {
+                errors.push(ValidationError::NoKeyFrames { frame: frame_index });
+            }
+            for (keyframe_index, keyframe) in frame.keyframes.iter().enumerate() {
+                if keyframe.duration == Duration::ZERO {
+                    errors.push(ValidationError::ZeroDuration {
+                        frame: frame_index,
+                        keyframe: keyframe_index,
+                    });
+                }
+                check_joint_limits(
+                    format!("keyframe {keyframe_index} of frame {frame_index}"),
+                    &keyframe.positions,
+                    joint_limits,
+                    tolerance,
+                    &mut errors,
+                );
+            }
+            for (kind, condition) in [
+                ("entry", &frame.entry_condition),
+                ("exit", &frame.exit_condition),
+            ] {
+                if let Some(condition) = condition {
+                    if !is_satisfiable(condition) {
+                        errors.push(ValidationError::UnsatisfiableCondition {
+                            frame: frame_index,
+                            kind,
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn is_satisfiable(condition: &DiscreteConditionType) -> bool {
+    match condition {
+        DiscreteConditionType::StabilizedCondition(condition) => condition.is_satisfiable(),
+    }
+}
+
+fn check_joint_limits(
+    location: String,
+    positions: &Joints<f32>,
+    joint_limits: &Joints<RangeInclusive<f32>>,
+    tolerance: f32,
+    errors: &mut Vec<ValidationError>,
+) {
+    for (joint, angle, limits) in named_joints(positions, joint_limits) {
+        let extended_limits = limits.start() - tolerance..=limits.end() + tolerance;
+        if !extended_limits.contains(&angle) {
+            errors.push(ValidationError::JointLimitExceeded {
+                location: location.clone(),
+                joint,
+                angle,
+                limits: limits.clone(),
+            });
+        }
+    }
+}
+
+/// Pairs each joint position with its name and limits, e.g. `left_leg.knee_pitch`
+pub fn named_joints<'a>(
+    positions: &Joints<f32>,
+    limits: &'a Joints<RangeInclusive<f32>>,
+) -> [(&'static str, f32, &'a RangeInclusive<f32>); 26] {
+    [
+        ("head.yaw", positions.head.yaw, &limits.head.yaw),
+        ("head.pitch", positions.head.pitch, &limits.head.pitch),
+        (
+            "left_arm.shoulder_pitch",
+            positions.left_arm.shoulder_pitch,
+            &limits.left_arm.shoulder_pitch,
+        ),
+        (
+            "left_arm.shoulder_roll",
+            positions.left_arm.shoulder_roll,
+            &limits.left_arm.shoulder_roll,
+        ),
+        (
+            "left_arm.elbow_yaw",
+            positions.left_arm.elbow_yaw,
+            &limits.left_arm.elbow_yaw,
+        ),
+        (
+            "left_arm.elbow_roll",
+            positions.left_arm.elbow_roll,
+            &limits.left_arm.elbow_roll,
+        ),
+        (
+            "left_arm.wrist_yaw",
+            positions.left_arm.wrist_yaw,
+            &limits.left_arm.wrist_yaw,
+        ),
+        (
+            "left_arm.hand",
+            positions.left_arm.hand,
+            &limits.left_arm.hand,
+        ),
+        (
+            "left_leg.hip_yaw_pitch",
+            positions.left_leg.hip_yaw_pitch,
+            &limits.left_leg.hip_yaw_pitch,
+        ),
+        (
+            "left_leg.hip_roll",
+            positions.left_leg.hip_roll,
+            &limits.left_leg.hip_roll,
+        ),
+        (
+            "left_leg.hip_pitch",
+            positions.left_leg.hip_pitch,
+            &limits.left_leg.hip_pitch,
+        ),
+        (
+            "left_leg.knee_pitch",
+            positions.left_leg.knee_pitch,
+            &limits.left_leg.knee_pitch,
+        ),
+        (
+            "left_leg.ankle_pitch",
+            positions.left_leg.ankle_pitch,
+            &limits.left_leg.ankle_pitch,
+        ),
+        (
+            "left_leg.ankle_roll",
+            positions.left_leg.ankle_roll,
+            &limits.left_leg.ankle_roll,
+        ),
+        (
+            "right_leg.hip_yaw_pitch",
+            positions.right_leg.hip_yaw_pitch,
+            &limits.right_leg.hip_yaw_pitch,
+        ),
+        (
+            "right_leg.hip_roll",
+            positions.right_leg.hip_roll,
+            &limits.right_leg.hip_roll,
+        ),
+        (
+            "right_leg.hip_pitch",
+            positions.right_leg.hip_pitch,
+            &limits.right_leg.hip_pitch,
+        ),
+        (
+            "right_leg.knee_pitch",
+            positions.right_leg.knee_pitch,
+            &limits.right_leg.knee_pitch,
+        ),
+        (
+            "right_leg.ankle_pitch",
+            positions.right_leg.ankle_pitch,
+            &limits.right_leg.ankle_pitch,
+        ),
+        (
+            "right_leg.ankle_roll",
+            positions.right_leg.ankle_roll,
+            &limits.right_leg.ankle_roll,
+        ),
+        (
+            "right_arm.shoulder_pitch",
+            positions.right_arm.shoulder_pitch,
+            &limits.right_arm.shoulder_pitch,
+        ),
+        (
+            "right_arm.shoulder_roll",
+            positions.right_arm.shoulder_roll,
+            &limits.right_arm.shoulder_roll,
+        ),
+        (
+            "right_arm.elbow_yaw",
+            positions.right_arm.elbow_yaw,
+            &limits.right_arm.elbow_yaw,
+        ),
+        (
+            "right_arm.elbow_roll",
+            positions.right_arm.elbow_roll,
+            &limits.right_arm.elbow_roll,
+        ),
+        (
+            "right_arm.wrist_yaw",
+            positions.right_arm.wrist_yaw,
+            &limits.right_arm.wrist_yaw,
+        ),
+        (
+            "right_arm.hand",
+            positions.right_arm.hand,
+            &limits.right_arm.hand,
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use splines::Interpolation;
+
+    use crate::{KeyFrame, MotionFileFrame};
+
+    use super::*;
+
+    #[test]
+    fn broken_motion_reports_all_errors() {
+        let mut positions = Joints::default();
+        positions.left_arm.elbow_roll = -0.5;
+        positions.right_arm.elbow_roll = 0.5;
+        let mut out_of_limits = positions;
+        out_of_limits.left_leg.knee_pitch = 3.0;
+        let motion_file = MotionFile {
+            interpolation_mode: Interpolation::Linear,
+            initial_positions: positions,
+            motion: vec![
+                MotionFileFrame {
+                    name: None,
+                    entry_condition: None,
+                    interrupt_conditions: vec![],
+                    keyframes: vec![
+                        KeyFrame {
+                            duration: Duration::from_secs(1),
+                            positions,
+                        },
+                        KeyFrame {
+                            duration: Duration::ZERO,
+                            positions: out_of_limits,
+                        },
+                    ],
+                    exit_condition: None,
+                },
+                MotionFileFrame {
+                    name: None,
+                    entry_condition: None,
+                    interrupt_conditions: vec![],
+                    keyframes: vec![],
+                    exit_condition: None,
+                },
+            ],
+        };
+
+        let errors = motion_file.validate(&nao_joint_limits(), 0.05);
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[0],
+            ValidationError::ZeroDuration {
+                frame: 0,
+                keyframe: 1
+            }
+        );
+        assert!(matches!(
+            errors[1],
+            ValidationError::JointLimitExceeded {
+                joint: "left_leg.knee_pitch",
+                ..
+            }
+        ));
+        assert_eq!(errors[2], ValidationError::NoKeyFrames { frame: 1 });
+    }
+}
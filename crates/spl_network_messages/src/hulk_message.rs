@@ -0,0 +1,172 @@
+use std::mem::size_of;
+
+use color_eyre::{eyre::bail, Report, Result};
+use nalgebra::{Isometry2, Matrix3};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::RoboCupGameControlReturnData, BallPosition, GameControllerReturnMessage, PlayerNumber,
+};
+
+/// Incremented whenever the layout of the extension changes, robots with different versions ignore
+/// each other's messages
+const HULK_MESSAGE_VERSION: u8 = 2;
+
+/// The version, whether the ball is an own detection, the upper triangle of the pose covariance
+/// and the localization quality are appended to the return data
+const EXTENSION_HEADER_SIZE: usize = 2 * size_of::<u8>();
+const EXTENSION_SIZE: usize = EXTENSION_HEADER_SIZE + 7 * size_of::<f32>();
+
+/// Message broadcast to the team, it extends the GameController return data by the confidence of
+/// the localization
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct HulkMessage {
+    pub player_number: PlayerNumber,
+    pub fallen: bool,
+    pub robot_to_field: Isometry2<f32>,
+    /// Covariance of x, y and orientation of the pose
+    pub robot_to_field_covariance: Matrix3<f32>,
+    /// Between 0 (lost) and 1 (certain)
+    pub localization_quality: f32,
+    pub ball_position: Option<BallPosition>,
+    /// The sender detected the ball itself instead of relaying its team ball
+    pub ball_is_own_detection: bool,
+}
+
+impl TryFrom<&[u8]> for HulkMessage {
+    type Error = Report;
+
+    fn try_from(buffer: &[u8]) -> Result<Self> {
+        let return_data_size = size_of::<RoboCupGameControlReturnData>();
+        if buffer.len() != return_data_size + EXTENSION_SIZE {
+            bail!(
+                "buffer has size {}, expected {}",
+                buffer.len(),
+                return_data_size + EXTENSION_SIZE
+            );
+        }
+        let version = buffer[return_data_size];
+        if version != HULK_MESSAGE_VERSION {
+            bail!("unexpected message version {version}, expected {HULK_MESSAGE_VERSION}");
+        }
+        let message = GameControllerReturnMessage::try_from(&buffer[..return_data_size])?;
+        let ball_is_own_detection = buffer[return_data_size + 1] != 0;
+        let extension: Vec<_> = buffer[return_data_size + EXTENSION_HEADER_SIZE..]
+            .chunks_exact(size_of::<f32>())
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        Ok(Self {
+            player_number: message.player_number,
+            fallen: message.fallen,
+            robot_to_field: message.robot_to_field,
+            robot_to_field_covariance: Matrix3::new(
+                extension[0],
+                extension[1],
+                extension[2],
+                extension[1],
+                extension[3],
+                extension[4],
+                extension[2],
+                extension[4],
+                extension[5],
+            ),
+            localization_quality: extension[6],
+            ball_position: message.ball_position,
+            ball_is_own_detection,
+        })
+    }
+}
+
+impl From<HulkMessage> for Vec<u8> {
+    fn from(message: HulkMessage) -> Self {
+        let covariance = message.robot_to_field_covariance;
+        let mut buffer: Vec<u8> = GameControllerReturnMessage::from(message).into();
+        buffer.push(HULK_MESSAGE_VERSION);
+        buffer.push(message.ball_is_own_detection as u8);
+        for value in [
+            covariance.m11,
+            covariance.m12,
+            covariance.m13,
+            covariance.m22,
+            covariance.m23,
+            covariance.m33,
+            message.localization_quality,
+        ] {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        buffer
+    }
+}
+
+impl From<HulkMessage> for GameControllerReturnMessage {
+    fn from(message: HulkMessage) -> Self {
+        Self {
+            player_number: message.player_number,
+            fallen: message.fallen,
+            robot_to_field: message.robot_to_field,
+            ball_position: message.ball_position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use approx::assert_relative_eq;
+    use nalgebra::{matrix, point, vector};
+
+    use super::*;
+
+    #[test]
+    fn localization_confidence_survives_serialization() {
+        let message = HulkMessage {
+            player_number: PlayerNumber::Three,
+            fallen: false,
+            robot_to_field: Isometry2::new(vector![1.0, -2.0], 0.5),
+            robot_to_field_covariance: matrix![
+                0.04, 0.01, 0.002;
+                0.01, 0.09, -0.003;
+                0.002, -0.003, 0.01
+            ],
+            localization_quality: 0.75,
+            ball_position: Some(BallPosition {
+                relative_position: point![0.5, 0.25],
+                age: Duration::from_millis(500),
+            }),
+            ball_is_own_detection: true,
+        };
+
+        let buffer: Vec<u8> = message.into();
+        let parsed_message = HulkMessage::try_from(buffer.as_slice()).unwrap();
+
+        assert_eq!(parsed_message.player_number, PlayerNumber::Three);
+        assert_relative_eq!(
+            parsed_message.robot_to_field,
+            message.robot_to_field,
+            epsilon = 0.001
+        );
+        assert_relative_eq!(
+            parsed_message.robot_to_field_covariance,
+            message.robot_to_field_covariance
+        );
+        assert_relative_eq!(parsed_message.localization_quality, 0.75);
+        assert!(parsed_message.ball_is_own_detection);
+
+        let truncated_buffer = &buffer[..size_of::<RoboCupGameControlReturnData>()];
+        assert!(HulkMessage::try_from(truncated_buffer).is_err());
+    }
+
+    #[test]
+    fn messages_of_other_versions_are_rejected() {
+        let mut buffer: Vec<u8> = HulkMessage::default().into();
+        assert!(HulkMessage::try_from(buffer.as_slice()).is_ok());
+
+        buffer[size_of::<RoboCupGameControlReturnData>()] = HULK_MESSAGE_VERSION + 1;
+        assert!(HulkMessage::try_from(buffer.as_slice()).is_err());
+
+        let mut buffer: Vec<u8> = HulkMessage::default().into();
+        buffer.push(0);
+        assert!(HulkMessage::try_from(buffer.as_slice()).is_err());
+    }
+}
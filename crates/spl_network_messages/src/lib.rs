@@ -1,6 +1,7 @@
 mod bindings;
 mod game_controller_return_message;
 mod game_controller_state_message;
+mod hulk_message;
 
 use std::{
     fmt::{self, Display, Formatter},
@@ -15,10 +16,9 @@ pub use game_controller_state_message::{
     GameControllerStateMessage, GamePhase, GameState, Half, Penalty, PenaltyShoot, Player,
     SubState, Team, TeamColor, TeamState,
 };
+pub use hulk_message::HulkMessage;
 use serialize_hierarchy::SerializeHierarchy;
 
-pub type HulkMessage = GameControllerReturnMessage;
-
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct BallPosition {
     pub relative_position: Point2<f32>,
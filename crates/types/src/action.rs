@@ -19,7 +19,7 @@ pub enum Action {
     DefendLeft,
     DefendRight,
     DefendPenaltyKick,
-    Jump,
+    KeeperMotion,
     PrepareJump,
     SupportLeft,
     SupportRight,
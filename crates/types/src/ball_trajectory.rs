@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use nalgebra::{matrix, vector, Matrix2x4, Point2, Vector2};
+use serde::{Deserialize, Serialize};
+use serialize_hierarchy::SerializeHierarchy;
+
+use crate::{multivariate_normal_distribution::MultivariateNormalDistribution, Line2};
+
+/// Prediction of the rolling ball in ground coordinates, the velocity decays exponentially like in
+/// the ball filter and the ball keeps its direction
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, SerializeHierarchy)]
+pub struct BallTrajectory {
+    /// Position and velocity when the prediction was made
+    pub state: MultivariateNormalDistribution<4>,
+    /// Time constant of the velocity decay in seconds
+    pub velocity_time_constant: f32,
+    pub is_moving: bool,
+    pub rest_position: Point2<f32>,
+    pub time_to_rest: Duration,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, SerializeHierarchy)]
+pub struct BallCrossing {
+    pub position: Point2<f32>,
+    pub time: Duration,
+    /// Standard deviation of the crossing position along the crossed line
+    pub deviation: f32,
+}
+
+impl BallTrajectory {
+    /// The ball is considered at rest once it is slower than the minimum velocity, a ball which
+    /// never gets that slow rests after [`Duration::MAX`]
+    pub fn new(
+        state: MultivariateNormalDistribution<4>,
+        velocity_time_constant: f32,
+        minimum_velocity: f32,
+        is_moving: bool,
+    ) -> Self {
+        let position = Point2::from(state.mean.xy());
+        let velocity = vector![state.mean.z, state.mean.w];
+        let speed = velocity.norm();
+        let time_to_rest =
+            if speed > minimum_velocity && minimum_velocity > 0.0 && velocity_time_constant > 0.0 {
+                velocity_time_constant * (speed / minimum_velocity).ln()
+            } else {
+                0.0
+            };
+        let mut trajectory = Self {
+            state,
+            velocity_time_constant,
+            is_moving,
+            rest_position: position,
+            time_to_rest: Duration::try_from_secs_f32(time_to_rest).unwrap_or(Duration::MAX),
+        };
+        if time_to_rest.is_finite() {
+            trajectory.rest_position = position + velocity * trajectory.travel_factor(time_to_rest);
+        }
+        trajectory
+    }
+
+    pub fn position(&self) -> Point2<f32> {
+        Point2::from(self.state.mean.xy())
+    }
+
+    pub fn velocity(&self) -> Vector2<f32> {
+        vector![self.state.mean.z, self.state.mean.w]
+    }
+
+    /// Distribution of the ball position after the given time, the ball does not move after it
+    /// came to rest
+    pub fn predict(&self, time: Duration) -> MultivariateNormalDistribution<2> {
+        let time = time.min(self.time_to_rest).as_secs_f32();
+        let prediction = position_prediction(self.travel_factor(time));
+        MultivariateNormalDistribution {
+            mean: prediction * self.state.mean,
+            covariance: prediction * self.state.covariance * prediction.transpose(),
+        }
+    }
+
+    /// First crossing of the rolling ball with the infinite line before it comes to rest
+    pub fn crossing(&self, line: Line2) -> Option<BallCrossing> {
+        let speed = self.velocity().norm();
+        let direction = self.velocity().try_normalize(f32::EPSILON)?;
+        let line_direction = (line.1 - line.0).try_normalize(f32::EPSILON)?;
+        let normal = vector![-line_direction.y, line_direction.x];
+        let approach = normal.dot(&direction);
+        if approach.abs() < f32::EPSILON {
+            return None;
+        }
+        let distance_to_line = normal.dot(&(line.0 - self.position())) / approach;
+        let distance_to_rest = if self.time_to_rest == Duration::MAX {
+            f32::INFINITY
+        } else {
+            (self.rest_position - self.position()).norm()
+        };
+        if !(0.0..=distance_to_rest).contains(&distance_to_line) {
+            return None;
+        }
+        let travel_factor = distance_to_line / speed;
+        let time = self.time_of_travel_factor(travel_factor);
+        let prediction = position_prediction(travel_factor);
+        let covariance = prediction * self.state.covariance * prediction.transpose();
+        Some(BallCrossing {
+            position: self.position() + direction * distance_to_line,
+            time: Duration::try_from_secs_f32(time.max(0.0)).unwrap_or(self.time_to_rest),
+            deviation: line_direction.dot(&(covariance * line_direction)).sqrt(),
+        })
+    }
+
+    /// Integral of the velocity decay, the distance travelled is this factor times the initial
+    /// velocity
+    fn travel_factor(&self, time: f32) -> f32 {
+        if self.velocity_time_constant.is_infinite() {
+            return time;
+        }
+        if self.velocity_time_constant <= 0.0 {
+            return 0.0;
+        }
+        self.velocity_time_constant * (1.0 - (-time / self.velocity_time_constant).exp())
+    }
+
+    /// Inverse of [`Self::travel_factor`]
+    fn time_of_travel_factor(&self, travel_factor: f32) -> f32 {
+        if self.velocity_time_constant.is_infinite() {
+            return travel_factor;
+        }
+        -self.velocity_time_constant * (1.0 - travel_factor / self.velocity_time_constant).ln()
+    }
+}
+
+fn position_prediction(travel_factor: f32) -> Matrix2x4<f32> {
+    matrix![
+        1.0, 0.0, travel_factor, 0.0;
+        0.0, 1.0, 0.0, travel_factor;
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::{point, Matrix4};
+
+    use crate::Line;
+
+    use super::*;
+
+    fn rolling_ball() -> BallTrajectory {
+        BallTrajectory::new(
+            MultivariateNormalDistribution {
+                mean: vector![2.0, 0.5, -1.0, 0.0],
+                covariance: Matrix4::from_diagonal(&vector![0.01, 0.01, 0.04, 0.04]),
+            },
+            1.0,
+            0.1,
+            true,
+        )
+    }
+
+    #[test]
+    fn ball_comes_to_rest_after_velocity_decayed() {
+        let trajectory = rolling_ball();
+
+        assert_relative_eq!(
+            trajectory.time_to_rest.as_secs_f32(),
+            10.0_f32.ln(),
+            epsilon = 1e-4
+        );
+        assert_relative_eq!(trajectory.rest_position, point![1.1, 0.5], epsilon = 1e-4);
+        assert_relative_eq!(
+            trajectory.predict(Duration::from_secs(60)).mean,
+            vector![1.1, 0.5],
+            epsilon = 1e-4
+        );
+    }
+
+    #[test]
+    fn crossing_is_found_only_before_the_ball_stops() {
+        let trajectory = rolling_ball();
+
+        let crossing = trajectory
+            .crossing(Line(point![1.5, -1.0], point![1.5, 1.0]))
+            .unwrap();
+        assert_relative_eq!(crossing.position, point![1.5, 0.5], epsilon = 1e-4);
+        assert_relative_eq!(crossing.time.as_secs_f32(), 2.0_f32.ln(), epsilon = 1e-4);
+        assert!(crossing.deviation > 0.1);
+
+        assert!(trajectory
+            .crossing(Line(point![0.0, -1.0], point![0.0, 1.0]))
+            .is_none());
+        assert!(trajectory
+            .crossing(Line(point![3.0, -1.0], point![3.0, 1.0]))
+            .is_none());
+    }
+
+    #[test]
+    fn ball_without_velocity_decay_does_not_panic() {
+        let trajectory = BallTrajectory::new(
+            MultivariateNormalDistribution {
+                mean: vector![2.0, 0.5, -1.0, 0.0],
+                covariance: Matrix4::from_diagonal(&vector![0.01, 0.01, 0.04, 0.04]),
+            },
+            f32::INFINITY,
+            0.1,
+            true,
+        );
+
+        assert_eq!(trajectory.time_to_rest, Duration::MAX);
+        assert_relative_eq!(
+            trajectory.predict(Duration::from_secs(1)).mean,
+            vector![1.0, 0.5],
+            epsilon = 1e-4
+        );
+        let crossing = trajectory
+            .crossing(Line(point![-1.0, -1.0], point![-1.0, 1.0]))
+            .unwrap();
+        assert_relative_eq!(crossing.time.as_secs_f32(), 3.0, epsilon = 1e-4);
+    }
+}
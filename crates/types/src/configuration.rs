@@ -6,8 +6,8 @@ use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
 
 use crate::{
-    ArmJoints, HeadJoints, InitialPose, KickStep, KickVariant, LegJoints, MotionCommand, Players,
-    Role, Step,
+    ArmJoints, HeadJoints, InWalkKickVariant, InitialPose, KickStep, KickVariant, LegJoints,
+    MotionCommand, Players, Role, Step,
 };
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
@@ -26,6 +26,8 @@ pub struct WhistleDetection {
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct Localization {
     pub circle_measurement_noise: Vector2<f32>,
+    pub goal_post_measurement_noise: Vector2<f32>,
+    pub goal_post_pair_measurement_noise: Vector3<f32>,
     pub gradient_convergence_threshold: f32,
     pub gradient_descent_step_size: f32,
     pub hypothesis_prediction_score_reduction_factor: f32,
@@ -37,19 +39,55 @@ pub struct Localization {
     pub line_measurement_noise: Vector2<f32>,
     pub maximum_amount_of_gradient_descent_iterations: usize,
     pub maximum_amount_of_outer_iterations: usize,
+    pub maximum_field_feature_association_angle: f32,
+    pub maximum_field_feature_association_distance: f32,
+    pub maximum_goal_post_association_distance: f32,
+    pub maximum_own_ball_age: Duration,
+    pub maximum_team_ball_age: Duration,
     pub minimum_fit_error: f32,
     pub odometry_noise: Vector3<f32>,
+    pub oriented_feature_measurement_noise: Vector3<f32>,
+    pub own_half_tolerance: f32,
+    pub particle_filter: ParticleFilter,
+    pub point_feature_measurement_noise: Vector2<f32>,
+    pub quality_reference_deviation: Vector2<f32>,
+    pub team_ball_agreement_distance: f32,
+    pub team_ball_agreement_score: f32,
+    pub use_field_feature_measurements: bool,
+    pub use_goal_post_measurements: bool,
     pub use_line_measurements: bool,
+    pub use_particle_filter: bool,
+    pub use_symmetry_resolution: bool,
     pub good_matching_threshold: f32,
     pub score_per_good_match: f32,
     pub hypothesis_score_base_increase: f32,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct ParticleFilter {
+    pub angle_measurement_noise: f32,
+    pub cluster_radius: f32,
+    pub line_measurement_noise: f32,
+    pub long_term_likelihood_factor: f32,
+    pub maximum_sensor_resetting_ratio: f32,
+    pub minimum_effective_sample_size_ratio: f32,
+    pub minimum_odometry_noise: Vector3<f32>,
+    pub number_of_particles: usize,
+    pub odometry_noise: Vector3<f32>,
+    pub outlier_likelihood: f32,
+    pub point_measurement_noise: f32,
+    pub sensor_resetting_noise: Vector3<f32>,
+    pub short_term_likelihood_factor: f32,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct StepPlanner {
+    pub arrival_angle: f32,
+    pub arrival_distance: f32,
     pub injected_step: Option<Step>,
     pub max_step_size: Step,
     pub max_step_size_backwards: f32,
+    pub number_of_planned_steps: usize,
     pub translation_exponent: f32,
     pub rotation_exponent: f32,
     pub inside_turn_ratio: f32,
@@ -84,6 +122,7 @@ pub struct Behavior {
     pub search: Search,
     pub look_action: LookAction,
     pub intercept_ball: InterceptBall,
+    pub keeper_motion: KeeperMotion,
     pub initial_lookaround_duration: Duration,
 }
 
@@ -136,18 +175,31 @@ pub struct FindKickTargets {
     pub ball_radius_for_kick_target_selection: f32,
 }
 
-impl Index<KickVariant> for InWalkKicks {
+impl Index<InWalkKickVariant> for InWalkKicks {
     type Output = InWalkKickInfo;
 
-    fn index(&self, variant: KickVariant) -> &Self::Output {
+    fn index(&self, variant: InWalkKickVariant) -> &Self::Output {
         match variant {
-            KickVariant::Forward => &self.forward,
-            KickVariant::Turn => &self.turn,
-            KickVariant::Side => &self.side,
+            InWalkKickVariant::Forward => &self.forward,
+            InWalkKickVariant::Turn => &self.turn,
+            InWalkKickVariant::Side => &self.side,
         }
     }
 }
 
+/// Parameters of any kick variant, the standalone kick is no in-walk kick and has its own
+/// parameters
+pub fn kick_info<'a>(
+    variant: KickVariant,
+    in_walk_kicks: &'a InWalkKicks,
+    standalone_kick: &'a InWalkKickInfo,
+) -> &'a InWalkKickInfo {
+    match variant.in_walk_kick() {
+        Some(in_walk_kick) => &in_walk_kicks[in_walk_kick],
+        None => standalone_kick,
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct InWalkKickInfo {
     pub offset: Vector2<f32>,
@@ -186,6 +238,16 @@ pub struct InterceptBall {
     pub maximum_intercept_distance: f32,
 }
 
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct KeeperMotion {
+    pub maximum_time_to_impact: Duration,
+    pub block_half_width: f32,
+    pub catch_reach: f32,
+    pub dive_reach: f32,
+    /// Balls crossing the keeper further away from the own goal line are not a shot on goal
+    pub maximum_crossing_distance_to_goal_line: f32,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct PathPlanning {
     pub robot_radius_at_foot_height: f32,
@@ -212,6 +274,7 @@ pub struct WalkingEngine {
     pub backward_foot_support_offset: f32,
     pub base_foot_lift: f32,
     pub base_step_duration: Duration,
+    pub capture_point_balancing: CapturePointBalancing,
     pub emergency_foot_lift: f32,
     pub emergency_step: Step,
     pub emergency_step_duration: Duration,
@@ -245,6 +308,22 @@ pub struct WalkingEngine {
     pub step_foot_lift_increase: Step,
 }
 
+/// Support region around the center of the support sole and limits for stepping towards the
+/// capture point, all distances in meters
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct CapturePointBalancing {
+    pub enabled: bool,
+    pub velocity_low_pass_factor: f32,
+    pub forward_support_offset: f32,
+    pub backward_support_offset: f32,
+    pub sideways_support_offset: f32,
+    pub max_forward_step_adjustment: f32,
+    pub max_sideways_step_adjustment: f32,
+    /// Relative speed up of the step per meter the capture point is outside of the support region
+    pub step_acceleration_factor: f32,
+    pub max_step_acceleration: f32,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct SwingingArms {
     pub debug_pull_back: bool,
@@ -265,6 +344,18 @@ pub struct KickSteps {
     pub side: Vec<KickStep>,
 }
 
+impl Index<InWalkKickVariant> for KickSteps {
+    type Output = Vec<KickStep>;
+
+    fn index(&self, variant: InWalkKickVariant) -> &Self::Output {
+        match variant {
+            InWalkKickVariant::Forward => &self.forward,
+            InWalkKickVariant::Turn => &self.turn,
+            InWalkKickVariant::Side => &self.side,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct HeadMotionLimits {
     pub maximum_yaw: f32,
@@ -329,6 +420,7 @@ pub struct SonarObstacle {
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct SplNetwork {
     pub game_controller_return_message_interval: Duration,
+    pub minimum_teammate_localization_quality: f32,
     pub remaining_amount_of_messages_to_stop_sending: u16,
     pub silence_interval_between_messages: Duration,
     pub spl_striker_message_receive_timeout: Duration,
@@ -412,6 +504,26 @@ pub struct LineDetection {
     pub minimum_number_of_points_on_line: usize,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct FieldFeatureDetection {
+    pub maximum_distance_to_robot: f32,
+    pub maximum_endpoint_distance: f32,
+    pub maximum_orthogonality_deviation: f32,
+    pub maximum_penalty_spot_size_deviation: f32,
+    pub minimum_number_of_penalty_spot_segments: usize,
+    pub minimum_penalty_spot_distance_to_lines: f32,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct GoalPostDetection {
+    pub maximum_distance_to_robot: f32,
+    pub maximum_foot_height_difference: f32,
+    pub maximum_scan_line_gap: u16,
+    pub maximum_width_deviation: f32,
+    pub minimum_height_above_field_border: f32,
+    pub minimum_luminance: u8,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct BallFilter {
     pub hypothesis_timeout: Duration,
@@ -419,12 +531,29 @@ pub struct BallFilter {
     pub hypothesis_merge_distance: f32,
     pub process_noise: Vector4<f32>,
     pub measurement_noise: Vector2<f32>,
+    pub minimum_moving_velocity: f32,
+    pub moving_velocity_hysteresis: f32,
     pub initial_covariance: Vector4<f32>,
     pub visible_validity_exponential_decay_factor: f32,
     pub hidden_validity_exponential_decay_factor: f32,
     pub validity_discard_threshold: f32,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct TeamBallFilter {
+    pub hypothesis_merge_distance: f32,
+    pub hypothesis_timeout: Duration,
+    pub initial_covariance: Vector4<f32>,
+    pub maximum_message_ball_age: Duration,
+    pub measurement_matching_distance: f32,
+    pub measurement_noise: Vector2<f32>,
+    pub message_age_noise: f32,
+    pub process_noise: Vector4<f32>,
+    pub validity_discard_threshold: f32,
+    pub validity_exponential_decay_factor: f32,
+    pub velocity_decay_factor: f32,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct StandUp {
     pub gyro_low_pass_filter_coefficient: f32,
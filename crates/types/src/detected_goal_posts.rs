@@ -0,0 +1,10 @@
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+use serialize_hierarchy::SerializeHierarchy;
+
+/// Goal posts represented by the points where they touch the ground
+#[derive(Default, Clone, Debug, Deserialize, Serialize, SerializeHierarchy)]
+pub struct DetectedGoalPosts {
+    pub in_image: Vec<Point2<f32>>,
+    pub on_ground: Vec<Point2<f32>>,
+}
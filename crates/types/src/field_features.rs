@@ -0,0 +1,30 @@
+use nalgebra::{Point2, Vector2};
+use serde::{Deserialize, Serialize};
+use serialize_hierarchy::SerializeHierarchy;
+
+/// Shape of the junction of two field lines
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum IntersectionKind {
+    /// Both lines end at the intersection, e.g. a field corner
+    L,
+    /// One line ends at the other one, e.g. the center line meeting a sideline
+    T,
+    /// Both lines pass through the intersection
+    X,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Intersection {
+    pub point: Point2<f32>,
+    pub kind: IntersectionKind,
+    /// Unit vector along the bisector of an L or along the stem of a T, pointing away from the
+    /// intersection, along one of the lines of an X
+    pub direction: Vector2<f32>,
+}
+
+/// Point-like field features in robot coordinates
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct FieldFeatures {
+    pub penalty_spots: Vec<Point2<f32>>,
+    pub intersections: Vec<Intersection>,
+}
@@ -1,8 +1,10 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
 use nalgebra::{distance, point, vector, Point2, Vector2};
 use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
 
-use super::{FieldDimensions, Line, Line2};
+use super::{FieldDimensions, IntersectionKind, Line, Line2};
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum FieldMark {
@@ -10,6 +12,21 @@ pub enum FieldMark {
     Circle { center: Point2<f32>, radius: f32 },
 }
 
+/// Point feature of the field that detected penalty spots and line intersections are matched with
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum PointMark {
+    PenaltySpot {
+        center: Point2<f32>,
+    },
+    /// Junction of two lines, the direction follows the convention of
+    /// [`Intersection::direction`](super::Intersection::direction)
+    Intersection {
+        point: Point2<f32>,
+        kind: IntersectionKind,
+        direction: Vector2<f32>,
+    },
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum Direction {
     PositiveX,
@@ -133,6 +150,57 @@ impl FieldMark {
     }
 }
 
+impl PointMark {
+    /// Correspondences of a measured point feature with its direction to this point mark
+    ///
+    /// The first correspondence relates the points themselves, the second one the points one meter
+    /// along the directions. Penalty spots have no direction and X intersections are symmetric,
+    /// the reference direction of an X is the one of its four arms closest to the measured one.
+    pub fn to_point_correspondences(
+        self,
+        measured_point: Point2<f32>,
+        measured_direction: Vector2<f32>,
+    ) -> Correspondences {
+        let (reference_point, reference_direction) = match self {
+            PointMark::PenaltySpot { center } => (center, measured_direction),
+            PointMark::Intersection {
+                point,
+                kind: IntersectionKind::X,
+                direction,
+            } => {
+                let orthogonal_direction = vector![-direction.y, direction.x];
+                let closest_arm = [
+                    direction,
+                    orthogonal_direction,
+                    -direction,
+                    -orthogonal_direction,
+                ]
+                .into_iter()
+                .max_by_key(|arm| NotNan::new(arm.dot(&measured_direction)).unwrap())
+                .unwrap();
+                (point, closest_arm)
+            }
+            PointMark::Intersection {
+                point, direction, ..
+            } => (point, direction),
+        };
+        Correspondences {
+            correspondence_points: (
+                CorrespondencePoints {
+                    measured: measured_point,
+                    reference: reference_point,
+                },
+                CorrespondencePoints {
+                    measured: measured_point + measured_direction,
+                    reference: reference_point + reference_direction,
+                },
+            ),
+            measured_direction,
+            reference_direction,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct Correspondences {
     pub correspondence_points: (CorrespondencePoints, CorrespondencePoints),
@@ -140,6 +208,16 @@ pub struct Correspondences {
     pub reference_direction: Vector2<f32>,
 }
 
+impl Correspondences {
+    /// Signed angle rotating the measured direction onto the reference direction
+    pub fn rotation(&self) -> f32 {
+        f32::atan2(
+            self.measured_direction.perp(&self.reference_direction),
+            self.measured_direction.dot(&self.reference_direction),
+        )
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct CorrespondencePoints {
     pub measured: Point2<f32>,
@@ -409,3 +487,133 @@ pub fn field_marks_from_field_dimensions(field_dimensions: &FieldDimensions) ->
         },
     ]
 }
+
+/// Penalty spots and line intersections of the field, the intersections of the center circle
+/// with the center line are treated as X intersections
+pub fn field_features_from_field_dimensions(field_dimensions: &FieldDimensions) -> Vec<PointMark> {
+    let half_length = field_dimensions.length / 2.0;
+    let half_width = field_dimensions.width / 2.0;
+    let center_circle_radius = field_dimensions.center_circle_diameter / 2.0;
+    let mut field_features = vec![
+        PointMark::Intersection {
+            point: point![0.0, center_circle_radius],
+            kind: IntersectionKind::X,
+            direction: Vector2::y(),
+        },
+        PointMark::Intersection {
+            point: point![0.0, -center_circle_radius],
+            kind: IntersectionKind::X,
+            direction: Vector2::y(),
+        },
+    ];
+    for side_y in [-1.0, 1.0] {
+        field_features.push(PointMark::Intersection {
+            point: point![0.0, side_y * half_width],
+            kind: IntersectionKind::T,
+            direction: vector![0.0, -side_y],
+        });
+    }
+    for side_x in [-1.0, 1.0] {
+        field_features.push(PointMark::PenaltySpot {
+            center: point![
+                side_x * (half_length - field_dimensions.penalty_marker_distance),
+                0.0
+            ],
+        });
+        for side_y in [-1.0, 1.0] {
+            field_features.push(PointMark::Intersection {
+                point: point![side_x * half_length, side_y * half_width],
+                kind: IntersectionKind::L,
+                direction: vector![-side_x, -side_y] * FRAC_1_SQRT_2,
+            });
+            for (area_length, area_width) in [
+                (
+                    field_dimensions.penalty_area_length,
+                    field_dimensions.penalty_area_width,
+                ),
+                (
+                    field_dimensions.goal_box_area_length,
+                    field_dimensions.goal_box_area_width,
+                ),
+            ] {
+                field_features.push(PointMark::Intersection {
+                    point: point![side_x * half_length, side_y * area_width / 2.0],
+                    kind: IntersectionKind::T,
+                    direction: vector![-side_x, 0.0],
+                });
+                field_features.push(PointMark::Intersection {
+                    point: point![
+                        side_x * (half_length - area_length),
+                        side_y * area_width / 2.0
+                    ],
+                    kind: IntersectionKind::L,
+                    direction: vector![side_x, -side_y] * FRAC_1_SQRT_2,
+                });
+            }
+        }
+    }
+    field_features
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn x_intersection_correspondence_uses_closest_arm() {
+        let mark = PointMark::Intersection {
+            point: point![0.0, 0.75],
+            kind: IntersectionKind::X,
+            direction: Vector2::y(),
+        };
+        let measured_direction = vector![0.9, 0.1].normalize();
+        let correspondences = mark.to_point_correspondences(point![0.1, 0.7], measured_direction);
+
+        assert_relative_eq!(correspondences.reference_direction, Vector2::x());
+        assert_relative_eq!(
+            correspondences.correspondence_points.0.reference,
+            point![0.0, 0.75]
+        );
+        assert_relative_eq!(correspondences.rotation(), -0.1_f32.atan2(0.9));
+    }
+
+    #[test]
+    fn field_features_contain_corners_and_penalty_spots() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            width: 6.0,
+            penalty_marker_distance: 1.3,
+            ..Default::default()
+        };
+        let field_features = field_features_from_field_dimensions(&field_dimensions);
+
+        let penalty_spots: Vec<_> = field_features
+            .iter()
+            .filter_map(|mark| match mark {
+                PointMark::PenaltySpot { center } => Some(*center),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(penalty_spots.len(), 2);
+        assert_relative_eq!(penalty_spots[0], point![-3.2, 0.0], epsilon = 1e-6);
+        assert_relative_eq!(penalty_spots[1], point![3.2, 0.0], epsilon = 1e-6);
+
+        let corner_directions: Vec<_> = field_features
+            .iter()
+            .filter_map(|mark| match mark {
+                PointMark::Intersection {
+                    point,
+                    kind: IntersectionKind::L,
+                    direction,
+                } if point.coords.abs() == vector![4.5, 3.0] => Some(*direction),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(corner_directions.len(), 4);
+        for direction in corner_directions {
+            assert_relative_eq!(direction.norm(), 1.0, epsilon = 1e-6);
+        }
+    }
+}
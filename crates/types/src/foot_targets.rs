@@ -0,0 +1,10 @@
+use nalgebra::Isometry3;
+use serde::{Deserialize, Serialize};
+use serialize_hierarchy::SerializeHierarchy;
+
+/// Poses of the ankles the walking engine passes to the inverse kinematics
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct FootTargets {
+    pub left_foot_to_robot: Isometry3<f32>,
+    pub right_foot_to_robot: Isometry3<f32>,
+}
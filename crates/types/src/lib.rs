@@ -3,6 +3,7 @@ mod action;
 mod ball;
 pub mod ball_filter;
 mod ball_position;
+mod ball_trajectory;
 mod buttons;
 pub mod camera_matrix;
 mod camera_position;
@@ -11,15 +12,18 @@ pub mod condition_input;
 pub mod configuration;
 mod cycle_time;
 pub mod detected_feet;
+pub mod detected_goal_posts;
 pub mod detected_robots;
 mod fall_state;
 mod field_border;
 mod field_color;
 mod field_dimensions;
+mod field_features;
 mod field_marks;
 mod filtered_game_state;
 mod filtered_segments;
 mod filtered_whistle;
+mod foot_targets;
 mod game_controller_state;
 mod geometry;
 pub mod grayscale_image;
@@ -76,6 +80,7 @@ pub mod ycbcr422_image;
 pub use action::Action;
 pub use ball::{Ball, CandidateEvaluation};
 pub use ball_position::BallPosition;
+pub use ball_trajectory::{BallCrossing, BallTrajectory};
 pub use buttons::Buttons;
 pub use camera_matrix::{CameraMatrices, CameraMatrix, ProjectedFieldLines};
 pub use camera_position::CameraPosition;
@@ -86,12 +91,15 @@ pub use fall_state::FallState;
 pub use field_border::FieldBorder;
 pub use field_color::FieldColor;
 pub use field_dimensions::FieldDimensions;
+pub use field_features::{FieldFeatures, Intersection, IntersectionKind};
 pub use field_marks::{
-    field_marks_from_field_dimensions, CorrespondencePoints, Correspondences, Direction, FieldMark,
+    field_features_from_field_dimensions, field_marks_from_field_dimensions, CorrespondencePoints,
+    Correspondences, Direction, FieldMark, PointMark,
 };
 pub use filtered_game_state::FilteredGameState;
 pub use filtered_segments::FilteredSegments;
 pub use filtered_whistle::FilteredWhistle;
+pub use foot_targets::FootTargets;
 pub use game_controller_state::GameControllerState;
 pub use geometry::{
     rotate_towards, Arc, Circle, LineSegment, Orientation, Rectangle, TwoLineSegments,
@@ -112,8 +120,8 @@ pub use line::{Line, Line2};
 pub use line_data::{ImageLines, LineData};
 pub use message_event::MessageEvent;
 pub use motion_command::{
-    ArmMotion, Facing, FallDirection, GlanceDirection, HeadMotion, JumpDirection, KickDirection,
-    KickVariant, MotionCommand, OrientationMode, SitDirection,
+    ArmMotion, Facing, FallDirection, GlanceDirection, HeadMotion, InWalkKickVariant,
+    JumpDirection, KickDirection, KickVariant, MotionCommand, OrientationMode, SitDirection,
 };
 pub use motion_selection::{MotionSafeExits, MotionSelection, MotionType};
 pub use obstacles::{Obstacle, ObstacleKind};
@@ -136,7 +144,7 @@ pub use sensor_data::{
 pub use sole_pressure::SolePressure;
 pub use sonar_obstacle::SonarObstacle;
 pub use sonar_values::SonarValues;
-pub use step_adjustment::StepAdjustment;
+pub use step_adjustment::{CapturePointAdjustment, StepAdjustment};
 pub use step_plan::Step;
 pub use support_foot::{Side, SupportFoot};
 pub use walk_command::WalkCommand;
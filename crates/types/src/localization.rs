@@ -14,6 +14,22 @@ pub struct Update {
     pub line_length_weight: f32,
 }
 
+/// How the 180° symmetry of the field was resolved for a pose hypothesis
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SerializeHierarchy,
+)]
+pub enum SymmetryResolution {
+    /// Neither the team ball nor the game state contradicted or confirmed the hypothesis
+    #[default]
+    Unresolved,
+    /// The ball seen by the robot agrees with the ball reported by teammates
+    ConfirmedByTeamBall,
+    /// The ball seen by the robot agrees with the mirrored ball reported by teammates
+    FlippedByTeamBall,
+    /// The hypothesis was in the opponent half while robots have to be in their own half
+    FlippedIntoOwnHalf,
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, SerializeHierarchy)]
 pub struct ScoredPose {
     pub state: MultivariateNormalDistribution<3>,
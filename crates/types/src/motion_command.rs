@@ -14,7 +14,18 @@ pub enum OrientationMode {
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub enum MotionCommand {
+    /// Unstiff lets the joints be moved by hand to record keyframes, stiff plays back the motion
+    /// file of the `animation` parameters
+    Animation {
+        stiff: bool,
+    },
     ArmsUpSquat,
+    /// Sit down in front of the ball to block it with the legs
+    Block,
+    /// Catch the ball with the arms and legs on one side without leaving the stance
+    Catch {
+        direction: JumpDirection,
+    },
     FallProtection {
         direction: FallDirection,
     },
@@ -32,6 +43,9 @@ pub enum MotionCommand {
     StandUp {
         facing: Facing,
     },
+    StandaloneKick {
+        kicking_side: Side,
+    },
     #[default]
     Unstiff,
     Walk {
@@ -40,10 +54,13 @@ pub enum MotionCommand {
         left_arm: ArmMotion,
         right_arm: ArmMotion,
         orientation_mode: OrientationMode,
+        /// Side of the kick executed at the end of the path, the approach is planned such that this
+        /// foot swings next when the robot arrives
+        kicking_side: Option<Side>,
     },
     InWalkKick {
         head: HeadMotion,
-        kick: KickVariant,
+        kick: InWalkKickVariant,
         kicking_side: Side,
         strength: f32,
     },
@@ -58,10 +75,14 @@ impl MotionCommand {
             | MotionCommand::InWalkKick { head, .. } => Some(*head),
             MotionCommand::Penalized => Some(HeadMotion::ZeroAngles),
             MotionCommand::Unstiff => Some(HeadMotion::Unstiff),
-            MotionCommand::ArmsUpSquat
+            MotionCommand::Animation { .. }
+            | MotionCommand::ArmsUpSquat
+            | MotionCommand::Block
+            | MotionCommand::Catch { .. }
             | MotionCommand::FallProtection { .. }
             | MotionCommand::Jump { .. }
-            | MotionCommand::StandUp { .. } => None,
+            | MotionCommand::StandUp { .. }
+            | MotionCommand::StandaloneKick { .. } => None,
         }
     }
 }
@@ -101,6 +122,28 @@ pub enum KickVariant {
     Forward,
     Turn,
     Side,
+    /// Strong kick executed as its own motion while standing instead of inside the walk
+    Standalone,
+}
+
+impl KickVariant {
+    /// The kick of the walking engine, if this variant is kicked inside the walk
+    pub fn in_walk_kick(self) -> Option<InWalkKickVariant> {
+        match self {
+            KickVariant::Forward => Some(InWalkKickVariant::Forward),
+            KickVariant::Turn => Some(InWalkKickVariant::Turn),
+            KickVariant::Side => Some(InWalkKickVariant::Side),
+            KickVariant::Standalone => None,
+        }
+    }
+}
+
+/// Kicks the walking engine executes as part of a walking step
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SerializeHierarchy)]
+pub enum InWalkKickVariant {
+    Forward,
+    Turn,
+    Side,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SerializeHierarchy)]
@@ -11,7 +11,12 @@ pub struct MotionSelection {
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SerializeHierarchy)]
 pub enum MotionType {
+    Animation,
+    AnimationStiff,
     ArmsUpSquat,
+    Block,
+    CatchLeft,
+    CatchRight,
     Dispatching,
     EnergySavingStand,
     FallProtection,
@@ -22,6 +27,7 @@ pub enum MotionType {
     Stand,
     StandUpBack,
     StandUpFront,
+    StandaloneKick,
     Unstiff,
     Walk,
 }
@@ -34,7 +40,12 @@ impl Default for MotionType {
 
 #[derive(Clone, Debug, Deserialize, Serialize, SerializeHierarchy)]
 pub struct MotionSafeExits {
+    animation: bool,
+    animation_stiff: bool,
     arms_up_squat: bool,
+    block: bool,
+    catch_left: bool,
+    catch_right: bool,
     dispatching: bool,
     energy_saving_stand: bool,
     fall_protection: bool,
@@ -45,6 +56,7 @@ pub struct MotionSafeExits {
     stand_up_back: bool,
     stand_up_front: bool,
     stand: bool,
+    standalone_kick: bool,
     unstiff: bool,
     walk: bool,
 }
@@ -52,7 +64,12 @@ pub struct MotionSafeExits {
 impl Default for MotionSafeExits {
     fn default() -> Self {
         Self {
+            animation: true,
+            animation_stiff: false,
             arms_up_squat: true,
+            block: false,
+            catch_left: false,
+            catch_right: false,
             dispatching: false,
             energy_saving_stand: true,
             fall_protection: true,
@@ -63,6 +80,7 @@ impl Default for MotionSafeExits {
             stand_up_back: false,
             stand_up_front: false,
             stand: true,
+            standalone_kick: false,
             unstiff: true,
             walk: false,
         }
@@ -74,7 +92,12 @@ impl Index<MotionType> for MotionSafeExits {
 
     fn index(&self, motion_type: MotionType) -> &Self::Output {
         match motion_type {
+            MotionType::Animation => &self.animation,
+            MotionType::AnimationStiff => &self.animation_stiff,
             MotionType::ArmsUpSquat => &self.arms_up_squat,
+            MotionType::Block => &self.block,
+            MotionType::CatchLeft => &self.catch_left,
+            MotionType::CatchRight => &self.catch_right,
             MotionType::Dispatching => &self.dispatching,
             MotionType::EnergySavingStand => &self.energy_saving_stand,
             MotionType::JumpLeft => &self.jump_left,
@@ -85,6 +108,7 @@ impl Index<MotionType> for MotionSafeExits {
             MotionType::Stand => &self.stand,
             MotionType::StandUpBack => &self.stand_up_back,
             MotionType::StandUpFront => &self.stand_up_front,
+            MotionType::StandaloneKick => &self.standalone_kick,
             MotionType::Unstiff => &self.unstiff,
             MotionType::Walk => &self.walk,
         }
@@ -94,7 +118,12 @@ impl Index<MotionType> for MotionSafeExits {
 impl IndexMut<MotionType> for MotionSafeExits {
     fn index_mut(&mut self, motion_type: MotionType) -> &mut Self::Output {
         match motion_type {
+            MotionType::Animation => &mut self.animation,
+            MotionType::AnimationStiff => &mut self.animation_stiff,
             MotionType::ArmsUpSquat => &mut self.arms_up_squat,
+            MotionType::Block => &mut self.block,
+            MotionType::CatchLeft => &mut self.catch_left,
+            MotionType::CatchRight => &mut self.catch_right,
             MotionType::Dispatching => &mut self.dispatching,
             MotionType::EnergySavingStand => &mut self.energy_saving_stand,
             MotionType::JumpLeft => &mut self.jump_left,
@@ -105,6 +134,7 @@ impl IndexMut<MotionType> for MotionSafeExits {
             MotionType::Stand => &mut self.stand,
             MotionType::StandUpBack => &mut self.stand_up_back,
             MotionType::StandUpFront => &mut self.stand_up_front,
+            MotionType::StandaloneKick => &mut self.standalone_kick,
             MotionType::Unstiff => &mut self.unstiff,
             MotionType::Walk => &mut self.walk,
         }
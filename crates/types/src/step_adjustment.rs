@@ -1,3 +1,4 @@
+use nalgebra::Vector2;
 use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
 
@@ -9,3 +10,16 @@ pub struct StepAdjustment {
     pub forward_balance_limit: f32,
     pub backward_balance_limit: f32,
 }
+
+/// Positions are relative to the center of the sole carrying the most weight and aligned with the
+/// ground
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct CapturePointAdjustment {
+    pub center_of_mass: Vector2<f32>,
+    pub center_of_mass_velocity: Vector2<f32>,
+    pub capture_point: Vector2<f32>,
+    /// Distance of the capture point to the support region
+    pub capture_point_error: Vector2<f32>,
+    pub limited_adjustment: Vector2<f32>,
+    pub step_time_scale: f32,
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
 
-use super::{KickVariant, Side, Step};
+use super::{InWalkKickVariant, Side, Step};
 
 pub type Strength = f32;
 
@@ -10,5 +10,5 @@ pub enum WalkCommand {
     #[default]
     Stand,
     Walk(Step),
-    Kick(KickVariant, Side, Strength),
+    Kick(InWalkKickVariant, Side, Strength),
 }
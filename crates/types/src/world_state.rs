@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
 use spl_network_messages::PlayerNumber;
 
-use crate::{GameControllerState, KickDecision};
+use crate::{BallTrajectory, GameControllerState, KickDecision};
 
 use crate::PenaltyShotDirection;
 use crate::RuleObstacle;
@@ -13,6 +13,8 @@ use super::{FallState, FilteredGameState, Obstacle, PrimaryState, Role, Side};
 #[derive(Clone, Debug, Default, Serialize, Deserialize, SerializeHierarchy)]
 pub struct WorldState {
     pub ball: Option<BallState>,
+    /// Prediction of the own ball, independent of the team ball
+    pub ball_trajectory: Option<BallTrajectory>,
     pub rule_ball: Option<BallState>,
     pub filtered_game_state: Option<FilteredGameState>,
     pub game_controller_state: Option<GameControllerState>,
@@ -48,6 +50,8 @@ impl BallState {
 #[derive(Clone, Debug, Default, Serialize, Deserialize, SerializeHierarchy)]
 pub struct RobotState {
     pub robot_to_field: Option<Isometry2<f32>>,
+    /// Between 0 (lost) and 1 (certain), `None` if not localized
+    pub localization_quality: Option<f32>,
     pub role: Role,
     pub primary_state: PrimaryState,
     pub fall_state: FallState,
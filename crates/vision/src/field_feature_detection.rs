@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+
+use color_eyre::Result;
+use context_attribute::context;
+use framework::{AdditionalOutput, MainOutput};
+use nalgebra::{center, distance, point, Point2, Vector2};
+use projection::Projection;
+use types::{
+    CameraMatrix, EdgeType, FieldDimensions, FieldFeatures, FilteredSegments, Intersection,
+    IntersectionKind, Line2, LineData,
+};
+
+pub struct FieldFeatureDetection {}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    pub penalty_spot_candidates: AdditionalOutput<Vec<Point2<f32>>, "penalty_spot_candidates">,
+
+    pub field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    pub maximum_distance_to_robot:
+        Parameter<f32, "field_feature_detection.$cycler_instance.maximum_distance_to_robot">,
+    pub maximum_endpoint_distance:
+        Parameter<f32, "field_feature_detection.$cycler_instance.maximum_endpoint_distance">,
+    pub maximum_orthogonality_deviation:
+        Parameter<f32, "field_feature_detection.$cycler_instance.maximum_orthogonality_deviation">,
+    pub maximum_penalty_spot_size_deviation: Parameter<
+        f32,
+        "field_feature_detection.$cycler_instance.maximum_penalty_spot_size_deviation",
+    >,
+    pub minimum_number_of_penalty_spot_segments: Parameter<
+        usize,
+        "field_feature_detection.$cycler_instance.minimum_number_of_penalty_spot_segments",
+    >,
+    pub minimum_penalty_spot_distance_to_lines: Parameter<
+        f32,
+        "field_feature_detection.$cycler_instance.minimum_penalty_spot_distance_to_lines",
+    >,
+
+    pub camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
+    pub filtered_segments: Input<FilteredSegments, "filtered_segments">,
+    pub line_data: RequiredInput<Option<LineData>, "line_data?">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub field_features: MainOutput<Option<FieldFeatures>>,
+}
+
+impl FieldFeatureDetection {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {})
+    }
+
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        let lines = &context.line_data.lines_in_robot;
+        let intersections = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(index, first)| {
+                lines[index + 1..].iter().filter_map(|second| {
+                    detect_intersection(
+                        *first,
+                        *second,
+                        *context.maximum_endpoint_distance,
+                        *context.maximum_orthogonality_deviation,
+                    )
+                })
+            })
+            .filter(|intersection| {
+                intersection.point.coords.norm() <= *context.maximum_distance_to_robot
+            })
+            .collect();
+
+        let maximum_penalty_spot_size = context.field_dimensions.penalty_marker_size
+            + *context.maximum_penalty_spot_size_deviation;
+        let candidates = penalty_spot_candidates(
+            context.camera_matrix,
+            context.filtered_segments,
+            &context.line_data.used_vertical_filtered_segments,
+            maximum_penalty_spot_size,
+        );
+        context
+            .penalty_spot_candidates
+            .fill_if_subscribed(|| candidates.clone());
+        let penalty_spots = cluster_penalty_spots(
+            &candidates,
+            maximum_penalty_spot_size,
+            *context.minimum_number_of_penalty_spot_segments,
+        )
+        .into_iter()
+        .filter(|penalty_spot| {
+            penalty_spot.coords.norm() <= *context.maximum_distance_to_robot
+                && lines.iter().all(|line| {
+                    line.squared_distance_to_segment(*penalty_spot).sqrt()
+                        >= *context.minimum_penalty_spot_distance_to_lines
+                })
+        })
+        .collect();
+
+        Ok(MainOutputs {
+            field_features: Some(FieldFeatures {
+                penalty_spots,
+                intersections,
+            })
+            .into(),
+        })
+    }
+}
+
+/// How a line takes part in an intersection
+enum Junction {
+    /// The line ends at the intersection, the point is its other end
+    End(Point2<f32>),
+    Through,
+}
+
+fn junction(line: Line2, point: Point2<f32>, maximum_endpoint_distance: f32) -> Option<Junction> {
+    if line.squared_distance_to_segment(point).sqrt() > maximum_endpoint_distance {
+        return None;
+    }
+    if distance(&line.0, &point) <= maximum_endpoint_distance {
+        Some(Junction::End(line.1))
+    } else if distance(&line.1, &point) <= maximum_endpoint_distance {
+        Some(Junction::End(line.0))
+    } else {
+        Some(Junction::Through)
+    }
+}
+
+fn detect_intersection(
+    first: Line2,
+    second: Line2,
+    maximum_endpoint_distance: f32,
+    maximum_orthogonality_deviation: f32,
+) -> Option<Intersection> {
+    if !first.is_orthogonal(&second, maximum_orthogonality_deviation) {
+        return None;
+    }
+    let point = first.intersection(&second);
+    let direction_to = |end: Point2<f32>| (end - point).try_normalize(f32::EPSILON);
+    let (kind, direction) = match (
+        junction(first, point, maximum_endpoint_distance)?,
+        junction(second, point, maximum_endpoint_distance)?,
+    ) {
+        (Junction::End(first_end), Junction::End(second_end)) => (
+            IntersectionKind::L,
+            (direction_to(first_end)? + direction_to(second_end)?).try_normalize(f32::EPSILON)?,
+        ),
+        (Junction::End(stem_end), Junction::Through)
+        | (Junction::Through, Junction::End(stem_end)) => {
+            (IntersectionKind::T, direction_to(stem_end)?)
+        }
+        (Junction::Through, Junction::Through) => (
+            IntersectionKind::X,
+            (first.1 - first.0).try_normalize(f32::EPSILON)?,
+        ),
+    };
+    Some(Intersection {
+        point,
+        kind,
+        direction,
+    })
+}
+
+/// Ground positions of bright vertical segments which are short enough to be part of a penalty
+/// spot and were not used for lines
+fn penalty_spot_candidates(
+    camera_matrix: &CameraMatrix,
+    filtered_segments: &FilteredSegments,
+    used_vertical_filtered_segments: &HashSet<Point2<u16>>,
+    maximum_penalty_spot_size: f32,
+) -> Vec<Point2<f32>> {
+    filtered_segments
+        .scan_grid
+        .vertical_scan_lines
+        .iter()
+        .flat_map(|scan_line| {
+            scan_line
+                .segments
+                .iter()
+                .map(move |segment| (scan_line.position, segment))
+        })
+        .filter(|(scan_line_position, segment)| {
+            segment.start_edge_type == EdgeType::Rising
+                && segment.end_edge_type == EdgeType::Falling
+                && !used_vertical_filtered_segments
+                    .contains(&point![*scan_line_position, segment.start])
+        })
+        .filter_map(|(scan_line_position, segment)| {
+            let start = camera_matrix
+                .pixel_to_ground(point![scan_line_position as f32, segment.start as f32])
+                .ok()?;
+            let end = camera_matrix
+                .pixel_to_ground(point![scan_line_position as f32, segment.end as f32])
+                .ok()?;
+            (distance(&start, &end) <= maximum_penalty_spot_size).then(|| center(&start, &end))
+        })
+        .collect()
+}
+
+/// Groups candidates closer than the penalty spot size and keeps the centers of groups which
+/// have enough members and are not larger than a penalty spot
+fn cluster_penalty_spots(
+    candidates: &[Point2<f32>],
+    maximum_penalty_spot_size: f32,
+    minimum_number_of_segments: usize,
+) -> Vec<Point2<f32>> {
+    let mut clusters: Vec<Vec<Point2<f32>>> = Vec::new();
+    for &candidate in candidates {
+        let cluster = clusters.iter_mut().find(|cluster| {
+            distance(&cluster_center(cluster), &candidate) <= maximum_penalty_spot_size
+        });
+        match cluster {
+            Some(cluster) => cluster.push(candidate),
+            None => clusters.push(vec![candidate]),
+        }
+    }
+    clusters
+        .into_iter()
+        .filter(|cluster| cluster.len() >= minimum_number_of_segments)
+        .filter_map(|cluster| {
+            let center = cluster_center(&cluster);
+            let radius = cluster
+                .iter()
+                .map(|point| distance(point, &center))
+                .fold(0.0, f32::max);
+            (2.0 * radius <= maximum_penalty_spot_size).then_some(center)
+        })
+        .collect()
+}
+
+fn cluster_center(cluster: &[Point2<f32>]) -> Point2<f32> {
+    Point2::from(
+        cluster
+            .iter()
+            .map(|point| point.coords)
+            .sum::<Vector2<f32>>()
+            / cluster.len() as f32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::vector;
+    use types::Line;
+
+    use super::*;
+
+    #[test]
+    fn line_pairs_are_classified_by_their_junction() {
+        let corner = detect_intersection(
+            Line(point![1.0, 1.0], point![3.0, 1.0]),
+            Line(point![1.0, 1.05], point![1.0, 2.0]),
+            0.3,
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(corner.kind, IntersectionKind::L);
+        assert_relative_eq!(corner.point, point![1.0, 1.0], epsilon = 1e-3);
+        assert_relative_eq!(
+            corner.direction,
+            vector![1.0, 1.0].normalize(),
+            epsilon = 1e-2
+        );
+
+        let t_junction = detect_intersection(
+            Line(point![2.0, -1.0], point![2.0, 1.0]),
+            Line(point![1.9, 0.5], point![0.5, 0.5]),
+            0.3,
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(t_junction.kind, IntersectionKind::T);
+        assert_relative_eq!(t_junction.point, point![2.0, 0.5], epsilon = 1e-3);
+        assert_relative_eq!(t_junction.direction, vector![-1.0, 0.0], epsilon = 1e-3);
+
+        let crossing = detect_intersection(
+            Line(point![1.0, -1.0], point![1.0, 1.0]),
+            Line(point![0.0, 0.0], point![2.0, 0.0]),
+            0.3,
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(crossing.kind, IntersectionKind::X);
+
+        let disjoint = detect_intersection(
+            Line(point![1.0, -1.0], point![1.0, 1.0]),
+            Line(point![2.0, 0.0], point![4.0, 0.0]),
+            0.3,
+            0.1,
+        );
+        assert!(disjoint.is_none());
+    }
+
+    #[test]
+    fn small_clusters_become_penalty_spots() {
+        let candidates = [
+            point![2.0, 0.0],
+            point![2.02, 0.01],
+            point![1.98, -0.02],
+            point![3.0, 1.0],
+        ];
+        let penalty_spots = cluster_penalty_spots(&candidates, 0.15, 2);
+        assert_eq!(penalty_spots.len(), 1);
+        assert_relative_eq!(penalty_spots[0], point![2.0, -0.00333], epsilon = 1e-3);
+    }
+}
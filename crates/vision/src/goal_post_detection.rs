@@ -0,0 +1,257 @@
+use color_eyre::Result;
+use context_attribute::context;
+use framework::{AdditionalOutput, MainOutput};
+use nalgebra::{distance, point, Point2};
+use projection::Projection;
+use types::{
+    detected_goal_posts::DetectedGoalPosts, CameraMatrix, FieldBorder, FieldDimensions,
+    ImageSegments, Intensity, Segment,
+};
+
+pub struct GoalPostDetection {}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    pub goal_post_candidates: AdditionalOutput<Vec<Point2<f32>>, "goal_post_candidates">,
+
+    pub field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    pub maximum_distance_to_robot:
+        Parameter<f32, "goal_post_detection.$cycler_instance.maximum_distance_to_robot">,
+    pub maximum_foot_height_difference:
+        Parameter<f32, "goal_post_detection.$cycler_instance.maximum_foot_height_difference">,
+    pub maximum_scan_line_gap:
+        Parameter<u16, "goal_post_detection.$cycler_instance.maximum_scan_line_gap">,
+    pub maximum_width_deviation:
+        Parameter<f32, "goal_post_detection.$cycler_instance.maximum_width_deviation">,
+    pub minimum_height_above_field_border:
+        Parameter<f32, "goal_post_detection.$cycler_instance.minimum_height_above_field_border">,
+    pub minimum_luminance: Parameter<u8, "goal_post_detection.$cycler_instance.minimum_luminance">,
+
+    pub camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
+    pub field_border: RequiredInput<Option<FieldBorder>, "field_border?">,
+    pub image_segments: Input<ImageSegments, "image_segments">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub detected_goal_posts: MainOutput<Option<DetectedGoalPosts>>,
+}
+
+/// Bright run of segments on one scan line which reaches from above the field border into the
+/// field
+#[derive(Clone, Copy, Debug)]
+struct PostCandidate {
+    scan_line_position: u16,
+    foot: f32,
+}
+
+impl GoalPostDetection {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {})
+    }
+
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        let candidates: Vec<_> = context
+            .image_segments
+            .scan_grid
+            .vertical_scan_lines
+            .iter()
+            .filter_map(|scan_line| {
+                find_post_candidate(
+                    scan_line.position,
+                    &scan_line.segments,
+                    context.field_border,
+                    *context.minimum_luminance,
+                    *context.minimum_height_above_field_border,
+                )
+            })
+            .collect();
+        context.goal_post_candidates.fill_if_subscribed(|| {
+            candidates
+                .iter()
+                .map(|candidate| point![candidate.scan_line_position as f32, candidate.foot])
+                .collect()
+        });
+
+        let maximum_width =
+            context.field_dimensions.goal_post_diameter + *context.maximum_width_deviation;
+        let (in_image, on_ground) = group_candidates(
+            &candidates,
+            *context.maximum_scan_line_gap,
+            *context.maximum_foot_height_difference,
+        )
+        .into_iter()
+        .filter_map(|group| {
+            let left = group.first()?;
+            let right = group.last()?;
+            let foot = group
+                .iter()
+                .map(|candidate| candidate.foot)
+                .fold(f32::NEG_INFINITY, f32::max);
+            let left_on_ground = context
+                .camera_matrix
+                .pixel_to_ground(point![left.scan_line_position as f32, foot])
+                .ok()?;
+            let right_on_ground = context
+                .camera_matrix
+                .pixel_to_ground(point![right.scan_line_position as f32, foot])
+                .ok()?;
+            if distance(&left_on_ground, &right_on_ground) > maximum_width {
+                return None;
+            }
+            let center = (left.scan_line_position as f32 + right.scan_line_position as f32) / 2.0;
+            let foot_in_image = point![center, foot];
+            let foot_on_ground = context.camera_matrix.pixel_to_ground(foot_in_image).ok()?;
+            (foot_on_ground.coords.norm() <= *context.maximum_distance_to_robot)
+                .then_some((foot_in_image, foot_on_ground))
+        })
+        .unzip();
+
+        Ok(MainOutputs {
+            detected_goal_posts: Some(DetectedGoalPosts {
+                in_image,
+                on_ground,
+            })
+            .into(),
+        })
+    }
+}
+
+/// Vertical image position of the field border at a column, the field is below all border lines
+fn field_border_position(field_border: &FieldBorder, x: f32) -> Option<f32> {
+    field_border
+        .border_lines
+        .iter()
+        .map(|line| line.0.y + (x - line.0.x) * line.slope())
+        .filter(|y| y.is_finite())
+        .reduce(f32::max)
+}
+
+fn is_bright(segment: &Segment, minimum_luminance: u8) -> bool {
+    segment.field_color != Intensity::High && segment.color.y >= minimum_luminance
+}
+
+fn find_post_candidate(
+    scan_line_position: u16,
+    segments: &[Segment],
+    field_border: &FieldBorder,
+    minimum_luminance: u8,
+    minimum_height_above_field_border: f32,
+) -> Option<PostCandidate> {
+    let border = field_border_position(field_border, scan_line_position as f32)?;
+    let mut run_start = None;
+    for (index, segment) in segments.iter().enumerate() {
+        if !is_bright(segment, minimum_luminance) {
+            run_start = None;
+            continue;
+        }
+        let start = *run_start.get_or_insert(segment.start as f32);
+        let is_end_of_run = !matches!(
+            segments.get(index + 1),
+            Some(next) if is_bright(next, minimum_luminance)
+        );
+        let foot = segment.end as f32;
+        if is_end_of_run && foot > border && border - start >= minimum_height_above_field_border {
+            return Some(PostCandidate {
+                scan_line_position,
+                foot,
+            });
+        }
+    }
+    None
+}
+
+/// Groups candidates of neighboring scan lines whose feet are at a similar height
+fn group_candidates(
+    candidates: &[PostCandidate],
+    maximum_scan_line_gap: u16,
+    maximum_foot_height_difference: f32,
+) -> Vec<Vec<PostCandidate>> {
+    let mut groups: Vec<Vec<PostCandidate>> = Vec::new();
+    for &candidate in candidates {
+        match groups.last_mut() {
+            Some(group)
+                if matches!(
+                    group.last(),
+                    Some(last) if candidate.scan_line_position.abs_diff(last.scan_line_position)
+                        <= maximum_scan_line_gap
+                        && (candidate.foot - last.foot).abs() <= maximum_foot_height_difference
+                ) =>
+            {
+                group.push(candidate)
+            }
+            _ => groups.push(vec![candidate]),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{EdgeType, Line, YCbCr444};
+
+    use super::*;
+
+    fn segment(start: u16, end: u16, luminance: u8, field_color: Intensity) -> Segment {
+        Segment {
+            start,
+            end,
+            start_edge_type: EdgeType::Rising,
+            end_edge_type: EdgeType::Falling,
+            color: YCbCr444 {
+                y: luminance,
+                cb: 128,
+                cr: 128,
+            },
+            field_color,
+        }
+    }
+
+    #[test]
+    fn bright_run_crossing_field_border_is_post_candidate() {
+        let field_border = FieldBorder {
+            border_lines: vec![Line(point![0.0, 100.0], point![640.0, 100.0])],
+        };
+        let post = [
+            segment(0, 40, 60, Intensity::Low),
+            segment(40, 90, 200, Intensity::Low),
+            segment(90, 130, 180, Intensity::Low),
+            segment(130, 480, 90, Intensity::High),
+        ];
+        let candidate = find_post_candidate(10, &post, &field_border, 150, 20.0).unwrap();
+        assert_eq!(candidate.foot, 130.0);
+
+        let field_line = [
+            segment(0, 110, 60, Intensity::Low),
+            segment(110, 120, 220, Intensity::Low),
+            segment(120, 480, 90, Intensity::High),
+        ];
+        assert!(find_post_candidate(10, &field_line, &field_border, 150, 20.0).is_none());
+    }
+
+    #[test]
+    fn neighboring_candidates_are_grouped() {
+        let candidates = [
+            PostCandidate {
+                scan_line_position: 100,
+                foot: 130.0,
+            },
+            PostCandidate {
+                scan_line_position: 104,
+                foot: 131.0,
+            },
+            PostCandidate {
+                scan_line_position: 200,
+                foot: 140.0,
+            },
+        ];
+        let groups = group_candidates(&candidates, 8, 5.0);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 2);
+        assert_eq!(groups[1].len(), 1);
+    }
+}
@@ -3,6 +3,8 @@ pub mod camera_matrix_extractor;
 pub mod feet_detection;
 pub mod field_border_detection;
 pub mod field_color_detection;
+pub mod field_feature_detection;
+pub mod goal_post_detection;
 pub mod image_receiver;
 pub mod image_segmenter;
 pub mod line_detection;
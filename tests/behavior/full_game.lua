@@ -0,0 +1,36 @@
+function spawn_robot(number)
+    table.insert(state.robots, create_robot(number))
+end
+
+function spawn_opponent(position, behavior)
+    table.insert(state.opponents, {
+        position = position,
+        orientation = math.pi,
+        behavior = behavior,
+    })
+end
+
+for number = 1, 7 do
+    spawn_robot(number)
+end
+
+spawn_opponent({ 4.3, 0.0 }, { Block = { distance = 0.2 } })
+spawn_opponent({ 3.0, -1.0 }, { Block = { distance = 1.5 } })
+spawn_opponent({ 1.0, 1.5 }, "ChaseBall")
+
+state.ball = {
+    position = { 0.0, 0.0 },
+    velocity = { 0.0, 0.0 },
+}
+state.game_controller.enabled = true
+
+local last_game_state = nil
+
+function on_cycle()
+    local game_state = state.game_controller_state.game_state
+    if game_state ~= last_game_state then
+        print(string.format("%.1f s: %s (%d:%d)", state.time_elapsed, game_state,
+            state.game_controller.score.hulks, state.game_controller.score.opponent))
+        last_game_state = game_state
+    end
+end
@@ -0,0 +1,70 @@
+local inspect = require 'inspect'
+
+function spawn_robot(number)
+    table.insert(state.robots, create_robot(number))
+end
+
+function spawn_opponent(position, behavior)
+    table.insert(state.opponents, {
+        position = position,
+        orientation = math.pi,
+        behavior = behavior,
+    })
+end
+
+spawn_robot(1)
+spawn_robot(2)
+spawn_robot(3)
+spawn_robot(4)
+spawn_robot(5)
+spawn_robot(6)
+spawn_robot(7)
+
+spawn_opponent({ 4.3, 0.0 }, { Block = { distance = 0.2 } })
+spawn_opponent({ 3.0, -1.0 }, { Block = { distance = 1.5 } })
+spawn_opponent({ 2.0, 1.5 }, { Patrol = { { 2.0, 1.5 }, { 2.0, -1.5 } } })
+spawn_opponent({ 0.5, 0.0 }, "ChaseBall")
+spawn_opponent({ 1.0, 2.0 }, "Static")
+
+local game_end_time = -1.0
+
+function on_goal()
+    print("Goal scored, resetting ball!")
+    print("Ball: " .. inspect(state.ball))
+    state.ball = nil
+    game_end_time = state.cycle_count + 200
+end
+
+function on_cycle()
+    if state.ball == nil and state.cycle_count % 1000 == 0 then
+        state.ball = {
+            position = { 0.0, 0.0 },
+            velocity = { 0.0, 0.0 },
+        }
+    end
+
+    if state.cycle_count == 100 then
+        state.game_controller_state.game_state = "Ready"
+        state.filtered_game_state = {
+            Ready = {
+                kicking_team = "Hulks"
+            }
+        }
+    end
+
+    if state.cycle_count == 1600 then
+        state.filtered_game_state = "Set"
+    end
+
+    if state.cycle_count == 1700 then
+        state.filtered_game_state = {
+            Playing = {
+                ball_is_free = true
+            }
+        }
+    end
+
+    if state.cycle_count == game_end_time then
+        state.finished = true
+    end
+end
@@ -0,0 +1,281 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    mem::take,
+    path::PathBuf,
+    time::Duration,
+};
+
+use mlua::RegistryKey;
+
+use crate::metrics::Metrics;
+
+pub struct Failure {
+    pub cycle: usize,
+    pub message: String,
+}
+
+/// Lua predicate that has to become true before the deadline cycle is reached
+pub struct TimedExpectation {
+    pub deadline: usize,
+    pub predicate: RegistryKey,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct Assertions {
+    passed: usize,
+    failures: Vec<Failure>,
+    pending: Vec<TimedExpectation>,
+}
+
+impl Assertions {
+    pub fn expect(&mut self, cycle: usize, condition: bool, message: String) {
+        if condition {
+            self.passed += 1;
+        } else {
+            self.fail(cycle, message);
+        }
+    }
+
+    pub fn pass(&mut self) {
+        self.passed += 1;
+    }
+
+    pub fn fail(&mut self, cycle: usize, message: String) {
+        self.failures.push(Failure { cycle, message });
+    }
+
+    pub fn add_timed_expectation(&mut self, expectation: TimedExpectation) {
+        self.pending.push(expectation);
+    }
+
+    pub fn take_timed_expectations(&mut self) -> Vec<TimedExpectation> {
+        take(&mut self.pending)
+    }
+
+    /// Passes the expectation once it is satisfied and fails it once the deadline is reached,
+    /// otherwise it stays pending
+    ///
+    /// Returns the predicate of a resolved expectation to release it from the Lua registry.
+    pub fn resolve_timed_expectation(
+        &mut self,
+        cycle: usize,
+        expectation: TimedExpectation,
+        is_satisfied: bool,
+    ) -> Option<RegistryKey> {
+        if is_satisfied {
+            self.pass();
+        } else if cycle < expectation.deadline {
+            self.add_timed_expectation(expectation);
+            return None;
+        } else {
+            self.fail(
+                cycle,
+                format!("{} (not satisfied before deadline)", expectation.message),
+            );
+        }
+        Some(expectation.predicate)
+    }
+
+    /// Fails all expectations that were still pending when the scenario finished
+    pub fn into_report(
+        mut self,
+        scenario: PathBuf,
+        cycle: usize,
+        time: Duration,
+        metrics: Metrics,
+    ) -> Report {
+        for expectation in take(&mut self.pending) {
+            self.fail(
+                cycle,
+                format!(
+                    "{} (scenario finished before deadline at cycle {})",
+                    expectation.message, expectation.deadline
+                ),
+            );
+        }
+        Report {
+            scenario,
+            cycles: cycle,
+            time,
+            passed: self.passed,
+            failures: self.failures,
+            metrics,
+        }
+    }
+}
+
+pub struct Report {
+    pub scenario: PathBuf,
+    pub cycles: usize,
+    pub time: Duration,
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+    pub metrics: Metrics,
+}
+
+impl Report {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        writeln!(
+            formatter,
+            "{}: {} after {} cycles ({:.2} s simulated)",
+            self.scenario.display(),
+            if self.is_success() {
+                "PASSED"
+            } else {
+                "FAILED"
+            },
+            self.cycles,
+            self.time.as_secs_f32(),
+        )?;
+        write!(
+            formatter,
+            "  {} expectation(s) passed, {} failed",
+            self.passed,
+            self.failures.len()
+        )?;
+        write!(
+            formatter,
+            "\n  {} kick(s) ({} by opponents), goals {}:{}, {} robot collision(s)",
+            self.metrics.kicks,
+            self.metrics.opponent_kicks,
+            self.metrics.goals_scored,
+            self.metrics.goals_conceded,
+            self.metrics.robot_collisions,
+        )?;
+        if let Some(time_to_first_kick) = self.metrics.time_to_first_kick {
+            write!(formatter, ", first kick after {time_to_first_kick:.2} s")?;
+        }
+        for failure in &self.failures {
+            write!(
+                formatter,
+                "\n  cycle {:>6}: {}",
+                failure.cycle, failure.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mlua::Lua;
+
+    use super::*;
+
+    fn timed_expectation(lua: &Lua, deadline: usize) -> TimedExpectation {
+        TimedExpectation {
+            deadline,
+            predicate: lua.create_registry_value(true).unwrap(),
+            message: "ball reached".to_string(),
+        }
+    }
+
+    fn report(assertions: Assertions, cycle: usize) -> Report {
+        assertions.into_report(
+            PathBuf::from("scenario.lua"),
+            cycle,
+            Duration::ZERO,
+            Metrics::default(),
+        )
+    }
+
+    #[test]
+    fn satisfied_expectation_passes() {
+        let mut assertions = Assertions::default();
+        assertions.expect(3, true, "robot is standing".to_string());
+
+        let report = report(assertions, 10);
+        assert!(report.is_success());
+        assert_eq!(report.passed, 1);
+    }
+
+    #[test]
+    fn violated_expectation_fails_in_its_cycle() {
+        let mut assertions = Assertions::default();
+        assertions.expect(3, false, "robot is standing".to_string());
+
+        let report = report(assertions, 10);
+        assert!(!report.is_success());
+        assert_eq!(report.passed, 0);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].cycle, 3);
+        assert_eq!(report.failures[0].message, "robot is standing");
+    }
+
+    #[test]
+    fn explicit_failure_fails() {
+        let mut assertions = Assertions::default();
+        assertions.fail(7, "robot left the field".to_string());
+
+        let report = report(assertions, 10);
+        assert!(!report.is_success());
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].cycle, 7);
+    }
+
+    #[test]
+    fn timed_expectation_satisfied_before_deadline_passes() {
+        let lua = Lua::new();
+        let mut assertions = Assertions::default();
+
+        let resolved = assertions.resolve_timed_expectation(5, timed_expectation(&lua, 100), true);
+        assert!(resolved.is_some());
+        assert!(assertions.take_timed_expectations().is_empty());
+
+        let report = report(assertions, 10);
+        assert!(report.is_success());
+        assert_eq!(report.passed, 1);
+    }
+
+    #[test]
+    fn unsatisfied_timed_expectation_stays_pending_before_deadline() {
+        let lua = Lua::new();
+        let mut assertions = Assertions::default();
+
+        let resolved = assertions.resolve_timed_expectation(5, timed_expectation(&lua, 100), false);
+        assert!(resolved.is_none());
+        assert_eq!(assertions.take_timed_expectations().len(), 1);
+    }
+
+    #[test]
+    fn unsatisfied_timed_expectation_fails_at_deadline() {
+        let lua = Lua::new();
+        let mut assertions = Assertions::default();
+
+        let resolved =
+            assertions.resolve_timed_expectation(100, timed_expectation(&lua, 100), false);
+        assert!(resolved.is_some());
+
+        let report = report(assertions, 100);
+        assert!(!report.is_success());
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].cycle, 100);
+        assert_eq!(
+            report.failures[0].message,
+            "ball reached (not satisfied before deadline)"
+        );
+    }
+
+    #[test]
+    fn pending_timed_expectation_fails_when_scenario_finishes() {
+        let lua = Lua::new();
+        let mut assertions = Assertions::default();
+        assertions.add_timed_expectation(timed_expectation(&lua, 100));
+
+        let report = report(assertions, 50);
+        assert!(!report.is_success());
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].cycle, 50);
+        assert_eq!(
+            report.failures[0].message,
+            "ball reached (scenario finished before deadline at cycle 100)"
+        );
+    }
+}
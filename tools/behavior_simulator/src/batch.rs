@@ -0,0 +1,248 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread::{available_parallelism, scope},
+};
+
+use clap::ValueEnum;
+use color_eyre::{eyre::WrapErr, Result};
+use log::info;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::to_writer_pretty;
+
+use crate::{
+    metrics::Metrics,
+    simulator::{Perturbation, Simulator},
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+pub struct BatchParameters {
+    pub runs: usize,
+    pub base_seed: u64,
+    pub jobs: Option<usize>,
+    pub maximum_cycles: usize,
+    pub perturbation: Perturbation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunResult {
+    pub run: usize,
+    pub seed: u64,
+    pub passed: bool,
+    pub cycles: usize,
+    pub simulated_time: f32,
+    pub failed_expectations: usize,
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub metrics: Metrics,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Statistics {
+    pub count: usize,
+    pub mean: f32,
+    pub standard_deviation: f32,
+    pub minimum: f32,
+    pub maximum: f32,
+}
+
+impl Statistics {
+    fn from_samples(samples: impl IntoIterator<Item = f32>) -> Option<Self> {
+        let samples: Vec<f32> = samples.into_iter().collect();
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len();
+        let mean = samples.iter().sum::<f32>() / count as f32;
+        let variance = samples
+            .iter()
+            .map(|sample| (sample - mean).powi(2))
+            .sum::<f32>()
+            / count as f32;
+        Some(Self {
+            count,
+            mean,
+            standard_deviation: variance.sqrt(),
+            minimum: samples.iter().copied().fold(f32::INFINITY, f32::min),
+            maximum: samples.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub runs: usize,
+    pub passed: usize,
+    pub errors: usize,
+    pub simulated_time: Option<Statistics>,
+    pub kicks: Option<Statistics>,
+    pub opponent_kicks: Option<Statistics>,
+    pub time_to_first_kick: Option<Statistics>,
+    pub goals_scored: Option<Statistics>,
+    pub goals_conceded: Option<Statistics>,
+    pub robot_collisions: Option<Statistics>,
+}
+
+impl Summary {
+    fn new(results: &[RunResult]) -> Self {
+        let completed: Vec<_> = results
+            .iter()
+            .filter(|result| result.error.is_none())
+            .collect();
+        let statistics = |metric: fn(&RunResult) -> Option<f32>| {
+            Statistics::from_samples(completed.iter().filter_map(|result| metric(result)))
+        };
+        Self {
+            runs: results.len(),
+            passed: results.iter().filter(|result| result.passed).count(),
+            errors: results.len() - completed.len(),
+            simulated_time: statistics(|result| Some(result.simulated_time)),
+            kicks: statistics(|result| Some(result.metrics.kicks as f32)),
+            opponent_kicks: statistics(|result| Some(result.metrics.opponent_kicks as f32)),
+            time_to_first_kick: statistics(|result| result.metrics.time_to_first_kick),
+            goals_scored: statistics(|result| Some(result.metrics.goals_scored as f32)),
+            goals_conceded: statistics(|result| Some(result.metrics.goals_conceded as f32)),
+            robot_collisions: statistics(|result| Some(result.metrics.robot_collisions as f32)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub scenario: PathBuf,
+    pub summary: Summary,
+    pub runs: Vec<RunResult>,
+}
+
+impl BatchReport {
+    pub fn write(&self, path: impl AsRef<Path>, format: ReportFormat) -> Result<()> {
+        let file = File::create(&path)
+            .wrap_err_with(|| format!("failed to create {}", path.as_ref().display()))?;
+        let mut writer = BufWriter::new(file);
+        match format {
+            ReportFormat::Json => {
+                to_writer_pretty(&mut writer, self).wrap_err("failed to serialize report")?
+            }
+            ReportFormat::Csv => self
+                .write_csv(&mut writer)
+                .wrap_err("failed to write report")?,
+        }
+        writer.flush().wrap_err("failed to flush report")
+    }
+
+    fn write_csv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "run,seed,passed,cycles,simulated_time,failed_expectations,kicks,opponent_kicks,time_to_first_kick,goals_scored,goals_conceded,robot_collisions,error"
+        )?;
+        for result in &self.runs {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{},{},\"{}\"",
+                result.run,
+                result.seed,
+                result.passed,
+                result.cycles,
+                result.simulated_time,
+                result.failed_expectations,
+                result.metrics.kicks,
+                result.metrics.opponent_kicks,
+                result
+                    .metrics
+                    .time_to_first_kick
+                    .map(|time| time.to_string())
+                    .unwrap_or_default(),
+                result.metrics.goals_scored,
+                result.metrics.goals_conceded,
+                result.metrics.robot_collisions,
+                result
+                    .error
+                    .as_deref()
+                    .unwrap_or_default()
+                    .replace('"', "\"\""),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the scenario repeatedly with consecutive seeds distributed over all CPU cores
+pub fn run(scenario_file: &Path, parameters: &BatchParameters) -> BatchReport {
+    let jobs = parameters
+        .jobs
+        .or_else(|| available_parallelism().ok().map(Into::into))
+        .unwrap_or(1)
+        .clamp(1, parameters.runs.max(1));
+    let next_run = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(parameters.runs));
+
+    scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let run = next_run.fetch_add(1, Ordering::SeqCst);
+                if run >= parameters.runs {
+                    break;
+                }
+                let seed = parameters.base_seed + run as u64;
+                let result = run_once(scenario_file, parameters, run, seed);
+                info!(
+                    "run {run} (seed {seed}) {}",
+                    if result.passed { "passed" } else { "failed" }
+                );
+                results.lock().push(result);
+            });
+        }
+    });
+
+    let mut runs = results.into_inner();
+    runs.sort_by_key(|result| result.run);
+    BatchReport {
+        scenario: scenario_file.to_path_buf(),
+        summary: Summary::new(&runs),
+        runs,
+    }
+}
+
+fn run_once(
+    scenario_file: &Path,
+    parameters: &BatchParameters,
+    run: usize,
+    seed: u64,
+) -> RunResult {
+    let report = Simulator::try_new().and_then(|mut simulator| {
+        simulator.seed(seed)?;
+        simulator.execute_script(scenario_file)?;
+        simulator.perturb(&parameters.perturbation);
+        simulator.run_headless(scenario_file, parameters.maximum_cycles)
+    });
+    match report {
+        Ok(report) => RunResult {
+            run,
+            seed,
+            passed: report.is_success(),
+            cycles: report.cycles,
+            simulated_time: report.time.as_secs_f32(),
+            failed_expectations: report.failures.len(),
+            error: None,
+            metrics: report.metrics,
+        },
+        Err(error) => RunResult {
+            run,
+            seed,
+            passed: false,
+            cycles: 0,
+            simulated_time: 0.0,
+            failed_expectations: 0,
+            error: Some(format!("{error:#}")),
+            metrics: Metrics::default(),
+        },
+    }
+}
@@ -9,6 +9,7 @@ use control::{
     motion::look_around::LookAround,
     role_assignment::{self, RoleAssignment},
     rule_obstacle_composer::RuleObstacleComposer,
+    team_ball_filter::{self, TeamBallFilter},
     world_state_composer::{self, WorldStateComposer},
 };
 use cyclers::control::Database;
@@ -21,6 +22,7 @@ pub struct BehaviorCycler<Interface> {
     hardware_interface: Arc<Interface>,
     own_changed: Arc<Notify>,
     role_assignment: RoleAssignment,
+    team_ball_filter: TeamBallFilter,
     ball_state_composer: BallStateComposer,
     active_vision: ActiveVision,
     kick_selector: KickSelector,
@@ -49,6 +51,8 @@ where
             spl_network: &configuration.spl_network,
         })
         .wrap_err("failed to create node `RoleAssignment`")?;
+        let team_ball_filter = TeamBallFilter::new(team_ball_filter::CreationContext {})
+            .wrap_err("failed to create node `TeamBallFilter`")?;
         let ball_state_composer = BallStateComposer::new(ball_state_composer::CreationContext {})
             .wrap_err("failed to create node `BallStateComposer`")?;
         let active_vision = ActiveVision::new(active_vision::CreationContext {
@@ -79,6 +83,7 @@ where
             own_changed,
 
             role_assignment,
+            team_ball_filter,
             ball_state_composer,
             rule_obstacle_composer,
             active_vision,
@@ -130,8 +135,13 @@ where
                     ball_position: own_database.main_outputs.ball_position.as_ref(),
                     fall_state: &own_database.main_outputs.fall_state,
                     game_controller_state: own_database.main_outputs.game_controller_state.as_ref(),
+                    localization_quality: own_database.main_outputs.localization_quality.as_ref(),
                     primary_state: &own_database.main_outputs.primary_state,
                     robot_to_field: own_database.main_outputs.robot_to_field.as_ref(),
+                    robot_to_field_covariance: own_database
+                        .main_outputs
+                        .robot_to_field_covariance
+                        .as_ref(),
                     cycle_time: &own_database.main_outputs.cycle_time,
                     field_dimensions: &configuration.field_dimensions,
                     forced_role: configuration.role_assignment.forced_role.as_ref(),
@@ -140,17 +150,65 @@ where
                     player_number: &configuration.player_number,
                     spl_network: &configuration.spl_network,
                     network_message: PerceptionInput {
-                        persistent: incoming_messages,
+                        persistent: incoming_messages.clone(),
                         temporary: Default::default(),
                     },
                     hardware: &self.hardware_interface,
                 })
                 .wrap_err("failed to execute cycle of node `RoleAssignment`")?;
-            own_database.main_outputs.team_ball = main_outputs.team_ball.value;
             own_database.main_outputs.network_robot_obstacles =
                 main_outputs.network_robot_obstacles.value;
             own_database.main_outputs.role = main_outputs.role.value;
         }
+        {
+            let main_outputs = self
+                .team_ball_filter
+                .cycle(team_ball_filter::CycleContext {
+                    team_ball_filter_hypotheses: AdditionalOutput::new(
+                        false,
+                        &mut own_database.additional_outputs.team_ball_filter_hypotheses,
+                    ),
+                    ball_position: own_database.main_outputs.ball_position.as_ref(),
+                    cycle_time: &own_database.main_outputs.cycle_time,
+                    robot_to_field: own_database.main_outputs.robot_to_field.as_ref(),
+                    robot_to_field_covariance: own_database
+                        .main_outputs
+                        .robot_to_field_covariance
+                        .as_ref(),
+                    field_dimensions: &configuration.field_dimensions,
+                    hypothesis_merge_distance: &configuration
+                        .team_ball_filter
+                        .hypothesis_merge_distance,
+                    hypothesis_timeout: &configuration.team_ball_filter.hypothesis_timeout,
+                    initial_covariance: &configuration.team_ball_filter.initial_covariance,
+                    maximum_message_ball_age: &configuration
+                        .team_ball_filter
+                        .maximum_message_ball_age,
+                    measurement_matching_distance: &configuration
+                        .team_ball_filter
+                        .measurement_matching_distance,
+                    measurement_noise: &configuration.team_ball_filter.measurement_noise,
+                    message_age_noise: &configuration.team_ball_filter.message_age_noise,
+                    minimum_teammate_localization_quality: &configuration
+                        .spl_network
+                        .minimum_teammate_localization_quality,
+                    player_number: &configuration.player_number,
+                    process_noise: &configuration.team_ball_filter.process_noise,
+                    validity_discard_threshold: &configuration
+                        .team_ball_filter
+                        .validity_discard_threshold,
+                    validity_exponential_decay_factor: &configuration
+                        .team_ball_filter
+                        .validity_exponential_decay_factor,
+                    velocity_decay_factor: &configuration.team_ball_filter.velocity_decay_factor,
+                    network_message: PerceptionInput {
+                        persistent: incoming_messages,
+                        temporary: Default::default(),
+                    },
+                })
+                .wrap_err("failed to execute cycle of node `TeamBallFilter`")?;
+            own_database.main_outputs.team_ball = main_outputs.team_ball.value;
+        }
         {
             let main_outputs = self
                 .ball_state_composer
@@ -200,8 +258,12 @@ where
                                 .unwrap(),
                             ball_state: own_database.main_outputs.ball_state.as_ref().unwrap(),
                             obstacles: &own_database.main_outputs.obstacles,
+                            network_robot_obstacles: &own_database
+                                .main_outputs
+                                .network_robot_obstacles,
                             field_dimensions: &configuration.field_dimensions,
                             in_walk_kicks: &configuration.in_walk_kicks,
+                            standalone_kick: &configuration.standalone_kick,
                             angle_distance_weight: &configuration
                                 .kick_selector
                                 .angle_distance_weight,
@@ -228,6 +290,15 @@ where
                                 .kick_selector
                                 .default_kick_strength,
                             corner_kick_strength: &configuration.kick_selector.corner_kick_strength,
+                            standalone_kick_maximum_ball_velocity: &configuration
+                                .kick_selector
+                                .standalone_kick_maximum_ball_velocity,
+                            standalone_kick_minimum_opponent_distance: &configuration
+                                .kick_selector
+                                .standalone_kick_minimum_opponent_distance,
+                            standalone_kick_teammate_matching_distance: &configuration
+                                .kick_selector
+                                .standalone_kick_teammate_matching_distance,
                         })
                         .wrap_err("failed to execute cycle of node `KickSelector`")?
                 };
@@ -244,6 +315,7 @@ where
                 .world_state_composer
                 .cycle(world_state_composer::CycleContext {
                     ball: own_database.main_outputs.ball_state.as_ref(),
+                    ball_trajectory: own_database.main_outputs.ball_trajectory.as_ref(),
                     filtered_game_state: own_database.main_outputs.filtered_game_state.as_ref(),
                     game_controller_state: own_database.main_outputs.game_controller_state.as_ref(),
                     penalty_shot_direction: own_database
@@ -251,6 +323,7 @@ where
                         .penalty_shot_direction
                         .as_ref(),
                     robot_to_field: own_database.main_outputs.robot_to_field.as_ref(),
+                    localization_quality: own_database.main_outputs.localization_quality.as_ref(),
                     kick_decisions: own_database.main_outputs.kick_decisions.as_ref(),
                     instant_kick_decisions: own_database
                         .main_outputs
@@ -285,6 +358,7 @@ where
                     cycle_time: &own_database.main_outputs.cycle_time,
                     configuration: &configuration.behavior,
                     in_walk_kicks: &configuration.in_walk_kicks,
+                    standalone_kick: &configuration.standalone_kick,
                     field_dimensions: &configuration.field_dimensions,
                     lost_ball_parameters: &configuration.behavior.lost_ball,
                     intercept_ball_parameters: &configuration.behavior.intercept_ball,
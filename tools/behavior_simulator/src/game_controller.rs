@@ -0,0 +1,574 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use nalgebra::{distance, point, Point2, Vector2};
+use serde::{Deserialize, Serialize};
+use spl_network_messages::{GameState, Penalty, PlayerNumber, SubState, Team};
+use types::{FieldDimensions, FilteredGameState, GameControllerState};
+
+/// Timings and thresholds of the simulated GameController, times are in seconds
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameControllerParameters {
+    pub initial_duration: f32,
+    pub ready_duration: f32,
+    pub set_duration: f32,
+    pub half_duration: f32,
+    pub half_time_break: f32,
+    /// Team that kicks off in the first half, the other team kicks off in the second half
+    pub kick_off_team: Team,
+    /// Time after a kick-off in which the defending team must not touch the ball
+    pub kick_off_grace_period: f32,
+    pub set_play_duration: f32,
+    pub penalty_duration: f32,
+    /// Distance the ball has to move to end a kick-off or set play early
+    pub ball_moved_distance: f32,
+    /// Distance outside of the field lines at which robots are penalized for leaving the field
+    pub leaving_the_field_margin: f32,
+    /// Distance between the centers of two robots at which they are in contact, slightly larger
+    /// than two robot radii because opponents block our robots at exactly that distance
+    pub contact_distance: f32,
+    /// Minimum speed in m/s towards an opponent in contact to count as pushing
+    pub pushing_speed: f32,
+}
+
+impl Default for GameControllerParameters {
+    fn default() -> Self {
+        Self {
+            initial_duration: 2.0,
+            ready_duration: 45.0,
+            set_duration: 5.0,
+            half_duration: 600.0,
+            half_time_break: 10.0,
+            kick_off_team: Team::Hulks,
+            kick_off_grace_period: 10.0,
+            set_play_duration: 30.0,
+            penalty_duration: 45.0,
+            ball_moved_distance: 0.2,
+            leaving_the_field_margin: 0.3,
+            contact_distance: 0.21,
+            pushing_speed: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Half {
+    #[default]
+    First,
+    Second,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Score {
+    pub hulks: usize,
+    pub opponent: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SetPlay {
+    pub start_time: f32,
+    pub ball_position: Point2<f32>,
+}
+
+/// Referee and GameController that drive the game states according to the simulated events
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameController {
+    /// Scenarios that set the game states themselves leave the GameController disabled
+    pub enabled: bool,
+    pub parameters: GameControllerParameters,
+    pub half: Half,
+    pub score: Score,
+    pub remaining_time_in_half: f32,
+    /// Simulation time at which the current game state was entered
+    pub state_entered_time: f32,
+    pub set_play: Option<SetPlay>,
+    /// Team that touched the ball last, decides about kick-ins, goal kicks and corner kicks, the
+    /// kick-off team until a robot touches the ball
+    pub last_touch: Team,
+}
+
+impl Default for GameController {
+    fn default() -> Self {
+        let parameters = GameControllerParameters::default();
+        Self {
+            enabled: false,
+            remaining_time_in_half: parameters.half_duration,
+            last_touch: parameters.kick_off_team,
+            parameters,
+            half: Half::First,
+            score: Score::default(),
+            state_entered_time: 0.0,
+            set_play: None,
+        }
+    }
+}
+
+pub struct RobotSituation {
+    pub player_number: PlayerNumber,
+    pub position: Point2<f32>,
+    pub velocity: Vector2<f32>,
+    pub is_penalized: bool,
+}
+
+/// Everything the referee observes in one cycle
+pub struct Situation {
+    pub now: Duration,
+    pub time_step: Duration,
+    pub goal_scored_by: Option<Team>,
+    pub ball: Option<Point2<f32>>,
+    pub robots: Vec<RobotSituation>,
+    pub opponents: Vec<Point2<f32>>,
+}
+
+/// Actions of the referee the simulation has to apply
+#[derive(Debug, PartialEq)]
+pub enum Decision {
+    Penalize(PlayerNumber),
+    Unpenalize(PlayerNumber),
+    PlaceBall(Point2<f32>),
+    FinishGame,
+}
+
+impl GameController {
+    pub fn cycle(
+        &mut self,
+        situation: &Situation,
+        state: &mut GameControllerState,
+        field_dimensions: &FieldDimensions,
+    ) -> Vec<Decision> {
+        let now = situation.now.as_secs_f32();
+        let time_in_state = now - self.state_entered_time;
+        let mut decisions = self.update_penalties(situation, state);
+
+        match state.game_state {
+            GameState::Initial => {
+                if time_in_state >= self.parameters.initial_duration {
+                    state.kicking_team = match self.half {
+                        Half::First => self.parameters.kick_off_team,
+                        Half::Second => opposing_team(self.parameters.kick_off_team),
+                    };
+                    self.last_touch = state.kicking_team;
+                    self.enter(GameState::Ready, now, state);
+                }
+            }
+            GameState::Ready => {
+                if time_in_state >= self.parameters.ready_duration {
+                    self.enter(GameState::Set, now, state);
+                    decisions.push(Decision::PlaceBall(Point2::origin()));
+                    decisions.extend(self.penalize_illegal_positions(
+                        situation,
+                        state,
+                        field_dimensions,
+                    ));
+                }
+            }
+            GameState::Set => {
+                if time_in_state >= self.parameters.set_duration {
+                    self.enter(GameState::Playing, now, state);
+                }
+            }
+            GameState::Playing => {
+                if let Some(team) = situation.goal_scored_by {
+                    match team {
+                        Team::Hulks => self.score.hulks += 1,
+                        _ => self.score.opponent += 1,
+                    }
+                    state.kicking_team = opposing_team(team);
+                    self.last_touch = state.kicking_team;
+                    self.enter(GameState::Ready, now, state);
+                } else {
+                    decisions.extend(self.referee_playing(situation, state, field_dimensions));
+                }
+            }
+            GameState::Finished => {
+                if self.half == Half::First && time_in_state >= self.parameters.half_time_break {
+                    self.half = Half::Second;
+                    self.remaining_time_in_half = self.parameters.half_duration;
+                    self.enter(GameState::Initial, now, state);
+                }
+            }
+        }
+
+        if matches!(
+            state.game_state,
+            GameState::Ready | GameState::Set | GameState::Playing
+        ) {
+            self.remaining_time_in_half -= situation.time_step.as_secs_f32();
+            if self.remaining_time_in_half <= 0.0 {
+                self.enter(GameState::Finished, now, state);
+                if self.half == Half::Second {
+                    decisions.push(Decision::FinishGame);
+                }
+            }
+        }
+
+        decisions
+    }
+
+    pub fn filtered_game_state(
+        &self,
+        state: &GameControllerState,
+        now: Duration,
+        ball: Option<Point2<f32>>,
+    ) -> FilteredGameState {
+        let opponent_is_kicking_team =
+            matches!(state.kicking_team, Team::Opponent | Team::Uncertain);
+        match state.game_state {
+            GameState::Initial => FilteredGameState::Initial,
+            GameState::Ready => FilteredGameState::Ready {
+                kicking_team: state.kicking_team,
+            },
+            GameState::Set => FilteredGameState::Set,
+            GameState::Playing => {
+                let is_in_kick_off = now.as_secs_f32() - self.state_entered_time
+                    < self.parameters.kick_off_grace_period
+                    && matches!(
+                        ball,
+                        Some(ball) if distance(&ball, &Point2::origin()) < self.parameters.ball_moved_distance
+                    );
+                FilteredGameState::Playing {
+                    ball_is_free: !(opponent_is_kicking_team
+                        && (is_in_kick_off || state.sub_state.is_some())),
+                }
+            }
+            GameState::Finished => FilteredGameState::Finished,
+        }
+    }
+
+    fn enter(&mut self, game_state: GameState, now: f32, state: &mut GameControllerState) {
+        state.game_state = game_state;
+        state.sub_state = None;
+        state.last_game_state_change = UNIX_EPOCH + Duration::from_secs_f32(now);
+        self.state_entered_time = now;
+        self.set_play = None;
+    }
+
+    fn update_penalties(
+        &mut self,
+        situation: &Situation,
+        state: &mut GameControllerState,
+    ) -> Vec<Decision> {
+        let mut decisions = Vec::new();
+        for robot in &situation.robots {
+            let Some(penalty) = state.penalties[robot.player_number].as_mut() else {
+                continue;
+            };
+            let remaining = remaining_time(penalty);
+            *remaining = remaining.saturating_sub(situation.time_step);
+            if remaining.is_zero() {
+                state.penalties[robot.player_number] = None;
+                decisions.push(Decision::Unpenalize(robot.player_number));
+            }
+        }
+        decisions
+    }
+
+    fn penalize(
+        &self,
+        player_number: PlayerNumber,
+        penalty: fn(Duration) -> Penalty,
+        state: &mut GameControllerState,
+    ) -> Decision {
+        let remaining = Duration::from_secs_f32(self.parameters.penalty_duration.max(0.0));
+        state.penalties[player_number] = Some(penalty(remaining));
+        Decision::Penalize(player_number)
+    }
+
+    /// Robots in the opponent half or, if the opponent kicks off, in the center circle
+    fn penalize_illegal_positions(
+        &self,
+        situation: &Situation,
+        state: &mut GameControllerState,
+        field_dimensions: &FieldDimensions,
+    ) -> Vec<Decision> {
+        let is_defending = state.kicking_team != Team::Hulks;
+        situation
+            .robots
+            .iter()
+            .filter(|robot| !robot.is_penalized)
+            .filter(|robot| {
+                let is_in_center_circle = distance(&robot.position, &Point2::origin())
+                    < field_dimensions.center_circle_diameter / 2.0;
+                robot.position.x > 0.0 || (is_defending && is_in_center_circle)
+            })
+            .map(|robot| {
+                self.penalize(
+                    robot.player_number,
+                    |remaining| Penalty::IllegalPositionInSet { remaining },
+                    state,
+                )
+            })
+            .collect()
+    }
+
+    fn referee_playing(
+        &mut self,
+        situation: &Situation,
+        state: &mut GameControllerState,
+        field_dimensions: &FieldDimensions,
+    ) -> Vec<Decision> {
+        let now = situation.now.as_secs_f32();
+        let mut decisions = Vec::new();
+
+        if let Some(set_play) = &self.set_play {
+            let is_ball_moved = matches!(
+                situation.ball,
+                Some(ball) if distance(&ball, &set_play.ball_position) > self.parameters.ball_moved_distance
+            );
+            if is_ball_moved || now - set_play.start_time >= self.parameters.set_play_duration {
+                state.sub_state = None;
+                self.set_play = None;
+            }
+        }
+
+        if let Some(ball) = situation.ball {
+            if let Some((sub_state, kicking_team, ball_position)) =
+                ball_out(ball, self.last_touch, field_dimensions)
+            {
+                decisions.push(Decision::PlaceBall(ball_position));
+                self.start_set_play(sub_state, kicking_team, ball_position, now, state);
+            }
+        }
+
+        for robot in situation.robots.iter().filter(|robot| !robot.is_penalized) {
+            let margin = self.parameters.leaving_the_field_margin;
+            let has_left_field = robot.position.x.abs() > field_dimensions.length / 2.0 + margin
+                || robot.position.y.abs() > field_dimensions.width / 2.0 + margin;
+            if has_left_field {
+                decisions.push(self.penalize(
+                    robot.player_number,
+                    |remaining| Penalty::LeavingTheField { remaining },
+                    state,
+                ));
+                continue;
+            }
+
+            let is_pushing = situation.opponents.iter().any(|opponent| {
+                let difference = opponent - robot.position;
+                difference.norm() < self.parameters.contact_distance
+                    && robot
+                        .velocity
+                        .dot(&difference.try_normalize(f32::EPSILON).unwrap_or_default())
+                        > self.parameters.pushing_speed
+            });
+            if is_pushing {
+                decisions.push(self.penalize(
+                    robot.player_number,
+                    |remaining| Penalty::PlayerPushing { remaining },
+                    state,
+                ));
+                if let Some(ball) = situation.ball {
+                    self.start_set_play(
+                        SubState::PushingFreeKick,
+                        Team::Opponent,
+                        ball,
+                        now,
+                        state,
+                    );
+                }
+            }
+        }
+
+        decisions
+    }
+
+    fn start_set_play(
+        &mut self,
+        sub_state: SubState,
+        kicking_team: Team,
+        ball_position: Point2<f32>,
+        now: f32,
+        state: &mut GameControllerState,
+    ) {
+        state.sub_state = Some(sub_state);
+        state.kicking_team = kicking_team;
+        self.set_play = Some(SetPlay {
+            start_time: now,
+            ball_position,
+        });
+    }
+}
+
+/// Set play, kicking team and ball placement after the ball left the field
+fn ball_out(
+    ball: Point2<f32>,
+    last_touch: Team,
+    field_dimensions: &FieldDimensions,
+) -> Option<(SubState, Team, Point2<f32>)> {
+    // without knowing who is at fault, no team can be awarded the set play
+    if last_touch == Team::Uncertain {
+        return None;
+    }
+    let half_length = field_dimensions.length / 2.0;
+    let half_width = field_dimensions.width / 2.0;
+    let kicking_team = opposing_team(last_touch);
+    if ball.x.abs() > half_length + field_dimensions.ball_radius {
+        let side = ball.y.signum();
+        let is_attackers_fault = match ball.x > 0.0 {
+            true => last_touch == Team::Hulks,
+            false => last_touch == Team::Opponent,
+        };
+        return Some(if is_attackers_fault {
+            (
+                SubState::GoalKick,
+                kicking_team,
+                point![
+                    (half_length - field_dimensions.goal_box_area_length).copysign(ball.x),
+                    side * field_dimensions.goal_box_area_width / 2.0
+                ],
+            )
+        } else {
+            (
+                SubState::CornerKick,
+                kicking_team,
+                point![half_length.copysign(ball.x), side * half_width],
+            )
+        });
+    }
+    if ball.y.abs() > half_width + field_dimensions.ball_radius {
+        return Some((
+            SubState::KickIn,
+            kicking_team,
+            point![ball.x, half_width.copysign(ball.y)],
+        ));
+    }
+    None
+}
+
+fn opposing_team(team: Team) -> Team {
+    match team {
+        Team::Hulks => Team::Opponent,
+        Team::Opponent => Team::Hulks,
+        Team::Uncertain => Team::Uncertain,
+    }
+}
+
+fn remaining_time(penalty: &mut Penalty) -> &mut Duration {
+    match penalty {
+        Penalty::IllegalBallContact { remaining }
+        | Penalty::PlayerPushing { remaining }
+        | Penalty::IllegalMotionInSet { remaining }
+        | Penalty::InactivePlayer { remaining }
+        | Penalty::IllegalPosition { remaining }
+        | Penalty::LeavingTheField { remaining }
+        | Penalty::RequestForPickup { remaining }
+        | Penalty::LocalGameStuck { remaining }
+        | Penalty::IllegalPositionInSet { remaining }
+        | Penalty::PlayerStance { remaining }
+        | Penalty::Substitute { remaining }
+        | Penalty::Manual { remaining } => remaining,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spl_network_messages::GamePhase;
+    use types::Players;
+
+    use crate::test_fixtures::field_dimensions;
+
+    use super::*;
+
+    fn game_controller_state() -> GameControllerState {
+        GameControllerState {
+            game_state: GameState::Initial,
+            game_phase: GamePhase::Normal,
+            kicking_team: Team::Hulks,
+            last_game_state_change: UNIX_EPOCH,
+            penalties: Players::default(),
+            remaining_amount_of_messages: 1200,
+            sub_state: None,
+        }
+    }
+
+    fn situation(now: Duration, robot_position: Point2<f32>) -> Situation {
+        Situation {
+            now,
+            time_step: Duration::from_millis(100),
+            goal_scored_by: None,
+            ball: Some(Point2::origin()),
+            robots: vec![RobotSituation {
+                player_number: PlayerNumber::Two,
+                position: robot_position,
+                velocity: Vector2::zeros(),
+                is_penalized: false,
+            }],
+            opponents: vec![],
+        }
+    }
+
+    #[test]
+    fn game_proceeds_from_initial_to_playing() {
+        let mut game_controller = GameController::default();
+        let mut state = game_controller_state();
+        let mut game_states = vec![];
+        for cycle in 0..600 {
+            let now = Duration::from_millis(100) * cycle;
+            game_controller.cycle(
+                &situation(now, point![-1.0, 0.0]),
+                &mut state,
+                &field_dimensions(),
+            );
+            if game_states.last() != Some(&state.game_state) {
+                game_states.push(state.game_state);
+            }
+        }
+        assert_eq!(
+            game_states,
+            vec![
+                GameState::Initial,
+                GameState::Ready,
+                GameState::Set,
+                GameState::Playing
+            ]
+        );
+        assert!(state.penalties[PlayerNumber::Two].is_none());
+    }
+
+    #[test]
+    fn robot_in_opponent_half_is_penalized_in_set() {
+        let mut game_controller = GameController::default();
+        let mut state = game_controller_state();
+        state.game_state = GameState::Ready;
+        let decisions = game_controller.cycle(
+            &situation(Duration::from_secs(50), point![1.0, 0.0]),
+            &mut state,
+            &field_dimensions(),
+        );
+        assert_eq!(state.game_state, GameState::Set);
+        assert!(decisions.contains(&Decision::Penalize(PlayerNumber::Two)));
+        assert!(matches!(
+            state.penalties[PlayerNumber::Two],
+            Some(Penalty::IllegalPositionInSet { .. })
+        ));
+    }
+
+    #[test]
+    fn ball_over_own_goal_line_touched_by_us_is_corner_kick() {
+        let (sub_state, kicking_team, position) =
+            ball_out(point![-4.6, 1.0], Team::Hulks, &field_dimensions()).unwrap();
+        assert!(matches!(sub_state, SubState::CornerKick));
+        assert_eq!(kicking_team, Team::Opponent);
+        assert_eq!(position, point![-4.5, 3.0]);
+    }
+
+    #[test]
+    fn ball_out_without_known_last_touch_is_no_set_play() {
+        assert!(ball_out(point![1.0, 3.2], Team::Uncertain, &field_dimensions()).is_none());
+    }
+
+    #[test]
+    fn kick_off_team_touched_the_ball_last() {
+        let mut game_controller = GameController::default();
+        game_controller.last_touch = Team::Uncertain;
+        let mut state = game_controller_state();
+        game_controller.cycle(
+            &situation(Duration::from_secs(50), point![-1.0, 0.0]),
+            &mut state,
+            &field_dimensions(),
+        );
+        assert_eq!(state.game_state, GameState::Ready);
+        assert_eq!(game_controller.last_touch, Team::Hulks);
+    }
+}
@@ -1,24 +1,85 @@
 use std::{io::stdout, path::PathBuf};
 
+use batch::{BatchParameters, ReportFormat};
 use chrono::Local;
-use clap::Parser;
-use color_eyre::{install, Result};
+use clap::{Parser, Subcommand};
+use color_eyre::{eyre::bail, install, Result};
 use fern::{Dispatch, InitError};
 use log::LevelFilter;
+use simulator::{Perturbation, Simulator};
 use tokio_util::sync::CancellationToken;
 
+mod assertions;
+mod batch;
 mod cycler;
+mod game_controller;
 mod interfake;
+mod metrics;
+mod opponent;
+mod perception;
+mod physics;
 mod robot;
 mod server;
 mod simulator;
 mod state;
+#[cfg(test)]
+mod test_fixtures;
+
+/// Scenarios that never finish fail instead of running forever, 50000 cycles are 10 minutes of
+/// simulated time
+const DEFAULT_MAXIMUM_CYCLES: usize = 50_000;
 
 #[derive(Parser)]
 struct Arguments {
-    #[arg(short, long, default_value = "[::]:1337")]
-    listen_address: String,
-    scenario_file: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Simulate a scenario and serve the recorded frames to twix
+    Serve {
+        #[arg(short, long, default_value = "[::]:1337")]
+        listen_address: String,
+        scenario_file: PathBuf,
+    },
+    /// Simulate a scenario without server and report the outcome of its expectations
+    Run {
+        /// Fail the scenario if it has not finished after this many cycles
+        #[arg(long, default_value_t = DEFAULT_MAXIMUM_CYCLES)]
+        maximum_cycles: usize,
+        scenario_file: PathBuf,
+    },
+    /// Simulate a scenario repeatedly with different seeds and perturbations and report metrics
+    Batch {
+        /// Number of simulations
+        #[arg(short, long, default_value_t = 100)]
+        runs: usize,
+        /// Seed of the first simulation, the following simulations use consecutive seeds
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Number of parallel simulations (defaults to the number of CPU cores)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Fail simulations that have not finished after this many cycles
+        #[arg(long, default_value_t = DEFAULT_MAXIMUM_CYCLES)]
+        maximum_cycles: usize,
+        /// Standard deviation of the noise added to initial robot positions in meters
+        #[arg(long, default_value_t = 0.0)]
+        robot_position_noise: f32,
+        /// Standard deviation of the noise added to initial robot orientations in radians
+        #[arg(long, default_value_t = 0.0)]
+        robot_orientation_noise: f32,
+        /// Standard deviation of the noise added to the initial ball position in meters
+        #[arg(long, default_value_t = 0.0)]
+        ball_position_noise: f32,
+        /// File to write the report to
+        #[arg(short, long, default_value = "report.json")]
+        output: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+        scenario_file: PathBuf,
+    },
 }
 
 fn setup_logger(is_verbose: bool) -> Result<(), InitError> {
@@ -43,8 +104,48 @@ fn setup_logger(is_verbose: bool) -> Result<(), InitError> {
 }
 
 fn main() -> Result<()> {
-    setup_logger(true)?;
+    let arguments = Arguments::parse();
+    setup_logger(matches!(arguments.command, Command::Serve { .. }))?;
     install()?;
+
+    match arguments.command {
+        Command::Serve {
+            listen_address,
+            scenario_file,
+        } => serve(listen_address, scenario_file),
+        Command::Run {
+            maximum_cycles,
+            scenario_file,
+        } => run(scenario_file, maximum_cycles),
+        Command::Batch {
+            runs,
+            seed,
+            jobs,
+            maximum_cycles,
+            robot_position_noise,
+            robot_orientation_noise,
+            ball_position_noise,
+            output,
+            format,
+            scenario_file,
+        } => {
+            let parameters = BatchParameters {
+                runs,
+                base_seed: seed,
+                jobs,
+                maximum_cycles,
+                perturbation: Perturbation {
+                    robot_position: robot_position_noise,
+                    robot_orientation: robot_orientation_noise,
+                    ball_position: ball_position_noise,
+                },
+            };
+            run_batch(scenario_file, parameters, output, format)
+        }
+    }
+}
+
+fn serve(listen_address: String, scenario_file: PathBuf) -> Result<()> {
     let keep_running = CancellationToken::new();
     {
         let keep_running = keep_running.clone();
@@ -54,11 +155,34 @@ fn main() -> Result<()> {
         })?;
     }
 
-    let arguments = Arguments::parse();
+    server::run(Some(listen_address), keep_running, scenario_file)
+}
+
+fn run(scenario_file: PathBuf, maximum_cycles: usize) -> Result<()> {
+    let mut simulator = Simulator::try_new()?;
+    simulator.execute_script(&scenario_file)?;
+    let report = simulator.run_headless(&scenario_file, maximum_cycles)?;
+    println!("{report}");
+    if !report.is_success() {
+        bail!("scenario failed");
+    }
+    Ok(())
+}
 
-    server::run(
-        Some(arguments.listen_address),
-        keep_running,
-        arguments.scenario_file,
-    )
+fn run_batch(
+    scenario_file: PathBuf,
+    parameters: BatchParameters,
+    output: PathBuf,
+    format: ReportFormat,
+) -> Result<()> {
+    let report = batch::run(&scenario_file, &parameters);
+    report.write(&output, format)?;
+    println!(
+        "{} of {} runs passed, {} failed with an error, report written to {}",
+        report.summary.passed,
+        report.summary.runs,
+        report.summary.errors,
+        output.display()
+    );
+    Ok(())
 }
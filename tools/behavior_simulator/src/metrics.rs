@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use nalgebra::{distance, Point2};
+use serde::{Deserialize, Serialize};
+
+/// Events counted during a simulation to compare scenario runs
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Metrics {
+    pub kicks: usize,
+    /// Kicks of simulated opponents that hit the ball
+    pub opponent_kicks: usize,
+    /// Simulated time in seconds until the first kick hit the ball
+    pub time_to_first_kick: Option<f32>,
+    pub goals_scored: usize,
+    pub goals_conceded: usize,
+    pub robot_collisions: usize,
+}
+
+/// Tracks which robots touch each other to count each contact only once
+#[derive(Default)]
+pub struct ContactTracker {
+    contacts: HashSet<(usize, usize)>,
+}
+
+impl ContactTracker {
+    /// Returns the number of contacts that started in this cycle
+    pub fn update(&mut self, robots: &[(usize, Point2<f32>)], contact_distance: f32) -> usize {
+        let mut contacts = HashSet::new();
+        for (index, (first_number, first_position)) in robots.iter().enumerate() {
+            for (second_number, second_position) in &robots[index + 1..] {
+                if distance(first_position, second_position) < contact_distance {
+                    contacts.insert((
+                        *first_number.min(second_number),
+                        *first_number.max(second_number),
+                    ));
+                }
+            }
+        }
+        let new_contacts = contacts.difference(&self.contacts).count();
+        self.contacts = contacts;
+        new_contacts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::point;
+
+    use super::*;
+
+    #[test]
+    fn ongoing_contacts_are_counted_once() {
+        let mut tracker = ContactTracker::default();
+        let apart = [(1, point![0.0, 0.0]), (2, point![1.0, 0.0])];
+        let touching = [(1, point![0.0, 0.0]), (2, point![0.1, 0.0])];
+        assert_eq!(tracker.update(&apart, 0.2), 0);
+        assert_eq!(tracker.update(&touching, 0.2), 1);
+        assert_eq!(tracker.update(&touching, 0.2), 0);
+        assert_eq!(tracker.update(&apart, 0.2), 0);
+        assert_eq!(tracker.update(&touching, 0.2), 1);
+    }
+}
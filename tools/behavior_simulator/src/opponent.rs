@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use nalgebra::{distance, point, Point2, UnitComplex, Vector2};
+use serde::{Deserialize, Serialize};
+use types::FieldDimensions;
+
+use crate::{physics::PhysicsParameters, state::Ball};
+
+/// What an opponent robot does in each cycle
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub enum OpponentBehavior {
+    /// Stands still and only acts as obstacle
+    #[default]
+    Static,
+    /// Walks along the waypoints and starts over after reaching the last one
+    Patrol(Vec<Point2<f32>>),
+    /// Walks behind the ball and kicks it towards our goal
+    ChaseBall,
+    /// Stays on the line between the ball and its own goal at the given distance to the goal
+    Block { distance: f32 },
+}
+
+/// Robot of the opposing team controlled by a simple scripted behavior instead of our code
+///
+/// Opponents defend the goal at positive x and attack the goal at negative x.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Opponent {
+    pub position: Point2<f32>,
+    pub orientation: f32,
+    pub behavior: OpponentBehavior,
+    /// Walking speed in m/s
+    pub maximum_speed: f32,
+    /// Turning speed in rad/s
+    pub maximum_turn_speed: f32,
+    /// Distance in meters a kicked ball rolls
+    pub kick_distance: f32,
+    pub next_waypoint: usize,
+    /// Simulation time in seconds of the last kick
+    pub last_kick_time: Option<f32>,
+}
+
+impl Default for Opponent {
+    fn default() -> Self {
+        Self {
+            position: Point2::origin(),
+            orientation: 0.0,
+            behavior: OpponentBehavior::Static,
+            maximum_speed: 0.2,
+            maximum_turn_speed: 1.0,
+            kick_distance: 3.0,
+            next_waypoint: 0,
+            last_kick_time: None,
+        }
+    }
+}
+
+impl Opponent {
+    /// Moves the opponent towards the target of its behavior and kicks the ball if it is in reach
+    ///
+    /// Returns whether the ball was kicked.
+    pub fn step(
+        &mut self,
+        ball: Option<&mut Ball>,
+        now: Duration,
+        time_step: Duration,
+        physics: &PhysicsParameters,
+        field_dimensions: &FieldDimensions,
+    ) -> bool {
+        let own_goal = point![field_dimensions.length / 2.0, 0.0];
+        let opponent_goal = point![-field_dimensions.length / 2.0, 0.0];
+        let ball_position = ball.as_ref().map(|ball| ball.position);
+
+        let target = match &self.behavior {
+            OpponentBehavior::Static => None,
+            OpponentBehavior::Patrol(waypoints) => {
+                let waypoint = waypoints.get(self.next_waypoint % waypoints.len().max(1));
+                if let Some(waypoint) = waypoint {
+                    if distance(&self.position, waypoint) < 0.1 {
+                        self.next_waypoint = (self.next_waypoint + 1) % waypoints.len();
+                    }
+                }
+                waypoint.copied()
+            }
+            OpponentBehavior::ChaseBall => ball_position.map(|ball_position| {
+                let kick_direction = (opponent_goal - ball_position)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(|| -Vector2::x());
+                ball_position
+                    - kick_direction * (physics.robot_radius + field_dimensions.ball_radius)
+            }),
+            OpponentBehavior::Block { distance } => ball_position.map(|ball_position| {
+                let direction = (ball_position - own_goal)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(|| -Vector2::x());
+                own_goal + direction * *distance
+            }),
+        };
+
+        let time_step = time_step.as_secs_f32();
+        if let Some(target) = target {
+            let step = (target - self.position).cap_magnitude(self.maximum_speed * time_step);
+            self.position += step;
+        }
+        if let Some(look_at) = ball_position.or(target) {
+            let direction = look_at - self.position;
+            if direction.norm() > f32::EPSILON {
+                let desired_rotation = UnitComplex::new(self.orientation)
+                    .rotation_to(&UnitComplex::from_cos_sin_unchecked(
+                        direction.x / direction.norm(),
+                        direction.y / direction.norm(),
+                    ))
+                    .angle();
+                let maximum_rotation = self.maximum_turn_speed * time_step;
+                self.orientation += desired_rotation.clamp(-maximum_rotation, maximum_rotation);
+            }
+        }
+
+        let is_chasing_ball = matches!(self.behavior, OpponentBehavior::ChaseBall);
+        match ball {
+            Some(ball) if is_chasing_ball => self.try_kick(ball, now, physics, field_dimensions),
+            _ => false,
+        }
+    }
+
+    fn try_kick(
+        &mut self,
+        ball: &mut Ball,
+        now: Duration,
+        physics: &PhysicsParameters,
+        field_dimensions: &FieldDimensions,
+    ) -> bool {
+        let now = now.as_secs_f32();
+        let is_cooled_down = !matches!(
+            self.last_kick_time,
+            Some(last_kick_time) if now - last_kick_time <= physics.kick_cooldown
+        );
+        let reach = physics.robot_radius + field_dimensions.ball_radius + physics.kick_reach;
+        if !is_cooled_down || distance(&self.position, &ball.position) > reach {
+            return false;
+        }
+        let opponent_goal = point![-field_dimensions.length / 2.0, 0.0];
+        let Some(direction) = (opponent_goal - ball.position).try_normalize(f32::EPSILON) else {
+            return false;
+        };
+        let speed = (2.0 * physics.rolling_deceleration * self.kick_distance.max(0.0)).sqrt();
+        ball.velocity = direction * speed;
+        self.last_kick_time = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+
+    use crate::test_fixtures::field_dimensions;
+
+    use super::*;
+
+    #[test]
+    fn chasing_opponent_kicks_ball_towards_our_goal() {
+        let mut opponent = Opponent {
+            position: point![1.0, 0.0],
+            behavior: OpponentBehavior::ChaseBall,
+            ..Default::default()
+        };
+        let mut ball = Ball {
+            position: point![0.0, 0.0],
+            velocity: Vector2::zeros(),
+        };
+        let time_step = Duration::from_millis(12);
+        let kicked = (0..1000).any(|cycle| {
+            opponent.step(
+                Some(&mut ball),
+                time_step * cycle,
+                time_step,
+                &PhysicsParameters::default(),
+                &field_dimensions(),
+            )
+        });
+        assert!(kicked);
+        assert!(ball.velocity.x < 0.0);
+        assert!(ball.velocity.y.abs() < 1e-3);
+    }
+
+    #[test]
+    fn blocking_opponent_stays_between_ball_and_goal() {
+        let mut opponent = Opponent {
+            position: point![4.0, 0.0],
+            behavior: OpponentBehavior::Block { distance: 1.0 },
+            maximum_speed: 1.0,
+            ..Default::default()
+        };
+        let mut ball = Ball {
+            position: point![4.5, 2.0],
+            velocity: Vector2::zeros(),
+        };
+        let time_step = Duration::from_millis(12);
+        for cycle in 0..1000 {
+            opponent.step(
+                Some(&mut ball),
+                time_step * cycle,
+                time_step,
+                &PhysicsParameters::default(),
+                &field_dimensions(),
+            );
+        }
+        assert!(distance(&opponent.position, &point![4.5, 1.0]) < 0.01);
+        assert_eq!(ball.velocity, vector![0.0, 0.0]);
+    }
+}
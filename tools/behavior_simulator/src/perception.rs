@@ -0,0 +1,336 @@
+use std::{collections::VecDeque, f32::consts::PI, time::Duration};
+
+use nalgebra::{vector, Isometry2, Matrix3, Point2, UnitComplex, Vector2, Vector3};
+use rand::{rngs::StdRng, Rng};
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+use crate::state::Ball;
+
+/// Imperfections of the simulated perception, the defaults describe an omniscient robot
+/// limited only by the field of view of its head
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PerceptionParameters {
+    pub maximum_ball_detection_distance: f32,
+    pub maximum_robot_detection_distance: f32,
+    /// Probability to detect a ball within the field of view per cycle
+    pub ball_detection_probability: f32,
+    /// Probability to detect a robot within the field of view per cycle
+    pub robot_detection_probability: f32,
+    /// Probability to detect a non-existing ball within the field of view per cycle
+    pub ball_false_positive_probability: f32,
+    /// Standard deviation of the ball position per meter of distance
+    pub ball_position_noise: f32,
+    /// Standard deviation of robot positions per meter of distance
+    pub robot_position_noise: f32,
+    /// Time in seconds between taking an image and the detections being available
+    pub latency: f32,
+    /// Time in seconds a ball is kept after it was seen last, similar to the ball filter
+    pub ball_timeout: f32,
+    /// Standard deviation of the localization error of the position in meters
+    pub localization_position_noise: f32,
+    /// Standard deviation of the localization error of the orientation in radians
+    pub localization_angle_noise: f32,
+    /// Time in seconds the localization error needs to decorrelate
+    pub localization_error_time_constant: f32,
+}
+
+impl Default for PerceptionParameters {
+    fn default() -> Self {
+        Self {
+            maximum_ball_detection_distance: 3.0,
+            maximum_robot_detection_distance: 3.0,
+            ball_detection_probability: 1.0,
+            robot_detection_probability: 1.0,
+            ball_false_positive_probability: 0.0,
+            ball_position_noise: 0.0,
+            robot_position_noise: 0.0,
+            latency: 0.0,
+            ball_timeout: 0.0,
+            localization_position_noise: 0.0,
+            localization_angle_noise: 0.0,
+            localization_error_time_constant: 2.0,
+        }
+    }
+}
+
+impl PerceptionParameters {
+    /// Covariance of the simulated localization error as the robot would report it
+    pub fn localization_covariance(&self) -> Matrix3<f32> {
+        Matrix3::from_diagonal(&vector![
+            self.localization_position_noise.powi(2),
+            self.localization_position_noise.powi(2),
+            self.localization_angle_noise.powi(2)
+        ])
+    }
+}
+
+/// What a robot perceives in one cycle, positions and velocities are in robot coordinates
+pub struct Percept {
+    pub ball: Option<PerceivedBall>,
+    pub robots: Vec<Point2<f32>>,
+    pub robot_to_field: Isometry2<f32>,
+}
+
+pub struct PerceivedBall {
+    pub position: Point2<f32>,
+    pub velocity: Vector2<f32>,
+    pub last_seen: Duration,
+}
+
+struct RememberedBall {
+    last_seen: Duration,
+    delivered: Duration,
+    ball: Ball,
+}
+
+/// Detections in field coordinates waiting for the latency to pass
+struct Detections {
+    time: Duration,
+    ball: Option<Ball>,
+    robots: Vec<Point2<f32>>,
+}
+
+#[derive(Default)]
+pub struct Perception {
+    pending_detections: VecDeque<Detections>,
+    last_ball: Option<RememberedBall>,
+    last_robots: Vec<Point2<f32>>,
+    localization_error: Vector3<f32>,
+}
+
+pub struct Sight {
+    pub robot_to_field: Isometry2<f32>,
+    pub head_yaw: f32,
+    pub field_of_view: f32,
+}
+
+impl Sight {
+    fn can_see(&self, position_in_field: Point2<f32>, maximum_distance: f32) -> bool {
+        let position_in_head = UnitComplex::new(self.head_yaw).inverse()
+            * (self.robot_to_field.inverse() * position_in_field).coords;
+        position_in_head.angle(&Vector2::x_axis()).abs() < self.field_of_view / 2.0
+            && position_in_head.norm() < maximum_distance
+    }
+
+    fn distance_to(&self, position_in_field: Point2<f32>) -> f32 {
+        (position_in_field.coords - self.robot_to_field.translation.vector).norm()
+    }
+}
+
+impl Perception {
+    #[allow(clippy::too_many_arguments)]
+    pub fn perceive(
+        &mut self,
+        rng: &mut StdRng,
+        parameters: &PerceptionParameters,
+        now: Duration,
+        time_step: Duration,
+        sight: &Sight,
+        ball: Option<&Ball>,
+        other_robots: &[Point2<f32>],
+    ) -> Percept {
+        self.pending_detections
+            .push_back(detect(rng, parameters, now, sight, ball, other_robots));
+
+        let latency = Duration::from_secs_f32(parameters.latency.max(0.0));
+        while let Some(detections) = self.pending_detections.front() {
+            if detections.time + latency > now {
+                break;
+            }
+            let detections = self.pending_detections.pop_front().unwrap();
+            if let Some(ball) = detections.ball {
+                self.last_ball = Some(RememberedBall {
+                    last_seen: detections.time,
+                    delivered: now,
+                    ball,
+                });
+            }
+            self.last_robots = detections.robots;
+        }
+        let ball_timeout = Duration::from_secs_f32(parameters.ball_timeout.max(0.0));
+        if matches!(&self.last_ball, Some(remembered) if remembered.delivered + ball_timeout < now)
+        {
+            self.last_ball = None;
+        }
+
+        self.update_localization_error(rng, parameters, time_step);
+        let ground_to_field = sight.robot_to_field.inverse();
+        Percept {
+            ball: self.last_ball.as_ref().map(|remembered| PerceivedBall {
+                position: ground_to_field * remembered.ball.position,
+                velocity: ground_to_field * remembered.ball.velocity,
+                last_seen: remembered.last_seen,
+            }),
+            robots: self
+                .last_robots
+                .iter()
+                .map(|position| ground_to_field * *position)
+                .collect(),
+            robot_to_field: sight.robot_to_field
+                * Isometry2::new(self.localization_error.xy(), self.localization_error.z),
+        }
+    }
+
+    /// First order Gauss-Markov process with the configured standard deviations
+    fn update_localization_error(
+        &mut self,
+        rng: &mut StdRng,
+        parameters: &PerceptionParameters,
+        time_step: Duration,
+    ) {
+        let time_constant = parameters
+            .localization_error_time_constant
+            .max(f32::EPSILON);
+        let decay = (-time_step.as_secs_f32() / time_constant).exp();
+        let innovation = (1.0 - decay * decay).sqrt();
+        let standard_deviations = vector![
+            parameters.localization_position_noise,
+            parameters.localization_position_noise,
+            parameters.localization_angle_noise
+        ];
+        for (error, standard_deviation) in self
+            .localization_error
+            .iter_mut()
+            .zip(standard_deviations.iter())
+        {
+            *error = decay * *error + innovation * sample_normal(rng, *standard_deviation);
+        }
+    }
+}
+
+fn detect(
+    rng: &mut StdRng,
+    parameters: &PerceptionParameters,
+    now: Duration,
+    sight: &Sight,
+    ball: Option<&Ball>,
+    other_robots: &[Point2<f32>],
+) -> Detections {
+    let detected_ball = ball
+        .filter(|ball| {
+            sight.can_see(ball.position, parameters.maximum_ball_detection_distance)
+                && rng.gen_bool(parameters.ball_detection_probability.clamp(0.0, 1.0))
+        })
+        .map(|ball| Ball {
+            position: add_noise(
+                rng,
+                ball.position,
+                parameters.ball_position_noise * sight.distance_to(ball.position),
+            ),
+            velocity: ball.velocity,
+        });
+    let false_positive = rng
+        .gen_bool(parameters.ball_false_positive_probability.clamp(0.0, 1.0))
+        .then(|| {
+            let angle = sight.head_yaw + rng.gen_range(-0.5..0.5) * sight.field_of_view;
+            let distance = rng.gen_range(0.0..parameters.maximum_ball_detection_distance.max(0.1));
+            Ball {
+                position: sight.robot_to_field
+                    * Point2::from(UnitComplex::new(angle) * vector![distance, 0.0]),
+                velocity: Vector2::zeros(),
+            }
+        });
+    let robots = other_robots
+        .iter()
+        .filter_map(|position| {
+            let is_detected = sight.can_see(*position, parameters.maximum_robot_detection_distance)
+                && rng.gen_bool(parameters.robot_detection_probability.clamp(0.0, 1.0));
+            is_detected.then(|| {
+                add_noise(
+                    rng,
+                    *position,
+                    parameters.robot_position_noise * sight.distance_to(*position),
+                )
+            })
+        })
+        .collect();
+
+    Detections {
+        time: now,
+        ball: false_positive.or(detected_ball),
+        robots,
+    }
+}
+
+fn add_noise(rng: &mut StdRng, position: Point2<f32>, standard_deviation: f32) -> Point2<f32> {
+    let angle = rng.gen_range(-PI..PI);
+    position + UnitComplex::new(angle) * vector![sample_normal(rng, standard_deviation), 0.0]
+}
+
+pub fn sample_normal(rng: &mut StdRng, standard_deviation: f32) -> f32 {
+    if standard_deviation <= 0.0 {
+        return 0.0;
+    }
+    Normal::new(0.0, standard_deviation)
+        .map(|normal| normal.sample(rng))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::point;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn sight() -> Sight {
+        Sight {
+            robot_to_field: Isometry2::identity(),
+            head_yaw: 0.0,
+            field_of_view: 1.0,
+        }
+    }
+
+    #[test]
+    fn balls_outside_of_field_of_view_are_not_perceived() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut perception = Perception::default();
+        let ball = Ball {
+            position: point![0.0, 1.0],
+            velocity: Vector2::zeros(),
+        };
+        let percept = perception.perceive(
+            &mut rng,
+            &PerceptionParameters::default(),
+            Duration::ZERO,
+            Duration::from_millis(12),
+            &sight(),
+            Some(&ball),
+            &[],
+        );
+        assert!(percept.ball.is_none());
+    }
+
+    #[test]
+    fn detections_are_delayed_by_latency() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut perception = Perception::default();
+        let parameters = PerceptionParameters {
+            latency: 0.075,
+            ..Default::default()
+        };
+        let ball = Ball {
+            position: point![1.0, 0.0],
+            velocity: Vector2::zeros(),
+        };
+        let time_step = Duration::from_millis(50);
+        let perceived: Vec<_> = (0..4)
+            .map(|cycle| {
+                perception
+                    .perceive(
+                        &mut rng,
+                        &parameters,
+                        time_step * cycle,
+                        time_step,
+                        &sight(),
+                        Some(&ball),
+                        &[],
+                    )
+                    .ball
+                    .is_some()
+            })
+            .collect();
+        assert_eq!(perceived, vec![false, false, true, true]);
+    }
+}
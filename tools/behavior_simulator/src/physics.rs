@@ -0,0 +1,292 @@
+use nalgebra::{point, vector, Isometry2, Point2, UnitComplex, Vector2};
+use serde::{Deserialize, Serialize};
+use types::{configuration::InWalkKickInfo, FieldDimensions, Side};
+
+use crate::state::Ball;
+
+/// Additional distance at which robots blocked by each other still count as touching
+pub const CONTACT_TOLERANCE: f32 = 0.01;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PhysicsParameters {
+    /// Deceleration of a rolling ball caused by the carpet in m/s²
+    pub rolling_deceleration: f32,
+    /// Fraction of the normal velocity kept when the ball bounces off a goal post
+    pub goal_post_restitution: f32,
+    /// Fraction of the normal velocity kept when the ball bounces off the field barrier or net
+    pub border_restitution: f32,
+    /// Radius of the circle around a robot's origin the ball cannot enter
+    pub robot_radius: f32,
+    /// Fraction of the normal velocity kept when the ball bounces off a robot
+    pub robot_restitution: f32,
+    /// Maximum distance between the ball and its position in the kick pose for a kick to hit
+    pub kick_reach: f32,
+    /// Minimum time in seconds between two kicks of the same robot
+    pub kick_cooldown: f32,
+}
+
+impl Default for PhysicsParameters {
+    fn default() -> Self {
+        Self {
+            rolling_deceleration: 0.4,
+            goal_post_restitution: 0.5,
+            border_restitution: 0.3,
+            robot_radius: 0.1,
+            robot_restitution: 0.2,
+            kick_reach: 0.1,
+            kick_cooldown: 1.0,
+        }
+    }
+}
+
+/// Whether the ball lies within reach of the kicking foot
+///
+/// The in walk kick offsets describe the kick pose relative to the ball, so in a perfect kick
+/// pose the ball is located at the negated (and for right kicks mirrored) offset.
+pub fn is_ball_in_kick_reach(
+    robot_to_field: Isometry2<f32>,
+    ball_position: Point2<f32>,
+    kick_info: &InWalkKickInfo,
+    kicking_side: Side,
+    kick_reach: f32,
+) -> bool {
+    let offset = match kicking_side {
+        Side::Left => kick_info.offset,
+        Side::Right => vector![kick_info.offset.x, -kick_info.offset.y],
+    };
+    let ball_in_robot = robot_to_field.inverse() * ball_position;
+    (ball_in_robot.coords + offset).norm() < kick_reach
+}
+
+/// Velocity (in robot coordinates) that lets the ball roll the kick's shot distance
+pub fn kick_velocity(
+    kick_info: &InWalkKickInfo,
+    kicking_side: Side,
+    strength: f32,
+    rolling_deceleration: f32,
+) -> Vector2<f32> {
+    let direction = match kicking_side {
+        Side::Left => UnitComplex::new(-kick_info.shot_angle),
+        Side::Right => UnitComplex::new(kick_info.shot_angle),
+    } * Vector2::x();
+    let speed = (2.0 * rolling_deceleration * kick_info.shot_distance * strength.max(0.0)).sqrt();
+    direction * speed
+}
+
+/// Advances the ball by one time step and returns whether a goal was scored
+pub fn move_ball(
+    ball: &mut Ball,
+    time_step: f32,
+    parameters: &PhysicsParameters,
+    field_dimensions: &FieldDimensions,
+    robots_to_field: impl IntoIterator<Item = Isometry2<f32>>,
+) -> bool {
+    let previous_position = ball.position;
+
+    let speed = ball.velocity.norm();
+    if speed > 0.0 {
+        let decelerated_speed = (speed - parameters.rolling_deceleration * time_step).max(0.0);
+        ball.velocity *= decelerated_speed / speed;
+    }
+    ball.position += ball.velocity * time_step;
+
+    let goal_post_radius = field_dimensions.goal_post_diameter / 2.0 + field_dimensions.ball_radius;
+    let goal_post_y =
+        field_dimensions.goal_inner_width / 2.0 + field_dimensions.goal_post_diameter / 2.0;
+    for x in [
+        -field_dimensions.length / 2.0,
+        field_dimensions.length / 2.0,
+    ] {
+        for y in [-goal_post_y, goal_post_y] {
+            collide_with_circle(
+                ball,
+                point![x, y],
+                goal_post_radius,
+                parameters.goal_post_restitution,
+            );
+        }
+    }
+
+    for robot_to_field in robots_to_field {
+        collide_with_circle(
+            ball,
+            Point2::from(robot_to_field.translation.vector),
+            parameters.robot_radius + field_dimensions.ball_radius,
+            parameters.robot_restitution,
+        );
+    }
+
+    let half_goal_width = field_dimensions.goal_inner_width / 2.0 - field_dimensions.ball_radius;
+    let was_inside_goal = previous_position.x.abs() > field_dimensions.length / 2.0
+        && previous_position.y.abs() < half_goal_width;
+    let is_inside_goal = ball.position.x.abs() > field_dimensions.length / 2.0
+        && ball.position.y.abs() < half_goal_width;
+    if was_inside_goal || is_inside_goal {
+        reflect_at_limit(
+            &mut ball.position.x,
+            &mut ball.velocity.x,
+            field_dimensions.length / 2.0 + field_dimensions.goal_depth
+                - field_dimensions.ball_radius,
+            parameters.border_restitution,
+        );
+        if was_inside_goal {
+            reflect_at_limit(
+                &mut ball.position.y,
+                &mut ball.velocity.y,
+                half_goal_width,
+                parameters.border_restitution,
+            );
+        }
+    } else {
+        let barrier_distance = field_dimensions.border_strip_width - field_dimensions.ball_radius;
+        reflect_at_limit(
+            &mut ball.position.x,
+            &mut ball.velocity.x,
+            field_dimensions.length / 2.0 + barrier_distance,
+            parameters.border_restitution,
+        );
+        reflect_at_limit(
+            &mut ball.position.y,
+            &mut ball.velocity.y,
+            field_dimensions.width / 2.0 + barrier_distance,
+            parameters.border_restitution,
+        );
+    }
+
+    let has_crossed_goal_line = previous_position.x.abs()
+        <= field_dimensions.length / 2.0 + field_dimensions.ball_radius
+        && ball.position.x.abs() > field_dimensions.length / 2.0 + field_dimensions.ball_radius;
+    has_crossed_goal_line && ball.position.y.abs() < half_goal_width
+}
+
+/// Moves the position out of all circles with the given radius around the obstacles
+pub fn separate_from_obstacles(
+    mut position: Point2<f32>,
+    obstacles: impl IntoIterator<Item = Point2<f32>>,
+    radius: f32,
+) -> Point2<f32> {
+    for obstacle in obstacles {
+        let difference = position - obstacle;
+        let distance = difference.norm();
+        if distance < radius && distance >= f32::EPSILON {
+            position = obstacle + difference * (radius / distance);
+        }
+    }
+    position
+}
+
+fn collide_with_circle(ball: &mut Ball, center: Point2<f32>, radius: f32, restitution: f32) {
+    let difference = ball.position - center;
+    let distance = difference.norm();
+    if distance >= radius || distance < f32::EPSILON {
+        return;
+    }
+    let normal = difference / distance;
+    ball.position = center + normal * radius;
+    let normal_velocity = ball.velocity.dot(&normal);
+    if normal_velocity < 0.0 {
+        ball.velocity -= (1.0 + restitution) * normal_velocity * normal;
+    }
+}
+
+fn reflect_at_limit(position: &mut f32, velocity: &mut f32, limit: f32, restitution: f32) {
+    if position.abs() <= limit {
+        return;
+    }
+    *position = limit.copysign(*position);
+    if *velocity * position.signum() > 0.0 {
+        *velocity *= -restitution;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::field_dimensions;
+
+    use super::*;
+
+    #[test]
+    fn robots_are_pushed_out_of_obstacles() {
+        let obstacles = [point![1.0, 0.0], point![-1.0, 0.0]];
+        let separated = separate_from_obstacles(point![0.9, 0.0], obstacles, 0.2);
+        assert!((separated - point![0.8, 0.0]).norm() < 1e-6);
+        let free = point![0.0, 0.5];
+        assert_eq!(separate_from_obstacles(free, obstacles, 0.2), free);
+    }
+
+    #[test]
+    fn rolling_ball_comes_to_rest() {
+        let mut ball = Ball {
+            position: point![0.0, 0.0],
+            velocity: vector![1.0, 0.0],
+        };
+        let parameters = PhysicsParameters::default();
+        for _ in 0..1000 {
+            move_ball(&mut ball, 0.012, &parameters, &field_dimensions(), []);
+        }
+        assert_eq!(ball.velocity, Vector2::zeros());
+        let expected_distance = 1.0 / (2.0 * parameters.rolling_deceleration);
+        assert!((ball.position.x - expected_distance).abs() < 0.02);
+    }
+
+    #[test]
+    fn ball_crossing_goal_line_scores_once() {
+        let mut ball = Ball {
+            position: point![4.4, 0.0],
+            velocity: vector![2.0, 0.0],
+        };
+        let goals = (0..100)
+            .filter(|_| {
+                move_ball(
+                    &mut ball,
+                    0.012,
+                    &PhysicsParameters::default(),
+                    &field_dimensions(),
+                    [],
+                )
+            })
+            .count();
+        assert_eq!(goals, 1);
+        assert!(ball.position.x <= 4.5 + 0.5);
+    }
+
+    #[test]
+    fn ball_bounces_off_field_barrier() {
+        let mut ball = Ball {
+            position: point![0.0, 3.6],
+            velocity: vector![0.0, 2.0],
+        };
+        move_ball(
+            &mut ball,
+            0.1,
+            &PhysicsParameters::default(),
+            &field_dimensions(),
+            [],
+        );
+        assert!(ball.position.y <= 3.65);
+        assert!(ball.velocity.y < 0.0);
+    }
+
+    #[test]
+    fn ball_outside_reach_is_not_kicked() {
+        let kick_info = InWalkKickInfo {
+            offset: vector![-0.23, 0.05],
+            ..Default::default()
+        };
+        let robot_to_field = Isometry2::new(vector![1.0, 1.0], 0.0);
+        assert!(is_ball_in_kick_reach(
+            robot_to_field,
+            point![1.23, 0.95],
+            &kick_info,
+            Side::Left,
+            0.1
+        ));
+        assert!(!is_ball_in_kick_reach(
+            robot_to_field,
+            point![2.0, 1.0],
+            &kick_info,
+            Side::Left,
+            0.1
+        ));
+    }
+}
@@ -8,13 +8,13 @@ use std::{
 use color_eyre::{eyre::WrapErr, Result};
 use control::localization::generate_initial_pose;
 use cyclers::control::Database;
-use nalgebra::vector;
+use nalgebra::{vector, Vector2};
 use parameters::directory::deserialize;
 use spl_network_messages::PlayerNumber;
 use structs::Configuration;
 use types::{messages::IncomingMessage, CameraMatrix};
 
-use crate::{cycler::BehaviorCycler, interfake::Interfake};
+use crate::{cycler::BehaviorCycler, interfake::Interfake, perception::Perception};
 
 pub struct Robot {
     pub interface: Arc<Interfake>,
@@ -23,25 +23,16 @@ pub struct Robot {
     pub configuration: Configuration,
     pub is_penalized: bool,
     pub last_kick_time: Duration,
+    pub perception: Perception,
+    /// Velocity in field coordinates the robot walked with before it was blocked by opponents
+    pub walk_velocity: Vector2<f32>,
 }
 
 impl Robot {
     pub fn try_new(player_number: PlayerNumber) -> Result<Self> {
         let interface: Arc<_> = Interfake::default().into();
 
-        let runtime = tokio::runtime::Builder::new_current_thread()
-            .build()
-            .unwrap();
-        let mut configuration: Configuration = runtime.block_on(async {
-            deserialize(
-                "etc/configuration",
-                &format!("behavior_simulator{}", from_player_number(player_number)),
-                &format!("behavior_simulator{}", from_player_number(player_number)),
-            )
-            .await
-            .wrap_err("could not load initial parameters")
-        })?;
-        configuration.player_number = player_number;
+        let configuration = load_configuration(player_number)?;
 
         let cycler = BehaviorCycler::new(interface.clone(), Default::default(), &configuration)
             .wrap_err("failed to create cycler")?;
@@ -60,6 +51,8 @@ impl Robot {
             configuration,
             is_penalized: false,
             last_kick_time: Duration::default(),
+            perception: Perception::default(),
+            walk_velocity: Vector2::zeros(),
         })
     }
 
@@ -82,6 +75,23 @@ impl Robot {
     }
 }
 
+pub fn load_configuration(player_number: PlayerNumber) -> Result<Configuration> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut configuration: Configuration = runtime.block_on(async {
+        deserialize(
+            "etc/configuration",
+            &format!("behavior_simulator{}", from_player_number(player_number)),
+            &format!("behavior_simulator{}", from_player_number(player_number)),
+        )
+        .await
+        .wrap_err("could not load initial parameters")
+    })?;
+    configuration.player_number = player_number;
+    Ok(configuration)
+}
+
 pub fn to_player_number(value: usize) -> Result<PlayerNumber, String> {
     let number = match value {
         1 => PlayerNumber::One,
@@ -5,6 +5,7 @@ use std::{
 };
 
 use crate::{
+    opponent::Opponent,
     robot::to_player_number,
     simulator::{Frame, Simulator},
     state::Ball,
@@ -31,8 +32,10 @@ struct Configuration {
 #[derive(Clone, Default, Serialize, Deserialize, SerializeHierarchy)]
 struct MainOutputs {
     frame_count: usize,
+    time_elapsed: Duration,
     ball: Option<Ball>,
     databases: Players<Option<Database>>,
+    opponents: Vec<Opponent>,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, SerializeHierarchy)]
@@ -71,8 +74,10 @@ async fn timeline_server(
             let mut outputs = outputs_writer.next();
             outputs.main_outputs.frame_count = frames.len();
             let frame = &frames[parameters.selected_frame];
+            outputs.main_outputs.time_elapsed = frame.time_elapsed;
             outputs.main_outputs.ball = frame.ball.clone();
             outputs.main_outputs.databases = frame.robots.clone();
+            outputs.main_outputs.opponents = frame.opponents.clone();
         }
         outputs_changed.notify_waiters();
 
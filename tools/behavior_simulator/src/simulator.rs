@@ -1,14 +1,22 @@
-use std::{fs::read_to_string, path::Path, sync::Arc, time::Duration};
+use std::{fs::read_to_string, mem::take, path::Path, sync::Arc, time::Duration};
 
-use crate::{robot::to_player_number, state::Ball};
+use crate::{
+    assertions::{Assertions, Report, TimedExpectation},
+    opponent::Opponent,
+    perception::sample_normal,
+    robot::{load_configuration, to_player_number},
+    state::Ball,
+};
 use color_eyre::{
     eyre::{eyre, WrapErr},
     Result,
 };
 use cyclers::control::Database;
-use mlua::{Error as LuaError, Function, Lua, LuaSerdeExt, SerializeOptions, Value};
-use nalgebra::{Isometry2, Vector2};
+use mlua::{Error as LuaError, Function, Lua, LuaSerdeExt, SerializeOptions, Table, Value};
+use nalgebra::{vector, Isometry2, Vector2};
 use parking_lot::Mutex;
+use rand::{rngs::StdRng, SeedableRng};
+use spl_network_messages::PlayerNumber;
 use types::Players;
 
 use crate::{
@@ -19,18 +27,31 @@ use crate::{
 const SERIALIZE_OPTIONS: SerializeOptions = SerializeOptions::new().serialize_none_to_null(false);
 
 pub struct Frame {
+    pub time_elapsed: Duration,
     pub ball: Option<Ball>,
     pub robots: Players<Option<Database>>,
+    pub opponents: Vec<Opponent>,
+}
+
+/// Standard deviations of the noise added to the initial state of a scenario
+#[derive(Clone, Debug, Default)]
+pub struct Perturbation {
+    pub robot_position: f32,
+    pub robot_orientation: f32,
+    pub ball_position: f32,
 }
 
 pub struct Simulator {
     pub state: Arc<Mutex<State>>,
+    assertions: Arc<Mutex<Assertions>>,
     lua: Lua,
 }
 
 impl Simulator {
     pub fn try_new() -> Result<Self> {
-        let state = Arc::new(Mutex::new(State::new()));
+        let configuration =
+            load_configuration(PlayerNumber::One).wrap_err("failed to load configuration")?;
+        let state = Arc::new(Mutex::new(State::new(configuration.field_dimensions)));
 
         let lua = Lua::new();
         let create_robot = lua
@@ -44,7 +65,108 @@ impl Simulator {
             .set("create_robot", create_robot)
             .wrap_err("failed to insert create_robot")?;
 
-        Ok(Self { state, lua })
+        let robot_pose = lua
+            .create_function({
+                let state = state.clone();
+                move |lua, player_number: usize| {
+                    let player_number =
+                        to_player_number(player_number).map_err(LuaError::external)?;
+                    let state = state.lock();
+                    let robot_to_field = state
+                        .robots
+                        .get(&player_number)
+                        .and_then(|robot| robot.database.main_outputs.robot_to_field)
+                        .ok_or_else(|| LuaError::external("robot does not exist"))?;
+                    let pose = lua.create_table()?;
+                    pose.set(
+                        "position",
+                        vec![robot_to_field.translation.x, robot_to_field.translation.y],
+                    )?;
+                    pose.set("angle", robot_to_field.rotation.angle())?;
+                    Ok(pose)
+                }
+            })
+            .wrap_err("failed to create function robot_pose")?;
+        lua.globals()
+            .set("robot_pose", robot_pose)
+            .wrap_err("failed to insert robot_pose")?;
+
+        let robot_role = lua
+            .create_function({
+                let state = state.clone();
+                move |lua, player_number: usize| {
+                    let player_number =
+                        to_player_number(player_number).map_err(LuaError::external)?;
+                    let state = state.lock();
+                    let robot = state
+                        .robots
+                        .get(&player_number)
+                        .ok_or_else(|| LuaError::external("robot does not exist"))?;
+                    lua.to_value(&robot.database.main_outputs.role)
+                }
+            })
+            .wrap_err("failed to create function robot_role")?;
+        lua.globals()
+            .set("robot_role", robot_role)
+            .wrap_err("failed to insert robot_role")?;
+
+        let assertions = Arc::new(Mutex::new(Assertions::default()));
+        let expect = lua
+            .create_function({
+                let state = state.clone();
+                let assertions = assertions.clone();
+                move |_, (condition, message): (bool, String)| {
+                    let cycle = state.lock().cycle_count;
+                    assertions.lock().expect(cycle, condition, message);
+                    Ok(())
+                }
+            })
+            .wrap_err("failed to create function expect")?;
+        lua.globals()
+            .set("expect", expect)
+            .wrap_err("failed to insert expect")?;
+
+        let expect_within = lua
+            .create_function({
+                let state = state.clone();
+                let assertions = assertions.clone();
+                move |lua, (cycles, predicate, message): (usize, Function, String)| {
+                    let deadline = state.lock().cycle_count + cycles;
+                    let predicate = lua.create_registry_value(predicate)?;
+                    assertions.lock().add_timed_expectation(TimedExpectation {
+                        deadline,
+                        predicate,
+                        message,
+                    });
+                    Ok(())
+                }
+            })
+            .wrap_err("failed to create function expect_within")?;
+        lua.globals()
+            .set("expect_within", expect_within)
+            .wrap_err("failed to insert expect_within")?;
+
+        let fail = lua
+            .create_function({
+                let state = state.clone();
+                let assertions = assertions.clone();
+                move |lua, message: String| {
+                    let cycle = state.lock().cycle_count;
+                    assertions.lock().fail(cycle, message);
+                    let lua_state: Table = lua.globals().get("state")?;
+                    lua_state.set("finished", true)
+                }
+            })
+            .wrap_err("failed to create function fail")?;
+        lua.globals()
+            .set("fail", fail)
+            .wrap_err("failed to insert fail")?;
+
+        Ok(Self {
+            state,
+            assertions,
+            lua,
+        })
     }
 
     pub fn execute_script(&mut self, file_name: impl AsRef<Path>) -> Result<()> {
@@ -77,7 +199,9 @@ impl Simulator {
                 robots[*player_number] = Some(robot.database.clone())
             }
             frames.push(Frame {
+                time_elapsed: state.time_elapsed,
                 robots,
+                opponents: state.opponents.clone(),
                 ball: state.ball.clone(),
             });
 
@@ -89,6 +213,89 @@ impl Simulator {
         Ok(frames)
     }
 
+    /// Runs the scenario without recording frames and reports the outcome of all expectations
+    pub fn run_headless(
+        mut self,
+        scenario: impl AsRef<Path>,
+        maximum_cycles: usize,
+    ) -> Result<Report> {
+        loop {
+            self.cycle()?;
+
+            let mut state = self.state.lock();
+            if state.cycle_count >= maximum_cycles {
+                let cycle = state.cycle_count;
+                self.assertions
+                    .lock()
+                    .fail(cycle, "maximum number of cycles reached".to_string());
+                state.finished = true;
+            }
+            if state.finished {
+                break;
+            }
+        }
+
+        let (cycles, time_elapsed, metrics) = {
+            let state = self.state.lock();
+            (state.cycle_count, state.time_elapsed, state.metrics.clone())
+        };
+        let assertions = take(&mut *self.assertions.lock());
+        Ok(assertions.into_report(
+            scenario.as_ref().to_path_buf(),
+            cycles,
+            time_elapsed,
+            metrics,
+        ))
+    }
+
+    /// Seeds the simulation's random number generator as well as Lua's `math.random` and
+    /// exposes the seed as global `seed` to the scenario
+    pub fn seed(&mut self, seed: u64) -> Result<()> {
+        self.state.lock().rng = StdRng::seed_from_u64(seed);
+        let math: Table = self
+            .lua
+            .globals()
+            .get("math")
+            .wrap_err("failed to get math library")?;
+        let random_seed: Function = math
+            .get("randomseed")
+            .wrap_err("failed to get math.randomseed")?;
+        random_seed
+            .call::<_, ()>(seed)
+            .wrap_err("failed to seed math.random")?;
+        self.lua
+            .globals()
+            .set("seed", seed)
+            .wrap_err("failed to set seed in lua globals")
+    }
+
+    /// Adds normally distributed noise to the current robot poses and ball position
+    pub fn perturb(&mut self, perturbation: &Perturbation) {
+        let mut state = self.state.lock();
+        let State {
+            robots, ball, rng, ..
+        } = &mut *state;
+        for robot in robots.values_mut() {
+            if let Some(robot_to_field) = robot.database.main_outputs.robot_to_field.as_mut() {
+                *robot_to_field = Isometry2::new(
+                    robot_to_field.translation.vector
+                        + vector![
+                            sample_normal(rng, perturbation.robot_position),
+                            sample_normal(rng, perturbation.robot_position)
+                        ],
+                    robot_to_field.rotation.angle()
+                        + sample_normal(rng, perturbation.robot_orientation),
+                );
+            }
+        }
+        if let Some(ball) = ball.as_mut() {
+            ball.position += vector![
+                sample_normal(rng, perturbation.ball_position),
+                sample_normal(rng, perturbation.ball_position)
+            ];
+        }
+    }
+
     pub fn cycle(&mut self) -> Result<()> {
         let events = {
             let mut state = self.state.lock();
@@ -156,6 +363,7 @@ impl Simulator {
                     Event::Goal => self.execute_event_callback("on_goal")?,
                 }
             }
+            self.check_timed_expectations()?;
 
             Ok(())
         })?;
@@ -163,6 +371,24 @@ impl Simulator {
         self.deserialize_state()
     }
 
+    fn check_timed_expectations(&self) -> Result<(), LuaError> {
+        let cycle = self.state.lock().cycle_count;
+        let expectations = self.assertions.lock().take_timed_expectations();
+        for expectation in expectations {
+            let predicate: Function = self.lua.registry_value(&expectation.predicate)?;
+            let is_satisfied: bool = predicate.call(())?;
+            let resolved_predicate =
+                self.assertions
+                    .lock()
+                    .resolve_timed_expectation(cycle, expectation, is_satisfied);
+            if let Some(predicate) = resolved_predicate {
+                self.lua.remove_registry_value(predicate)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn execute_event_callback(&self, name: &str) -> Result<(), LuaError> {
         if let Ok(on_goal) = self.lua.globals().get::<_, Function>(name) {
             on_goal.call(())?;
@@ -1,23 +1,41 @@
 use std::{
     collections::{BTreeMap, HashMap},
+    f32::consts::FRAC_PI_2,
     mem::take,
-    time::{Duration, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use color_eyre::Result;
+use control::ball_filter::velocity_time_constant;
 use cyclers::control::Database;
-use nalgebra::{vector, Isometry2, Point2, UnitComplex, Vector2};
+use nalgebra::{vector, Isometry2, Matrix4, Point2, UnitComplex, Vector2};
+use rand::{rngs::StdRng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
 use spl_network_messages::{GamePhase, GameState, HulkMessage, PlayerNumber, Team};
 use structs::{control::AdditionalOutputs, Configuration};
 use types::{
     messages::{IncomingMessage, OutgoingMessage},
-    BallPosition, FilteredGameState, GameControllerState, HeadMotion, KickVariant, LineSegment,
-    MotionCommand, OrientationMode, PathSegment, Players, PrimaryState, Side,
+    multivariate_normal_distribution::MultivariateNormalDistribution,
+    BallPosition, BallTrajectory, FieldDimensions, FilteredGameState, GameControllerState,
+    HeadMotion, LineSegment, MotionCommand, Obstacle, OrientationMode, PathSegment, Players,
+    PrimaryState,
 };
 
-use crate::robot::Robot;
+use crate::{
+    game_controller::{Decision, GameController, RobotSituation, Situation},
+    metrics::{ContactTracker, Metrics},
+    opponent::Opponent,
+    perception::{PerceptionParameters, Sight},
+    physics::{
+        is_ball_in_kick_reach, kick_velocity, move_ball, separate_from_obstacles,
+        PhysicsParameters, CONTACT_TOLERANCE,
+    },
+    robot::{from_player_number, Robot},
+};
+
+/// Identifies opponents in the collision tracking, distinct from all player numbers
+const FIRST_OPPONENT_ID: usize = 100;
 
 pub enum Event {
     Cycle,
@@ -34,15 +52,23 @@ pub struct State {
     pub time_elapsed: Duration,
     pub cycle_count: usize,
     pub robots: HashMap<PlayerNumber, Robot>,
+    pub opponents: Vec<Opponent>,
     pub ball: Option<Ball>,
     pub messages: Vec<(PlayerNumber, HulkMessage)>,
     pub finished: bool,
     pub game_controller_state: GameControllerState,
     pub filtered_game_state: FilteredGameState,
+    pub game_controller: GameController,
+    pub field_dimensions: FieldDimensions,
+    pub physics: PhysicsParameters,
+    pub perception: PerceptionParameters,
+    pub rng: StdRng,
+    pub metrics: Metrics,
+    contact_tracker: ContactTracker,
 }
 
 impl State {
-    pub fn new() -> Self {
+    pub fn new(field_dimensions: FieldDimensions) -> Self {
         let robots = HashMap::new();
 
         let game_controller_state = GameControllerState {
@@ -67,11 +93,19 @@ impl State {
             time_elapsed: Duration::ZERO,
             cycle_count: 0,
             robots,
+            opponents: Vec::new(),
             ball: None,
             messages: Vec::new(),
             finished: false,
             game_controller_state,
             filtered_game_state: FilteredGameState::Initial,
+            game_controller: GameController::default(),
+            field_dimensions,
+            physics: PhysicsParameters::default(),
+            perception: PerceptionParameters::default(),
+            rng: StdRng::seed_from_u64(0),
+            metrics: Metrics::default(),
+            contact_tracker: ContactTracker::default(),
         }
     }
 
@@ -81,8 +115,14 @@ impl State {
         let mut events = vec![Event::Cycle];
 
         self.move_robots(time_step);
-        self.cycle_robots(now)?;
+        self.move_opponents(time_step);
+        self.count_robot_collisions();
+        self.cycle_robots(now, time_step)?;
         events.extend(self.move_ball(time_step));
+        if self.game_controller.enabled {
+            let is_goal = events.iter().any(|event| matches!(event, Event::Goal));
+            self.referee(is_goal, time_step);
+        }
 
         self.time_elapsed += time_step;
         self.cycle_count += 1;
@@ -100,6 +140,7 @@ impl State {
                 .expect("simulated robots should always have a known pose");
 
             robot.database.additional_outputs = AdditionalOutputs::default();
+            robot.walk_velocity = Vector2::zeros();
             let head_motion = match &robot.database.main_outputs.motion_command {
                 MotionCommand::Walk {
                     head,
@@ -124,8 +165,15 @@ impl State {
                         OrientationMode::Override(orientation) => *orientation,
                     };
 
+                    robot.walk_velocity = robot_to_field.rotation * step / time_step.as_secs_f32();
+                    let position = separate_from_obstacles(
+                        Point2::from(robot_to_field.translation.vector)
+                            + robot_to_field.rotation * step,
+                        self.opponents.iter().map(|opponent| opponent.position),
+                        2.0 * self.physics.robot_radius,
+                    );
                     *robot_to_field = Isometry2::new(
-                        robot_to_field.translation.vector + robot_to_field.rotation * step,
+                        position.coords,
                         robot_to_field.rotation.angle()
                             + orientation.angle().clamp(
                                 -std::f32::consts::FRAC_PI_4 * time_step.as_secs_f32(),
@@ -135,32 +183,7 @@ impl State {
 
                     head
                 }
-                MotionCommand::InWalkKick {
-                    head,
-                    kick,
-                    kicking_side,
-                    strength,
-                } => {
-                    if let Some(ball) = self.ball.as_mut() {
-                        let side = match kicking_side {
-                            Side::Left => 1.0,
-                            Side::Right => -1.0,
-                        };
-
-                        // TODO: Check if ball is even in range
-                        // let kick_location = robot_to_field * ();
-                        if (self.time_elapsed - robot.last_kick_time).as_secs_f32() > 1.0 {
-                            let direction = match kick {
-                                KickVariant::Forward => vector![1.0, 0.0],
-                                KickVariant::Turn => vector![0.707, 0.707 * side],
-                                KickVariant::Side => vector![0.0, 1.0 * -side],
-                            };
-                            ball.velocity += *robot_to_field * direction * *strength * 2.5;
-                            robot.last_kick_time = self.time_elapsed;
-                        };
-                    }
-                    head
-                }
+                MotionCommand::InWalkKick { head, .. } => head,
                 MotionCommand::SitDown { head } => head,
                 MotionCommand::Stand {
                     head,
@@ -169,6 +192,52 @@ impl State {
                 _ => &HeadMotion::Center,
             };
 
+            let kick = match &robot.database.main_outputs.motion_command {
+                MotionCommand::InWalkKick {
+                    kick,
+                    kicking_side,
+                    strength,
+                    ..
+                } => Some((
+                    &robot.configuration.in_walk_kicks[*kick],
+                    *kicking_side,
+                    *strength,
+                )),
+                MotionCommand::StandaloneKick { kicking_side } => {
+                    Some((&robot.configuration.standalone_kick, *kicking_side, 1.0))
+                }
+                _ => None,
+            };
+            if let (Some((kick_info, kicking_side, strength)), Some(ball)) =
+                (kick, self.ball.as_mut())
+            {
+                let is_cooled_down = (self.time_elapsed - robot.last_kick_time).as_secs_f32()
+                    > self.physics.kick_cooldown;
+                if is_cooled_down
+                    && is_ball_in_kick_reach(
+                        *robot_to_field,
+                        ball.position,
+                        kick_info,
+                        kicking_side,
+                        self.physics.kick_reach,
+                    )
+                {
+                    ball.velocity += *robot_to_field
+                        * kick_velocity(
+                            kick_info,
+                            kicking_side,
+                            strength,
+                            self.physics.rolling_deceleration,
+                        );
+                    robot.last_kick_time = self.time_elapsed;
+                    self.game_controller.last_touch = Team::Hulks;
+                    self.metrics.kicks += 1;
+                    self.metrics
+                        .time_to_first_kick
+                        .get_or_insert(self.time_elapsed.as_secs_f32());
+                }
+            }
+
             let f = self.time_elapsed.as_secs_f32().sin();
             let desired_head_yaw = match head_motion {
                 HeadMotion::ZeroAngles => 0.0,
@@ -193,15 +262,49 @@ impl State {
         }
     }
 
-    fn cycle_robots(&mut self, now: std::time::SystemTime) -> Result<()> {
+    fn move_opponents(&mut self, time_step: Duration) {
+        if !matches!(self.filtered_game_state, FilteredGameState::Playing { .. }) {
+            return;
+        }
+        for opponent in &mut self.opponents {
+            let has_kicked = opponent.step(
+                self.ball.as_mut(),
+                self.time_elapsed,
+                time_step,
+                &self.physics,
+                &self.field_dimensions,
+            );
+            if has_kicked {
+                self.game_controller.last_touch = Team::Opponent;
+                self.metrics.opponent_kicks += 1;
+            }
+        }
+    }
+
+    fn cycle_robots(&mut self, now: SystemTime, time_step: Duration) -> Result<()> {
         let incoming_messages = take(&mut self.messages);
+        let robot_positions: Vec<_> = self
+            .robots
+            .iter()
+            .filter_map(|(player_number, robot)| {
+                let robot_to_field = robot.database.main_outputs.robot_to_field?;
+                Some((
+                    *player_number,
+                    Point2::from(robot_to_field.translation.vector),
+                ))
+            })
+            .collect();
+        let opponent_positions: Vec<_> = self
+            .opponents
+            .iter()
+            .map(|opponent| opponent.position)
+            .collect();
 
         for (player_number, robot) in self.robots.iter_mut() {
-            let robot_to_field = robot
+            let ground_truth_robot_to_field = robot
                 .database
                 .main_outputs
                 .robot_to_field
-                .as_mut()
                 .expect("simulated robots should always have a known pose");
 
             let incoming_messages: Vec<_> = incoming_messages
@@ -213,25 +316,77 @@ impl State {
             let messages = BTreeMap::from_iter([(now, incoming_messages.iter().collect())]);
 
             robot.database.main_outputs.cycle_time.start_time = now;
+            robot.database.main_outputs.cycle_time.last_cycle_duration = time_step;
 
-            robot.database.main_outputs.ball_position = self
-                .ball
-                .as_ref()
-                .map(|ball| BallPosition {
-                    position: robot_to_field.inverse() * ball.position,
-                    velocity: robot_to_field.inverse() * ball.velocity,
-                    last_seen: now,
+            let other_robots: Vec<_> = robot_positions
+                .iter()
+                .filter_map(|(other_player_number, position)| {
+                    (other_player_number != player_number).then_some(*position)
                 })
-                .filter(|ball| {
-                    let head_rotation = UnitComplex::from_angle(
-                        robot.database.main_outputs.sensor_data.positions.head.yaw,
-                    );
-                    let ball_in_head = head_rotation.inverse() * ball.position.coords;
-                    let field_of_view = robot.field_of_view();
-                    let angle_to_ball = ball_in_head.angle(&Vector2::x_axis());
-
-                    angle_to_ball.abs() < field_of_view / 2.0 && ball_in_head.norm() < 3.0
-                });
+                .chain(opponent_positions.iter().copied())
+                .collect();
+            let sight = Sight {
+                robot_to_field: ground_truth_robot_to_field,
+                head_yaw: robot.database.main_outputs.sensor_data.positions.head.yaw,
+                field_of_view: robot.field_of_view(),
+            };
+            let percept = robot.perception.perceive(
+                &mut self.rng,
+                &self.perception,
+                self.time_elapsed,
+                time_step,
+                &sight,
+                self.ball.as_ref(),
+                &other_robots,
+            );
+            robot.database.main_outputs.ball_position = percept.ball.map(|ball| BallPosition {
+                position: ball.position,
+                velocity: ball.velocity,
+                last_seen: UNIX_EPOCH + ball.last_seen,
+            });
+            let ball_filter = &robot.configuration.ball_filter;
+            robot.database.main_outputs.ball_trajectory = percept.ball.as_ref().map(|ball| {
+                let position_variance =
+                    (self.perception.ball_position_noise * ball.position.coords.norm()).powi(2);
+                BallTrajectory::new(
+                    MultivariateNormalDistribution {
+                        mean: vector![
+                            ball.position.x,
+                            ball.position.y,
+                            ball.velocity.x,
+                            ball.velocity.y
+                        ],
+                        covariance: Matrix4::identity() * position_variance,
+                    },
+                    velocity_time_constant(ball_filter.velocity_decay_factor),
+                    ball_filter.minimum_moving_velocity,
+                    ball.velocity.norm() > ball_filter.minimum_moving_velocity,
+                )
+            });
+            robot.database.main_outputs.obstacles = percept
+                .robots
+                .into_iter()
+                .map(|position| {
+                    Obstacle::robot(
+                        position,
+                        robot
+                            .configuration
+                            .obstacle_filter
+                            .robot_obstacle_radius_at_foot_height,
+                        robot
+                            .configuration
+                            .obstacle_filter
+                            .robot_obstacle_radius_at_hip_height,
+                    )
+                })
+                .collect();
+            // the behavior acts on the perceived pose while the simulation continues with the
+            // ground truth
+            robot.database.main_outputs.robot_to_field = Some(percept.robot_to_field);
+            robot.database.main_outputs.robot_to_field_covariance =
+                Some(self.perception.localization_covariance());
+            // the simulated localization is noisy but never lost
+            robot.database.main_outputs.localization_quality = Some(1.0);
 
             robot.database.main_outputs.primary_state =
                 match (robot.is_penalized, self.filtered_game_state) {
@@ -246,11 +401,19 @@ impl State {
             robot.database.main_outputs.game_controller_state = Some(self.game_controller_state);
 
             robot.cycle(messages)?;
+            robot.database.main_outputs.robot_to_field = Some(ground_truth_robot_to_field);
 
             for message in robot.interface.take_outgoing_messages() {
-                if let OutgoingMessage::Spl(message) = message {
-                    self.messages.push((*player_number, message));
-                    self.game_controller_state.remaining_amount_of_messages -= 1
+                match message {
+                    // messages exceeding the budget of the team are not delivered
+                    OutgoingMessage::Spl(message)
+                        if self.game_controller_state.remaining_amount_of_messages > 0 =>
+                    {
+                        self.messages.push((*player_number, message));
+                        self.game_controller_state.remaining_amount_of_messages -= 1
+                    }
+                    // return messages to the GameController do not count towards the budget
+                    OutgoingMessage::Spl(_) | OutgoingMessage::GameController(_) => {}
                 }
             }
         }
@@ -261,16 +424,160 @@ impl State {
     fn move_ball(&mut self, time_step: Duration) -> Vec<Event> {
         let mut events = Vec::new();
         if let Some(ball) = self.ball.as_mut() {
-            ball.position += ball.velocity * time_step.as_secs_f32();
-            ball.velocity *= 0.98;
-
-            if ball.position.x.abs() > 4.5 && ball.position.y < 0.75 {
+            let robots_to_field = self
+                .robots
+                .values()
+                .filter_map(|robot| robot.database.main_outputs.robot_to_field)
+                .chain(self.opponents.iter().map(|opponent| {
+                    Isometry2::new(opponent.position.coords, opponent.orientation)
+                }));
+            let is_goal = move_ball(
+                ball,
+                time_step.as_secs_f32(),
+                &self.physics,
+                &self.field_dimensions,
+                robots_to_field,
+            );
+            if is_goal {
+                if ball.position.x > 0.0 {
+                    self.metrics.goals_scored += 1;
+                } else {
+                    self.metrics.goals_conceded += 1;
+                }
                 events.push(Event::Goal);
             }
         }
         events
     }
 
+    fn count_robot_collisions(&mut self) {
+        let robot_positions: Vec<_> = self
+            .robots
+            .iter()
+            .filter_map(|(player_number, robot)| {
+                let robot_to_field = robot.database.main_outputs.robot_to_field?;
+                Some((
+                    from_player_number(*player_number),
+                    Point2::from(robot_to_field.translation.vector),
+                ))
+            })
+            .chain(
+                self.opponents
+                    .iter()
+                    .enumerate()
+                    .map(|(index, opponent)| (FIRST_OPPONENT_ID + index, opponent.position)),
+            )
+            .collect();
+        self.metrics.robot_collisions += self.contact_tracker.update(
+            &robot_positions,
+            2.0 * self.physics.robot_radius + CONTACT_TOLERANCE,
+        );
+    }
+
+    fn robot_positions(&self) -> HashMap<PlayerNumber, Point2<f32>> {
+        self.robots
+            .iter()
+            .filter_map(|(player_number, robot)| {
+                let robot_to_field = robot.database.main_outputs.robot_to_field?;
+                Some((
+                    *player_number,
+                    Point2::from(robot_to_field.translation.vector),
+                ))
+            })
+            .collect()
+    }
+
+    fn referee(&mut self, is_goal: bool, time_step: Duration) {
+        let ball = self.ball.as_ref().map(|ball| ball.position);
+        let robots = self
+            .robot_positions()
+            .into_iter()
+            .map(|(player_number, position)| RobotSituation {
+                player_number,
+                position,
+                velocity: self.robots[&player_number].walk_velocity,
+                is_penalized: self.robots[&player_number].is_penalized,
+            })
+            .collect();
+        let situation = Situation {
+            now: self.time_elapsed,
+            time_step,
+            goal_scored_by: is_goal.then(|| match ball {
+                Some(ball) if ball.x > 0.0 => Team::Hulks,
+                _ => Team::Opponent,
+            }),
+            ball,
+            robots,
+            opponents: self
+                .opponents
+                .iter()
+                .map(|opponent| opponent.position)
+                .collect(),
+        };
+
+        let decisions = self.game_controller.cycle(
+            &situation,
+            &mut self.game_controller_state,
+            &self.field_dimensions,
+        );
+        for decision in decisions {
+            match decision {
+                Decision::Penalize(player_number) => {
+                    if let Some(robot) = self.robots.get_mut(&player_number) {
+                        robot.is_penalized = true;
+                        // penalized robots are removed from the field
+                        let pose = robot
+                            .database
+                            .main_outputs
+                            .robot_to_field
+                            .get_or_insert_with(Isometry2::identity);
+                        let side = if pose.translation.y < 0.0 { -1.0 } else { 1.0 };
+                        *pose = Isometry2::new(
+                            vector![
+                                pose.translation.x,
+                                side * (self.field_dimensions.width / 2.0
+                                    + self.field_dimensions.border_strip_width
+                                    + 0.5)
+                            ],
+                            -side * FRAC_PI_2,
+                        );
+                    }
+                }
+                Decision::Unpenalize(player_number) => {
+                    if let Some(robot) = self.robots.get_mut(&player_number) {
+                        robot.is_penalized = false;
+                        // robots return on the touchline farther away from the ball
+                        let side = match ball {
+                            Some(ball) if ball.y > 0.0 => -1.0,
+                            _ => 1.0,
+                        };
+                        robot.database.main_outputs.robot_to_field = Some(Isometry2::new(
+                            vector![
+                                -self.field_dimensions.length / 2.0
+                                    + self.field_dimensions.penalty_marker_distance,
+                                side * self.field_dimensions.width / 2.0
+                            ],
+                            -side * FRAC_PI_2,
+                        ));
+                    }
+                }
+                Decision::PlaceBall(position) => {
+                    self.ball = Some(Ball {
+                        position,
+                        velocity: Vector2::zeros(),
+                    });
+                }
+                Decision::FinishGame => self.finished = true,
+            }
+        }
+
+        self.filtered_game_state = self.game_controller.filtered_game_state(
+            &self.game_controller_state,
+            self.time_elapsed,
+            self.ball.as_ref().map(|ball| ball.position),
+        );
+    }
+
     pub fn get_lua_state(&self) -> LuaState {
         LuaState {
             time_elapsed: self.time_elapsed.as_secs_f32(),
@@ -278,6 +585,7 @@ impl State {
             // TODO: Expose robot data to lua again
             // robots: self.robots.iter().map(LuaRobot::new).collect(),
             robots: Default::default(),
+            opponents: self.opponents.clone(),
             ball: self.ball.clone(),
             messages: self.messages.clone(),
 
@@ -285,11 +593,15 @@ impl State {
 
             game_controller_state: self.game_controller_state,
             filtered_game_state: self.filtered_game_state,
+            game_controller: self.game_controller.clone(),
+            physics: self.physics.clone(),
+            perception: self.perception.clone(),
         }
     }
 
     pub fn load_lua_state(&mut self, lua_state: LuaState) -> Result<()> {
         self.ball = lua_state.ball;
+        self.opponents = lua_state.opponents;
         self.cycle_count = lua_state.cycle_count;
         for lua_robot in lua_state.robots {
             let mut robot = Robot::try_new(lua_robot.configuration.player_number)
@@ -303,6 +615,9 @@ impl State {
 
         self.game_controller_state = lua_state.game_controller_state;
         self.filtered_game_state = lua_state.filtered_game_state;
+        self.game_controller = lua_state.game_controller;
+        self.physics = lua_state.physics;
+        self.perception = lua_state.perception;
 
         Ok(())
     }
@@ -313,11 +628,15 @@ pub struct LuaState {
     pub time_elapsed: f32,
     pub cycle_count: usize,
     pub robots: Vec<LuaRobot>,
+    pub opponents: Vec<Opponent>,
     pub ball: Option<Ball>,
     pub messages: Vec<(PlayerNumber, HulkMessage)>,
     pub finished: bool,
     pub game_controller_state: GameControllerState,
     pub filtered_game_state: FilteredGameState,
+    pub game_controller: GameController,
+    pub physics: PhysicsParameters,
+    pub perception: PerceptionParameters,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
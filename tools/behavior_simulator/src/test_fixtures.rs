@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use serde_json::{from_str, Value};
+use types::FieldDimensions;
+
+/// Field dimensions of the default configuration
+pub fn field_dimensions() -> FieldDimensions {
+    let configuration: Value = from_str(include_str!("../../../etc/configuration/default.json"))
+        .expect("failed to parse default configuration");
+    FieldDimensions::deserialize(&configuration["field_dimensions"])
+        .expect("failed to deserialize field dimensions of default configuration")
+}
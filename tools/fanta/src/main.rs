@@ -1,12 +1,16 @@
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 use clap::Parser;
-use color_eyre::{eyre::bail, Result};
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
 use communication::{
     client::{Communication, CyclerOutput, SubscriberMessage},
-    messages::Format,
+    messages::{Format, SubscriptionPolicy},
 };
-use log::{error, info};
+use log::{debug, error, info};
+use tokio::{select, signal::ctrl_c, spawn, sync::mpsc};
 
 use crate::logging::setup_logger;
 
@@ -17,7 +21,44 @@ mod logging;
 struct CommandlineArguments {
     #[clap(short, long, default_value = "localhost")]
     address: String,
-    path: String,
+    /// Outputs to subscribe, e.g. `Control.main.robot_to_field`
+    #[clap(required = true)]
+    paths: Vec<String>,
+    /// Record all subscribed outputs into this file until interrupted
+    #[clap(long)]
+    record: Option<PathBuf>,
+    /// Subscribe in the binary format, e.g. for images, which can only be recorded
+    #[clap(long, requires = "record")]
+    binary: bool,
+    /// Receive at most this many updates per second
+    #[clap(long, conflicts_with_all = ["every_nth_cycle", "on_change"])]
+    maximum_rate: Option<f32>,
+    /// Receive only every n-th cycle
+    #[clap(long, conflicts_with = "on_change")]
+    every_nth_cycle: Option<usize>,
+    /// Receive only cycles in which the output changed
+    #[clap(long)]
+    on_change: bool,
+}
+
+impl CommandlineArguments {
+    fn policy(&self) -> Result<SubscriptionPolicy> {
+        Ok(
+            match (self.maximum_rate, self.every_nth_cycle, self.on_change) {
+                (Some(rate), _, _) => {
+                    if rate.is_nan() || rate <= 0.0 {
+                        bail!("maximum rate has to be positive");
+                    }
+                    SubscriptionPolicy::MaximumRate {
+                        interval: Duration::from_secs_f32(1.0 / rate),
+                    }
+                }
+                (_, Some(n), _) => SubscriptionPolicy::EveryNthCycle { n },
+                (_, _, true) => SubscriptionPolicy::OnChange,
+                _ => SubscriptionPolicy::EveryCycle,
+            },
+        )
+    }
 }
 
 #[tokio::main]
@@ -25,21 +66,68 @@ async fn main() -> Result<()> {
     setup_logger()?;
 
     let arguments = CommandlineArguments::parse();
-    let output_to_subscribe = CyclerOutput::from_str(&arguments.path)?;
+    let outputs = arguments
+        .paths
+        .iter()
+        .map(|path| CyclerOutput::from_str(path))
+        .collect::<Result<Vec<_>>>()?;
+    let format = if arguments.binary {
+        Format::Binary
+    } else {
+        Format::Textual
+    };
+    let policy = arguments.policy()?;
     let communication = Communication::new(Some(format!("ws://{}:1337", arguments.address)), true);
-    let (_uuid, mut receiver) = communication
-        .subscribe_output(output_to_subscribe, Format::Textual)
-        .await;
-    while let Some(message) = receiver.recv().await {
+    if let Some(recording) = &arguments.record {
+        communication
+            .start_recording(recording)
+            .await
+            .wrap_err("failed to start recording")?;
+        info!("Recording to {}", recording.display());
+    }
+
+    let print_output_names = outputs.len() > 1;
+    let (update_sender, mut update_receiver) = mpsc::channel(10);
+    for output in outputs {
+        let (_uuid, mut receiver) = communication
+            .subscribe_output(output.clone(), format, policy)
+            .await;
+        let update_sender = update_sender.clone();
+        spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if update_sender.send((output.clone(), message)).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(update_sender);
+
+    loop {
+        let (output, message) = select! {
+            update = update_receiver.recv() => match update {
+                Some(update) => update,
+                None => break,
+            },
+            _ = ctrl_c() => break,
+        };
         match message {
+            SubscriberMessage::Update { value } if print_output_names => {
+                println!("{output}: {value:#}")
+            }
             SubscriberMessage::Update { value } => println!("{value:#}"),
-            SubscriberMessage::SubscriptionSuccess => info!("Successfully subscribed"),
+            SubscriberMessage::SubscriptionSuccess => info!("Successfully subscribed to {output}"),
             SubscriberMessage::SubscriptionFailure { info } => {
-                error!("Failed to subscribe: {info:?}");
+                error!("Failed to subscribe to {output}: {info:?}");
                 break;
             }
-            SubscriberMessage::UpdateBinary { .. } => bail!("Cannot print binary data"),
+            SubscriberMessage::UpdateBinary { data } => {
+                debug!("Received {} bytes of {output}", data.len())
+            }
         }
     }
+    if arguments.record.is_some() {
+        communication.stop_recording().await;
+    }
     Ok(())
 }
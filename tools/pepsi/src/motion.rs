@@ -0,0 +1,78 @@
+use std::{fs::write, path::PathBuf, time::Duration};
+
+use clap::Subcommand;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+
+use motionfile::{nao_joint_limits, MotionFile};
+use types::Joints;
+
+#[derive(Subcommand)]
+pub enum Arguments {
+    /// Convert a .motion2 or .pose file into a motion file
+    Convert {
+        /// The .motion2 or .pose file to convert
+        input: PathBuf,
+        /// Where to write the motion file (if not given the motion file is printed)
+        output: Option<PathBuf>,
+        /// Seconds to reach the pose when converting a .pose file
+        #[arg(long, default_value_t = 1.0)]
+        pose_duration: f32,
+    },
+    /// Check motion files for joint limits, keyframe durations and conditions
+    Validate {
+        /// The .json, .motion2 or .pose files to validate
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Radians by which joint angles may exceed the joint limits
+        #[arg(long, default_value_t = 0.05)]
+        tolerance: f32,
+    },
+}
+
+pub async fn motion(arguments: Arguments) -> Result<()> {
+    match arguments {
+        Arguments::Convert {
+            input,
+            output,
+            pose_duration,
+        } => {
+            let motion_file =
+                MotionFile::from_any_path(&input, Duration::from_secs_f32(pose_duration))
+                    .wrap_err_with(|| format!("failed to load {input:?}"))?;
+            let contents = serde_json::to_string_pretty(&motion_file)
+                .wrap_err("failed to serialize motion file")?;
+            match output {
+                Some(output) => write(&output, contents + "\n")
+                    .wrap_err_with(|| format!("failed to write {output:?}"))?,
+                None => println!("{contents}"),
+            }
+        }
+        Arguments::Validate { files, tolerance } => {
+            let joint_limits = nao_joint_limits();
+            let mut number_of_invalid_files = 0;
+            for file in files {
+                let motion_file: MotionFile<Joints<f32>> =
+                    MotionFile::from_any_path(&file, Duration::from_secs(1))
+                        .wrap_err_with(|| format!("failed to load {file:?}"))?;
+                let errors = motion_file.validate(&joint_limits, tolerance);
+                if errors.is_empty() {
+                    println!("{}: valid", file.display());
+                    continue;
+                }
+                number_of_invalid_files += 1;
+                println!("{}:", file.display());
+                for error in errors {
+                    println!("- {error}");
+                }
+            }
+            if number_of_invalid_files > 0 {
+                bail!("{number_of_invalid_files} motion file(s) are invalid");
+            }
+        }
+    }
+
+    Ok(())
+}
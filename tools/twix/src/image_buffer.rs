@@ -1,4 +1,7 @@
-use communication::client::{Communication, CyclerOutput, SubscriberMessage};
+use communication::{
+    client::{Communication, CyclerOutput, SubscriberMessage},
+    messages::{Format, SubscriptionPolicy},
+};
 use log::error;
 use tokio::{
     select, spawn,
@@ -23,11 +26,15 @@ pub struct ImageBuffer {
 }
 
 impl ImageBuffer {
-    pub fn new(communication: Communication, output: CyclerOutput) -> Self {
+    pub fn new(
+        communication: Communication,
+        output: CyclerOutput,
+        policy: SubscriptionPolicy,
+    ) -> Self {
         let (command_sender, command_receiver) = mpsc::channel(10);
         spawn(async move {
             let (uuid, receiver) = communication
-                .subscribe_output(output.clone(), communication::messages::Format::Binary)
+                .subscribe_output(output.clone(), Format::Binary, policy)
                 .await;
             image_buffer(receiver, command_receiver).await;
             communication.unsubscribe_output(uuid).await;
@@ -1,8 +1,9 @@
 use std::{
     fmt::{self, Display, Formatter},
+    path::PathBuf,
     str::FromStr,
     sync::Arc,
-    time::SystemTime,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use color_eyre::{
@@ -22,12 +23,13 @@ use eframe::{
 };
 use egui_dock::{DockArea, NodeIndex, TabAddAlign, TabIndex, Tree};
 use fern::{colors::ColoredLevelConfig, Dispatch, InitError};
+use log::{error, info};
 
 use nao::Nao;
 use panel::Panel;
 use panels::{
     BehaviorSimulatorPanel, ImagePanel, ImageSegmentsPanel, LookAtPanel, ManualCalibrationPanel,
-    MapPanel, ParameterPanel, PlotPanel, TextPanel,
+    MapPanel, MotionEditorPanel, ParameterPanel, PlotPanel, TextPanel,
 };
 use serde_json::{from_str, to_string, Value};
 use tokio::sync::mpsc;
@@ -78,6 +80,7 @@ enum SelectablePanel {
     Image(ImagePanel),
     ImageSegments(ImageSegmentsPanel),
     Map(MapPanel),
+    MotionEditor(MotionEditorPanel),
     Parameter(ParameterPanel),
     ManualCalibration(ManualCalibrationPanel),
     LookAt(LookAtPanel),
@@ -104,6 +107,7 @@ impl SelectablePanel {
             "image" => SelectablePanel::Image(ImagePanel::new(nao, value)),
             "image segments" => SelectablePanel::ImageSegments(ImageSegmentsPanel::new(nao, value)),
             "map" => SelectablePanel::Map(MapPanel::new(nao, value)),
+            "motion editor" => SelectablePanel::MotionEditor(MotionEditorPanel::new(nao, value)),
             "parameter" => SelectablePanel::Parameter(ParameterPanel::new(nao, value)),
             "manual calibration" => {
                 SelectablePanel::ManualCalibration(ManualCalibrationPanel::new(nao, value))
@@ -122,6 +126,7 @@ impl SelectablePanel {
             SelectablePanel::Image(panel) => panel.save(),
             SelectablePanel::ImageSegments(panel) => panel.save(),
             SelectablePanel::Map(panel) => panel.save(),
+            SelectablePanel::MotionEditor(panel) => panel.save(),
             SelectablePanel::Parameter(panel) => panel.save(),
             SelectablePanel::ManualCalibration(panel) => panel.save(),
             SelectablePanel::LookAt(panel) => panel.save(),
@@ -141,6 +146,7 @@ impl Widget for &mut SelectablePanel {
             SelectablePanel::Image(panel) => panel.ui(ui),
             SelectablePanel::ImageSegments(panel) => panel.ui(ui),
             SelectablePanel::Map(panel) => panel.ui(ui),
+            SelectablePanel::MotionEditor(panel) => panel.ui(ui),
             SelectablePanel::Parameter(panel) => panel.ui(ui),
             SelectablePanel::ManualCalibration(panel) => panel.ui(ui),
             SelectablePanel::LookAt(panel) => panel.ui(ui),
@@ -157,6 +163,7 @@ impl Display for SelectablePanel {
             SelectablePanel::Image(_) => ImagePanel::NAME,
             SelectablePanel::ImageSegments(_) => ImageSegmentsPanel::NAME,
             SelectablePanel::Map(_) => MapPanel::NAME,
+            SelectablePanel::MotionEditor(_) => MotionEditorPanel::NAME,
             SelectablePanel::Parameter(_) => ParameterPanel::NAME,
             SelectablePanel::ManualCalibration(_) => ManualCalibrationPanel::NAME,
             SelectablePanel::LookAt(_) => LookAtPanel::NAME,
@@ -174,6 +181,7 @@ struct TwixApp {
     tree: Tree<Tab>,
     connection_status: ConnectionStatus,
     connection_receiver: mpsc::Receiver<ConnectionStatus>,
+    recording: Option<PathBuf>,
     visual: Visuals,
 }
 
@@ -236,6 +244,7 @@ impl TwixApp {
             last_focused_tab: (0.into(), 0.into()),
             connection_status,
             connection_receiver,
+            recording: None,
             visual,
         }
     }
@@ -259,6 +268,9 @@ impl App for TwixApp {
                     }
                     if address_input.changed() || address_input.lost_focus() {
                         self.nao.set_address(&self.ip_address);
+                        if self.nao.is_replaying() {
+                            self.connection_intent = false;
+                        }
                     }
                     let (connect_text, color) = match &self.connection_status {
                         ConnectionStatus::Disconnected { connect, .. } => (
@@ -271,14 +283,23 @@ impl App for TwixApp {
                         ),
                         ConnectionStatus::Connecting { .. } => ("Connecting", Color32::YELLOW),
                         ConnectionStatus::Connected { .. } => ("Connected", Color32::GREEN),
+                        ConnectionStatus::Incompatible { .. } => ("Incompatible", Color32::RED),
                     };
                     let connect_text = WidgetText::from(connect_text).color(color);
-                    if ui
-                        .checkbox(&mut self.connection_intent, connect_text)
-                        .changed()
-                    {
+                    let checkbox = ui.checkbox(&mut self.connection_intent, connect_text);
+                    let checkbox = match &self.connection_status {
+                        ConnectionStatus::Incompatible { reason, .. } => {
+                            checkbox.on_hover_text(reason)
+                        }
+                        _ => checkbox,
+                    };
+                    if checkbox.changed() {
                         self.nao.set_connect(self.connection_intent);
                     }
+                    if self.nao.is_replaying() {
+                        ui.label(WidgetText::from("Replaying").color(Color32::LIGHT_BLUE));
+                    }
+                    self.recording_ui(ui);
 
                     if self.active_tab_index() != Some(self.last_focused_tab) {
                         self.last_focused_tab =
@@ -374,6 +395,39 @@ impl App for TwixApp {
 }
 
 impl TwixApp {
+    fn recording_ui(&mut self, ui: &mut Ui) {
+        let (text, hover_text) = match &self.recording {
+            Some(path) => ("⏹ Stop", format!("Recording to {}", path.display())),
+            None => (
+                "⏺ Record",
+                "Record all subscribed outputs to a file which can be replayed by entering its path as address".to_string(),
+            ),
+        };
+        if !ui.button(text).on_hover_text(hover_text).clicked() {
+            return;
+        }
+        match self.recording.take() {
+            Some(path) => {
+                self.nao.stop_recording();
+                info!("Stopped recording to {}", path.display());
+            }
+            None => {
+                let seconds_since_epoch = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or_default();
+                let path = PathBuf::from(format!("twix_{seconds_since_epoch}.hulklog"));
+                match self.nao.start_recording(&path) {
+                    Ok(()) => {
+                        info!("Recording to {}", path.display());
+                        self.recording = Some(path);
+                    }
+                    Err(error) => error!("{error:#}"),
+                }
+            }
+        }
+    }
+
     fn active_panel(&mut self) -> Option<&mut SelectablePanel> {
         let (_viewport, tab) = self.tree.find_active_focused()?;
         Some(&mut tab.panel)
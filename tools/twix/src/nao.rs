@@ -1,10 +1,12 @@
-use std::{collections::BTreeSet, sync::Mutex};
+use std::{collections::BTreeSet, path::Path as FilePath, sync::Mutex};
 
+use color_eyre::Result;
 use communication::{
-    client::{Communication, ConnectionStatus, CyclerOutput},
-    messages::{Fields, Path},
+    client::{Communication, ConnectionStatus, CyclerOutput, Replay},
+    messages::{Fields, Path, SubscriptionPolicy},
 };
 
+use log::error;
 use serde_json::Value;
 use tokio::runtime::{Builder, Runtime};
 
@@ -14,49 +16,100 @@ pub struct Nao {
     communication: Communication,
     runtime: Runtime,
     address: Mutex<Option<String>>,
+    replay: Mutex<Option<Replay>>,
 }
 
 impl Nao {
     pub fn new(address: Option<String>, connect: bool) -> Self {
         let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
         let _guard = runtime.enter();
-        let communication = Communication::new(
-            address
-                .as_ref()
-                .map(|ip_address| ip_address_to_communication_url(ip_address)),
-            connect,
-        );
-        Self {
+        let recording = address.clone().filter(|address| is_recording(address));
+        let communication = if recording.is_some() {
+            Communication::new(None, false)
+        } else {
+            Communication::new(
+                address
+                    .as_ref()
+                    .map(|ip_address| ip_address_to_communication_url(ip_address)),
+                connect,
+            )
+        };
+        let nao = Self {
             communication,
             runtime,
             address: Mutex::new(address),
+            replay: Mutex::new(None),
+        };
+        if let Some(recording) = recording {
+            nao.start_replay(&recording);
         }
+        nao
     }
 
     pub fn set_connect(&self, connect: bool) {
+        if connect {
+            self.replay.lock().unwrap().take();
+        }
         self.runtime
             .block_on(self.communication.set_connect(connect))
     }
 
+    /// Connects to the robot at the address or replays the recording if the address is a file
     pub fn set_address(&self, address: &str) {
         {
             let mut current_address = self.address.lock().unwrap();
+            let is_unchanged = current_address.as_deref() == Some(address);
+            if is_unchanged && self.is_replaying() {
+                return;
+            }
             *current_address = Some(address.to_string());
         }
+        if is_recording(address) {
+            self.start_replay(address);
+            return;
+        }
+        self.replay.lock().unwrap().take();
         self.runtime.block_on(
             self.communication
                 .set_address(ip_address_to_communication_url(address)),
         );
     }
 
+    fn start_replay(&self, path: &str) {
+        self.replay.lock().unwrap().take();
+        self.runtime.block_on(self.communication.set_connect(false));
+        let _guard = self.runtime.enter();
+        match self.communication.replay(path) {
+            Ok(replay) => *self.replay.lock().unwrap() = Some(replay),
+            Err(error) => error!("{error:#}"),
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.lock().unwrap().is_some()
+    }
+
+    pub fn start_recording(&self, path: impl AsRef<FilePath>) -> Result<()> {
+        self.runtime
+            .block_on(self.communication.start_recording(path))
+    }
+
+    pub fn stop_recording(&self) {
+        self.runtime.block_on(self.communication.stop_recording())
+    }
+
     pub fn subscribe_output(&self, output: CyclerOutput) -> ValueBuffer {
         let _guard = self.runtime.enter();
-        ValueBuffer::output(self.communication.clone(), output)
+        ValueBuffer::output(
+            self.communication.clone(),
+            output,
+            SubscriptionPolicy::EveryCycle,
+        )
     }
 
-    pub fn subscribe_image(&self, output: CyclerOutput) -> ImageBuffer {
+    pub fn subscribe_image(&self, output: CyclerOutput, policy: SubscriptionPolicy) -> ImageBuffer {
         let _guard = self.runtime.enter();
-        ImageBuffer::new(self.communication.clone(), output)
+        ImageBuffer::new(self.communication.clone(), output, policy)
     }
 
     pub fn subscribe_parameter(&self, path: &str) -> ValueBuffer {
@@ -90,6 +143,10 @@ impl Nao {
     }
 }
 
+fn is_recording(address: &str) -> bool {
+    FilePath::new(address).is_file()
+}
+
 fn ip_address_to_communication_url(ip_address: &str) -> String {
     format!("ws://{ip_address}:1337")
 }
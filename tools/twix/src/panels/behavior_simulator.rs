@@ -1,29 +1,37 @@
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 use communication::client::CyclerOutput;
-use eframe::egui::{Response, Slider, Ui, Widget};
-use serde_json::Value;
+use eframe::egui::{Button, Response, Slider, Ui, Widget};
+use serde_json::{json, Value};
 use tokio::sync::mpsc;
 
 use crate::{nao::Nao, panel::Panel, value_buffer::ValueBuffer};
 
+/// The behavior simulator records one frame per control cycle of 12 ms
+const FRAMES_PER_SECOND: f64 = 1.0 / 0.012;
+const FAST_STEP: usize = 10;
+
 pub struct BehaviorSimulatorPanel {
     nao: Arc<Nao>,
     update_notify_receiver: mpsc::Receiver<()>,
 
     selected_frame: usize,
     selected_robot: usize,
-    playing: bool,
-    playing_start: f64,
+    is_playing: bool,
+    is_looping: bool,
+    speed: f64,
+    playback_position: f64,
+    last_playback_time: Option<f64>,
 
     value_buffer: ValueBuffer,
     frame_count: ValueBuffer,
+    time_elapsed: ValueBuffer,
 }
 
 impl Panel for BehaviorSimulatorPanel {
     const NAME: &'static str = "Behavior Simulator";
 
-    fn new(nao: Arc<Nao>, _value: Option<&Value>) -> Self {
+    fn new(nao: Arc<Nao>, value: Option<&Value>) -> Self {
         let value_buffer = nao.subscribe_parameter("selected_frame");
         let (update_notify_sender, update_notify_receiver) = mpsc::channel(1);
         value_buffer.listen_to_updates(update_notify_sender);
@@ -31,19 +39,82 @@ impl Panel for BehaviorSimulatorPanel {
         let frame_count = nao.subscribe_output(
             CyclerOutput::from_str("BehaviorSimulator.main_outputs.frame_count").unwrap(),
         );
+        let time_elapsed = nao.subscribe_output(
+            CyclerOutput::from_str("BehaviorSimulator.main_outputs.time_elapsed").unwrap(),
+        );
+
+        let selected_robot = value
+            .and_then(|value| value.get("selected_robot"))
+            .and_then(|value| value.as_u64())
+            .unwrap_or(1) as usize;
+        nao.update_parameter_value("selected_robot", selected_robot.into());
+        let speed = value
+            .and_then(|value| value.get("speed"))
+            .and_then(|value| value.as_f64())
+            .unwrap_or(1.0);
+        let is_looping = value
+            .and_then(|value| value.get("is_looping"))
+            .and_then(|value| value.as_bool())
+            .unwrap_or(true);
+
         Self {
             nao,
             update_notify_receiver,
 
             selected_frame: 0,
-            selected_robot: 0,
-            playing: false,
-            playing_start: 0.0,
+            selected_robot,
+            is_playing: false,
+            is_looping,
+            speed,
+            playback_position: 0.0,
+            last_playback_time: None,
 
             value_buffer,
             frame_count,
+            time_elapsed,
         }
     }
+
+    fn save(&self) -> Value {
+        json!({
+            "selected_robot": self.selected_robot,
+            "speed": self.speed,
+            "is_looping": self.is_looping,
+        })
+    }
+}
+
+impl BehaviorSimulatorPanel {
+    fn frame_count(&self) -> usize {
+        self.frame_count
+            .get_latest()
+            .ok()
+            .and_then(|value| value.as_u64())
+            .unwrap_or(1)
+            .max(1) as usize
+    }
+
+    /// Advances the playback by the time since the last repaint scaled by the speed
+    fn play(&mut self, ui: &Ui, frame_count: usize) -> Option<usize> {
+        let now = ui.input(|input| input.time);
+        let time_since_last_playback = now - self.last_playback_time.unwrap_or(now);
+        self.last_playback_time = Some(now);
+        self.playback_position += time_since_last_playback * FRAMES_PER_SECOND * self.speed;
+
+        let last_frame = (frame_count - 1) as f64;
+        if self.playback_position > last_frame {
+            if self.is_looping {
+                self.playback_position %= frame_count as f64;
+            } else {
+                self.playback_position = last_frame;
+                self.is_playing = false;
+            }
+        }
+        ui.ctx().request_repaint();
+
+        let frame = self.playback_position as usize;
+        (frame != self.selected_frame).then_some(frame)
+    }
 }
 
 impl Widget for &mut BehaviorSimulatorPanel {
@@ -53,27 +124,77 @@ impl Widget for &mut BehaviorSimulatorPanel {
                 self.selected_frame = value;
             }
         }
+        let frame_count = self.frame_count();
+        let last_frame = frame_count - 1;
         let mut new_frame = None;
+
         let response = ui
             .vertical(|ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("⏮").on_hover_text("First frame").clicked() {
+                        new_frame = Some(0);
+                    }
+                    if ui
+                        .button("⏪")
+                        .on_hover_text(format!("{FAST_STEP} frames back"))
+                        .clicked()
+                    {
+                        new_frame = Some(self.selected_frame.saturating_sub(FAST_STEP));
+                    }
+                    if ui.button("◀").on_hover_text("Previous frame").clicked() {
+                        new_frame = Some(self.selected_frame.saturating_sub(1));
+                    }
+                    let play_pause = if self.is_playing { "⏸" } else { "▶" };
+                    if ui
+                        .add(Button::new(play_pause).min_size([30.0, 0.0].into()))
+                        .on_hover_text("Play/Pause")
+                        .clicked()
+                    {
+                        self.is_playing = !self.is_playing;
+                        self.playback_position = self.selected_frame as f64;
+                        self.last_playback_time = None;
+                    }
+                    if ui.button("▶|").on_hover_text("Next frame").clicked() {
+                        new_frame = Some((self.selected_frame + 1).min(last_frame));
+                    }
+                    if ui
+                        .button("⏩")
+                        .on_hover_text(format!("{FAST_STEP} frames forward"))
+                        .clicked()
+                    {
+                        new_frame = Some((self.selected_frame + FAST_STEP).min(last_frame));
+                    }
+                    if ui.button("⏭").on_hover_text("Last frame").clicked() {
+                        new_frame = Some(last_frame);
+                    }
+                    ui.add(
+                        Slider::new(&mut self.speed, 0.05..=20.0)
+                            .logarithmic(true)
+                            .max_decimals(2)
+                            .suffix("×")
+                            .text("Speed"),
+                    );
+                    ui.checkbox(&mut self.is_looping, "Loop");
+
+                    let time_elapsed = self
+                        .time_elapsed
+                        .parse_latest::<Duration>()
+                        .map(|time_elapsed| time_elapsed.as_secs_f64())
+                        .unwrap_or(self.selected_frame as f64 / FRAMES_PER_SECOND);
+                    ui.label(format!(
+                        "Frame {} / {last_frame}, {time_elapsed:.3} s",
+                        self.selected_frame
+                    ));
+                });
                 ui.horizontal(|ui| {
                     ui.style_mut().spacing.slider_width = ui.available_size().x - 100.0;
                     let mut frame = self.selected_frame;
                     if ui
                         .add_sized(
                             ui.available_size(),
-                            Slider::new(
-                                &mut frame,
-                                0..=self
-                                    .frame_count
-                                    .get_latest()
-                                    .ok()
-                                    .and_then(|v| v.as_u64())
-                                    .unwrap_or(1) as usize
-                                    - 1,
-                            )
-                            .smart_aim(false)
-                            .text("Frame"),
+                            Slider::new(&mut frame, 0..=last_frame)
+                                .smart_aim(false)
+                                .text("Frame"),
                         )
                         .changed()
                     {
@@ -94,23 +215,17 @@ impl Widget for &mut BehaviorSimulatorPanel {
                             .update_parameter_value("selected_robot", self.selected_robot.into());
                     };
                 });
-                if ui.checkbox(&mut self.playing, "Play").changed() || new_frame.is_some() {
-                    self.playing_start = ui.input(|input| input.time)
-                        - new_frame.unwrap_or(self.selected_frame) as f64 / 83.0;
-                };
             })
             .response;
 
-        if self.playing {
-            let now = ui.input(|input| input.time);
-            let time_elapsed = now - self.playing_start;
-            new_frame = Some((time_elapsed * 83.0) as usize);
-        }
-        if ui.button(">>").clicked() {
-            new_frame = Some(new_frame.unwrap_or(self.selected_frame) + 10);
+        match new_frame {
+            // manual navigation continues the playback from the selected frame
+            Some(frame) => self.playback_position = frame as f64,
+            None if self.is_playing => new_frame = self.play(ui, frame_count),
+            None => self.last_playback_time = None,
         }
         if let Some(new_frame) = new_frame {
-            self.selected_frame = new_frame % self.frame_count.require_latest().unwrap_or(1);
+            self.selected_frame = new_frame.min(last_frame);
             self.nao
                 .update_parameter_value("selected_frame", self.selected_frame.into());
         }
@@ -1,7 +1,10 @@
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 use color_eyre::{eyre::eyre, Result};
-use communication::client::{Cycler, CyclerOutput, Output};
+use communication::{
+    client::{Cycler, CyclerOutput, Output},
+    messages::SubscriptionPolicy,
+};
 use eframe::{
     egui::{ComboBox, Response, TextureOptions, Ui, Widget},
     emath::Rect,
@@ -10,7 +13,7 @@ use egui_extras::RetainedImage;
 use log::error;
 use nalgebra::{vector, Similarity2};
 use serde::{Deserialize, Serialize};
-use serde_json::{from_value, json, Value};
+use serde_json::{from_value, json, to_value, Value};
 
 use crate::{
     image_buffer::ImageBuffer,
@@ -44,12 +47,38 @@ impl ImageKind {
     }
 }
 
+const SUBSCRIPTION_POLICIES: [(&str, SubscriptionPolicy); 4] = [
+    ("Every cycle", SubscriptionPolicy::EveryCycle),
+    (
+        "10 Hz",
+        SubscriptionPolicy::MaximumRate {
+            interval: Duration::from_millis(100),
+        },
+    ),
+    (
+        "2 Hz",
+        SubscriptionPolicy::MaximumRate {
+            interval: Duration::from_millis(500),
+        },
+    ),
+    ("On change", SubscriptionPolicy::OnChange),
+];
+
+fn policy_name(policy: SubscriptionPolicy) -> String {
+    SUBSCRIPTION_POLICIES
+        .iter()
+        .find(|(_, known_policy)| *known_policy == policy)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| format!("{policy:?}"))
+}
+
 pub struct ImagePanel {
     nao: Arc<Nao>,
     image_buffer: ImageBuffer,
     cycler_selector: VisionCyclerSelector,
     overlays: Overlays,
     image_kind: ImageKind,
+    policy: SubscriptionPolicy,
 }
 
 impl Panel for ImagePanel {
@@ -76,11 +105,15 @@ impl Panel for ImagePanel {
             .and_then(|value| value.get("image_kind"))
             .and_then(|value| from_value(value.clone()).ok())
             .unwrap_or(ImageKind::YCbCr422);
+        let policy = value
+            .and_then(|value| value.get("policy"))
+            .and_then(|value| from_value(value.clone()).ok())
+            .unwrap_or_default();
         let output = CyclerOutput {
             cycler,
             output: image_kind.as_output(),
         };
-        let image_buffer = nao.subscribe_image(output);
+        let image_buffer = nao.subscribe_image(output, policy);
         let cycler_selector = VisionCyclerSelector::new(cycler);
         let overlays = Overlays::new(
             nao.clone(),
@@ -93,6 +126,7 @@ impl Panel for ImagePanel {
             cycler_selector,
             overlays,
             image_kind,
+            policy,
         }
     }
 
@@ -105,6 +139,7 @@ impl Panel for ImagePanel {
             "cycler": cycler.to_string(),
             "overlays": overlays,
             "image_kind": image_kind,
+            "policy": to_value(self.policy).unwrap(),
         })
     }
 }
@@ -113,11 +148,7 @@ impl Widget for &mut ImagePanel {
     fn ui(self, ui: &mut Ui) -> Response {
         ui.horizontal(|ui| {
             if self.cycler_selector.ui(ui).changed() {
-                let output = CyclerOutput {
-                    cycler: self.cycler_selector.selected_cycler(),
-                    output: self.image_kind.as_output(),
-                };
-                self.image_buffer = self.nao.subscribe_image(output);
+                self.resubscribe();
                 self.overlays
                     .update_cycler(self.cycler_selector.selected_cycler());
             }
@@ -139,14 +170,26 @@ impl Widget for &mut ImagePanel {
                     }
                 });
             if image_selection_changed {
-                let output = CyclerOutput {
-                    cycler: self.cycler_selector.selected_cycler(),
-                    output: self.image_kind.as_output(),
-                };
-                self.image_buffer = self.nao.subscribe_image(output);
+                self.resubscribe();
                 self.overlays
                     .update_cycler(self.cycler_selector.selected_cycler());
             }
+            let mut policy_changed = false;
+            ComboBox::from_label("Rate")
+                .selected_text(policy_name(self.policy))
+                .show_ui(ui, |ui| {
+                    for (name, policy) in SUBSCRIPTION_POLICIES {
+                        if ui
+                            .selectable_value(&mut self.policy, policy, name)
+                            .changed()
+                        {
+                            policy_changed = true;
+                        }
+                    }
+                });
+            if policy_changed {
+                self.resubscribe();
+            }
             self.overlays
                 .combo_box(ui, self.cycler_selector.selected_cycler());
         });
@@ -159,6 +202,14 @@ impl Widget for &mut ImagePanel {
 }
 
 impl ImagePanel {
+    fn resubscribe(&mut self) {
+        let output = CyclerOutput {
+            cycler: self.cycler_selector.selected_cycler(),
+            output: self.image_kind.as_output(),
+        };
+        self.image_buffer = self.nao.subscribe_image(output, self.policy);
+    }
+
     fn show_image(&self, ui: &mut Ui) -> Result<Response> {
         let image_data = self
             .image_buffer
@@ -4,6 +4,7 @@ use color_eyre::Result;
 use communication::client::CyclerOutput;
 use eframe::epaint::{Color32, Stroke};
 use nalgebra::{point, Isometry2, Point2, UnitComplex};
+use serde::Deserialize;
 use types::{FieldDimensions, MotionCommand};
 
 use crate::{
@@ -13,12 +14,20 @@ use crate::{
 
 const TRANSPARENT_BLUE: Color32 = Color32::from_rgba_premultiplied(0, 0, 202, 150);
 const TRANSPARENT_LIGHT_BLUE: Color32 = Color32::from_rgba_premultiplied(136, 170, 182, 150);
+const TRANSPARENT_RED: Color32 = Color32::from_rgba_premultiplied(202, 0, 0, 150);
+
+#[derive(Deserialize)]
+struct Opponent {
+    position: Point2<f32>,
+    orientation: f32,
+}
 
 pub struct BehaviorSimulator {
     robot_to_field: PlayersValueBuffer,
     motion_command: PlayersValueBuffer,
     head_yaw: PlayersValueBuffer,
     ball: ValueBuffer,
+    opponents: ValueBuffer,
 }
 
 impl Layer for BehaviorSimulator {
@@ -46,18 +55,22 @@ impl Layer for BehaviorSimulator {
         let ball = nao.subscribe_output(
             CyclerOutput::from_str("BehaviorSimulator.main_outputs.ball.position").unwrap(),
         );
+        let opponents = nao.subscribe_output(
+            CyclerOutput::from_str("BehaviorSimulator.main_outputs.opponents").unwrap(),
+        );
         Self {
             robot_to_field,
             motion_command,
             head_yaw: sensor_data,
             ball,
+            opponents,
         }
     }
 
     fn paint(&self, painter: &TwixPainter, _field_dimensions: &FieldDimensions) -> Result<()> {
         for (player_number, value_buffer) in self.robot_to_field.0.iter() {
             let Ok(robot_to_field): Result<Isometry2<f32>> = value_buffer.parse_latest() else {
-                continue
+                continue;
             };
 
             let pose_color = Color32::from_white_alpha(63);
@@ -103,6 +116,22 @@ impl Layer for BehaviorSimulator {
             painter.pose(robot_to_field, 0.15, 0.25, pose_color, pose_stroke);
         }
 
+        if let Ok(opponents) = self.opponents.parse_latest::<Vec<Opponent>>() {
+            let stroke = Stroke {
+                width: 0.02,
+                color: Color32::BLACK,
+            };
+            for opponent in opponents {
+                painter.pose(
+                    Isometry2::new(opponent.position.coords, opponent.orientation),
+                    0.15,
+                    0.25,
+                    TRANSPARENT_RED,
+                    stroke,
+                );
+            }
+        }
+
         if let Ok(ball_position) = self.ball.parse_latest::<Point2<f32>>() {
             painter.ball(ball_position, 0.05);
         }
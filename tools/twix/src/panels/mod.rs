@@ -4,6 +4,7 @@ mod image_segments;
 mod look_at;
 mod manual_camera_calibration;
 mod map;
+mod motion_editor;
 mod parameter;
 mod plot;
 mod text;
@@ -14,6 +15,7 @@ pub use image_segments::ImageSegmentsPanel;
 pub use look_at::LookAtPanel;
 pub use manual_camera_calibration::ManualCalibrationPanel;
 pub use map::MapPanel;
+pub use motion_editor::MotionEditorPanel;
 pub use parameter::ParameterPanel;
 pub use plot::PlotPanel;
 pub use text::TextPanel;
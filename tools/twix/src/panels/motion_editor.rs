@@ -0,0 +1,463 @@
+use std::{fs::write, str::FromStr, sync::Arc, time::Duration};
+
+use color_eyre::{eyre::WrapErr, Result};
+use communication::client::CyclerOutput;
+use eframe::{
+    egui::{
+        Button, Color32, DragValue, Response, ScrollArea, SelectableLabel, TextEdit, Ui, Widget,
+    },
+    epaint::Vec2,
+};
+use motionfile::{
+    nao_joint_limits, ContinuousConditionType, DiscreteConditionType, KeyFrame, MotionFile,
+    MotionFileFrame,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use types::{Joints, MotionCommand, MotionSelection, MotionType};
+
+use crate::{nao::Nao, panel::Panel, value_buffer::ValueBuffer};
+
+const INJECTED_MOTION_COMMAND: &str = "behavior.injected_motion_command";
+const ANIMATION_MOTION_FILE: &str = "animation.motion_file";
+const PIXELS_PER_SECOND: f32 = 200.0;
+const MINIMUM_KEYFRAME_WIDTH: f32 = 20.0;
+const DEFAULT_KEYFRAME_DURATION: Duration = Duration::from_millis(500);
+const JOINT_LIMIT_TOLERANCE: f32 = 0.05;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Selection {
+    InitialPositions,
+    KeyFrame { frame: usize, keyframe: usize },
+}
+
+#[derive(Default)]
+struct ConditionTexts {
+    frame: Option<usize>,
+    entry_condition: String,
+    interrupt_conditions: String,
+    exit_condition: String,
+}
+
+pub struct MotionEditorPanel {
+    nao: Arc<Nao>,
+    path: String,
+    motion_file: MotionFile<Joints<f32>>,
+    selection: Selection,
+    condition_texts: ConditionTexts,
+    message: Option<Result<String, String>>,
+    positions_buffer: ValueBuffer,
+    motion_selection_buffer: ValueBuffer,
+}
+
+impl Panel for MotionEditorPanel {
+    const NAME: &'static str = "Motion Editor";
+
+    fn new(nao: Arc<Nao>, value: Option<&Value>) -> Self {
+        let path = match value.and_then(|value| value.get("path")) {
+            Some(Value::String(path)) => path.clone(),
+            _ => String::new(),
+        };
+        let positions_buffer = nao.subscribe_output(
+            CyclerOutput::from_str("Control.main_outputs.sensor_data.positions")
+                .expect("Failed to subscribe to main_outputs.sensor_data.positions"),
+        );
+        let motion_selection_buffer = nao.subscribe_output(
+            CyclerOutput::from_str("Control.main_outputs.motion_selection")
+                .expect("Failed to subscribe to main_outputs.motion_selection"),
+        );
+
+        Self {
+            nao,
+            path,
+            motion_file: Default::default(),
+            selection: Selection::InitialPositions,
+            condition_texts: Default::default(),
+            message: None,
+            positions_buffer,
+            motion_selection_buffer,
+        }
+    }
+
+    fn save(&self) -> Value {
+        json!({
+            "path": self.path.clone()
+        })
+    }
+}
+
+impl Widget for &mut MotionEditorPanel {
+    fn ui(self, ui: &mut Ui) -> Response {
+        ui.vertical(|ui| {
+            self.file_ui(ui);
+            ui.separator();
+            self.robot_ui(ui);
+            ui.separator();
+            self.timeline_ui(ui);
+            ui.separator();
+            self.selection_ui(ui);
+            ui.separator();
+            self.validation_ui(ui);
+        })
+        .response
+    }
+}
+
+impl MotionEditorPanel {
+    fn file_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.path).hint_text("etc/motions/*.json"));
+            if ui.button("Load").clicked() {
+                self.message = Some(
+                    MotionFile::from_any_path(&self.path, DEFAULT_KEYFRAME_DURATION)
+                        .map(|motion_file| {
+                            self.motion_file = motion_file;
+                            self.selection = Selection::InitialPositions;
+                            self.condition_texts.frame = None;
+                            format!("Loaded {}", self.path)
+                        })
+                        .map_err(|error| format!("{error:#}")),
+                );
+            }
+            if ui.button("Save").clicked() {
+                self.message = Some(
+                    save_motion_file(&self.motion_file, &self.path)
+                        .map(|_| format!("Saved {}", self.path))
+                        .map_err(|error| format!("{error:#}")),
+                );
+            }
+            if ui.button("New").clicked() {
+                self.motion_file = Default::default();
+                self.selection = Selection::InitialPositions;
+                self.condition_texts.frame = None;
+            }
+        });
+        match &self.message {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(error)) => {
+                ui.colored_label(Color32::RED, error);
+            }
+            None => {}
+        }
+    }
+
+    fn robot_ui(&mut self, ui: &mut Ui) {
+        let motion_selection: Option<MotionSelection> =
+            self.motion_selection_buffer.parse_latest().ok();
+        let current_motion = motion_selection.map(|selection| selection.current_motion);
+        ui.label(match current_motion {
+            Some(motion) => format!("Current motion: {motion:?}"),
+            None => "Current motion: unknown".to_string(),
+        });
+
+        let is_safe_to_play = matches!(
+            current_motion,
+            Some(
+                MotionType::Animation
+                    | MotionType::AnimationStiff
+                    | MotionType::Penalized
+                    | MotionType::SitDown
+                    | MotionType::Stand
+                    | MotionType::Unstiff
+            )
+        );
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(is_safe_to_play, Button::new("Unstiffen"))
+                .on_hover_text("Let the joints be moved by hand to capture keyframes")
+                .clicked()
+            {
+                self.inject_motion_command(Some(MotionCommand::Animation { stiff: false }));
+            }
+            if ui
+                .add_enabled(is_safe_to_play, Button::new("Play"))
+                .on_hover_text("Upload the motion and play it back on the robot")
+                .clicked()
+            {
+                self.nao.update_parameter_value(
+                    ANIMATION_MOTION_FILE,
+                    serde_json::to_value(&self.motion_file).unwrap(),
+                );
+                self.inject_motion_command(Some(MotionCommand::Animation { stiff: true }));
+            }
+            if ui
+                .button("Release")
+                .on_hover_text("Return control to the behavior")
+                .clicked()
+            {
+                self.inject_motion_command(None);
+            }
+        });
+        if !is_safe_to_play {
+            ui.colored_label(
+                Color32::RED,
+                "Cannot safely play the motion, please put the NAO into a standing or unstiff position!",
+            );
+        }
+    }
+
+    fn timeline_ui(&mut self, ui: &mut Ui) {
+        ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .add(SelectableLabel::new(
+                        self.selection == Selection::InitialPositions,
+                        "Initial",
+                    ))
+                    .clicked()
+                {
+                    self.selection = Selection::InitialPositions;
+                }
+                for (frame_index, frame) in self.motion_file.motion.iter().enumerate() {
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        ui.label(
+                            frame
+                                .name
+                                .clone()
+                                .unwrap_or_else(|| format!("Frame {frame_index}")),
+                        );
+                        ui.horizontal(|ui| {
+                            for (keyframe_index, keyframe) in frame.keyframes.iter().enumerate() {
+                                let selection = Selection::KeyFrame {
+                                    frame: frame_index,
+                                    keyframe: keyframe_index,
+                                };
+                                let width = (keyframe.duration.as_secs_f32() * PIXELS_PER_SECOND)
+                                    .max(MINIMUM_KEYFRAME_WIDTH);
+                                if ui
+                                    .add_sized(
+                                        Vec2::new(width, 20.0),
+                                        SelectableLabel::new(
+                                            self.selection == selection,
+                                            format!("{:.2}s", keyframe.duration.as_secs_f32()),
+                                        ),
+                                    )
+                                    .clicked()
+                                {
+                                    self.selection = selection;
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Add Frame").clicked() {
+                self.motion_file.motion.push(MotionFileFrame {
+                    name: None,
+                    entry_condition: None,
+                    interrupt_conditions: vec![],
+                    keyframes: vec![],
+                    exit_condition: None,
+                });
+            }
+            if ui.button("Capture Keyframe").clicked() {
+                self.capture_keyframe();
+            }
+            let total_duration: Duration = self
+                .motion_file
+                .motion
+                .iter()
+                .flat_map(|frame| &frame.keyframes)
+                .map(|keyframe| keyframe.duration)
+                .sum();
+            ui.label(format!("Total: {:.2}s", total_duration.as_secs_f32()));
+        });
+    }
+
+    fn selection_ui(&mut self, ui: &mut Ui) {
+        let (frame_index, keyframe_index) = match self.selection {
+            Selection::InitialPositions => {
+                ui.horizontal(|ui| {
+                    ui.label("Initial positions");
+                    if ui.button("Replace with Robot Positions").clicked() {
+                        if let Some(positions) = self.current_positions() {
+                            self.motion_file.initial_positions = positions;
+                        }
+                    }
+                });
+                return;
+            }
+            Selection::KeyFrame { frame, keyframe } => (frame, keyframe),
+        };
+        let Some(keyframe) = self
+            .motion_file
+            .motion
+            .get_mut(frame_index)
+            .and_then(|frame| frame.keyframes.get_mut(keyframe_index))
+        else {
+            self.selection = Selection::InitialPositions;
+            return;
+        };
+
+        let mut replace_positions = false;
+        let mut delete = false;
+        ui.horizontal(|ui| {
+            ui.label(format!("Keyframe {keyframe_index} of frame {frame_index}"));
+            let mut duration = keyframe.duration.as_secs_f32();
+            if ui
+                .add(
+                    DragValue::new(&mut duration)
+                        .speed(0.01)
+                        .clamp_range(0.0..=10.0)
+                        .suffix("s"),
+                )
+                .changed()
+            {
+                keyframe.duration = Duration::from_secs_f32(duration);
+            }
+            replace_positions = ui.button("Replace with Robot Positions").clicked();
+            delete = ui.button("Delete").clicked();
+        });
+        if replace_positions {
+            if let Some(positions) = self.current_positions() {
+                self.motion_file.motion[frame_index].keyframes[keyframe_index].positions =
+                    positions;
+            }
+        }
+        if delete {
+            self.motion_file.motion[frame_index]
+                .keyframes
+                .remove(keyframe_index);
+            self.selection = Selection::InitialPositions;
+            return;
+        }
+        self.conditions_ui(ui, frame_index);
+    }
+
+    fn conditions_ui(&mut self, ui: &mut Ui, frame_index: usize) {
+        let frame = &mut self.motion_file.motion[frame_index];
+        if self.condition_texts.frame != Some(frame_index) {
+            self.condition_texts = ConditionTexts {
+                frame: Some(frame_index),
+                entry_condition: serde_json::to_string(&frame.entry_condition).unwrap(),
+                interrupt_conditions: serde_json::to_string(&frame.interrupt_conditions).unwrap(),
+                exit_condition: serde_json::to_string(&frame.exit_condition).unwrap(),
+            };
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            let mut name = frame.name.clone().unwrap_or_default();
+            if ui.text_edit_singleline(&mut name).changed() {
+                frame.name = (!name.is_empty()).then_some(name);
+            }
+        });
+        condition_edit::<Option<DiscreteConditionType>>(
+            ui,
+            "Entry condition",
+            &mut self.condition_texts.entry_condition,
+            &mut frame.entry_condition,
+        );
+        condition_edit::<Vec<ContinuousConditionType>>(
+            ui,
+            "Interrupt conditions",
+            &mut self.condition_texts.interrupt_conditions,
+            &mut frame.interrupt_conditions,
+        );
+        condition_edit::<Option<DiscreteConditionType>>(
+            ui,
+            "Exit condition",
+            &mut self.condition_texts.exit_condition,
+            &mut frame.exit_condition,
+        );
+    }
+
+    fn validation_ui(&mut self, ui: &mut Ui) {
+        let errors = self
+            .motion_file
+            .validate(&nao_joint_limits(), JOINT_LIMIT_TOLERANCE);
+        if errors.is_empty() {
+            ui.label("Motion is valid");
+            return;
+        }
+        ScrollArea::vertical().show(ui, |ui| {
+            for error in errors {
+                ui.colored_label(Color32::RED, error.to_string());
+            }
+        });
+    }
+
+    fn capture_keyframe(&mut self) {
+        let Some(positions) = self.current_positions() else {
+            return;
+        };
+        let keyframe = KeyFrame {
+            duration: DEFAULT_KEYFRAME_DURATION,
+            positions,
+        };
+        let (frame_index, keyframe_index) = match self.selection {
+            Selection::KeyFrame { frame, keyframe } => (frame, keyframe + 1),
+            Selection::InitialPositions => {
+                if self.motion_file.motion.is_empty() {
+                    self.motion_file.motion.push(MotionFileFrame {
+                        name: None,
+                        entry_condition: None,
+                        interrupt_conditions: vec![],
+                        keyframes: vec![],
+                        exit_condition: None,
+                    });
+                }
+                (0, 0)
+            }
+        };
+        self.motion_file.motion[frame_index]
+            .keyframes
+            .insert(keyframe_index, keyframe);
+        self.selection = Selection::KeyFrame {
+            frame: frame_index,
+            keyframe: keyframe_index,
+        };
+    }
+
+    fn current_positions(&mut self) -> Option<Joints<f32>> {
+        match self.positions_buffer.parse_latest() {
+            Ok(positions) => Some(positions),
+            Err(error) => {
+                self.message = Some(Err(format!("Robot positions are not available: {error:#}")));
+                None
+            }
+        }
+    }
+
+    fn inject_motion_command(&self, motion_command: Option<MotionCommand>) {
+        self.nao.update_parameter_value(
+            INJECTED_MOTION_COMMAND,
+            serde_json::to_value(motion_command).unwrap(),
+        );
+    }
+}
+
+fn condition_edit<T>(ui: &mut Ui, label: &str, text: &mut String, value: &mut T)
+where
+    for<'de> T: Deserialize<'de>,
+{
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let response = ui.add(
+            TextEdit::singleline(text)
+                .code_editor()
+                .desired_width(f32::INFINITY),
+        );
+        match serde_json::from_str(text) {
+            Ok(parsed_value) => {
+                if response.changed() {
+                    *value = parsed_value;
+                }
+            }
+            Err(error) => {
+                ui.colored_label(Color32::RED, error.to_string());
+            }
+        }
+    });
+}
+
+fn save_motion_file(motion_file: &MotionFile<Joints<f32>>, path: &str) -> Result<()> {
+    let contents =
+        serde_json::to_string_pretty(motion_file).wrap_err("failed to serialize motion file")?;
+    write(path, contents + "\n").wrap_err_with(|| format!("failed to write {path}"))
+}
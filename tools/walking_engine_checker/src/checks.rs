@@ -0,0 +1,389 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+    time::Duration,
+};
+
+use clap::Args;
+use kinematics::{
+    left_ankle_to_left_tibia, left_foot_to_left_ankle, left_hip_to_left_pelvis,
+    left_pelvis_to_robot, left_thigh_to_left_hip, left_tibia_to_left_thigh, leg_angles,
+    right_ankle_to_right_tibia, right_foot_to_right_ankle, right_hip_to_right_pelvis,
+    right_pelvis_to_robot, right_thigh_to_right_hip, right_tibia_to_right_thigh,
+};
+use motionfile::{named_joints, nao_joint_limits};
+use nalgebra::{point, vector, Isometry3, Point2, Vector2};
+use thiserror::Error;
+use types::{HeadJoints, Joints, LegJoints, Side};
+
+use crate::simulation::{Frame, CYCLE_DURATION};
+
+/// Only the first violations of each check are listed in the report
+const MAXIMUM_LISTED_VIOLATIONS: usize = 10;
+
+#[derive(Args, Clone, Debug)]
+pub struct Thresholds {
+    /// Joint angles may exceed the limits of the NAO by this many radians
+    #[arg(long, default_value_t = 0.0)]
+    pub joint_limit_tolerance: f32,
+    /// Maximum distance in meters between a requested ankle position and the one reached by the
+    /// inverse kinematics
+    #[arg(long, default_value_t = 0.001)]
+    pub maximum_inverse_kinematics_error: f32,
+    /// Maximum angle in radians between a requested foot orientation and the one reached by the
+    /// inverse kinematics
+    #[arg(long, default_value_t = 0.01)]
+    pub maximum_inverse_kinematics_angle_error: f32,
+    /// Minimum distance in meters between the soles projected to the ground
+    #[arg(long, default_value_t = 0.005)]
+    pub minimum_foot_distance: f32,
+    /// Minimum height in meters the swing sole has to reach above the support sole in each step
+    #[arg(long, default_value_t = 0.005)]
+    pub minimum_swing_clearance: f32,
+    /// Maximum velocity of each joint in radians per second
+    #[arg(long, default_value_t = 6.0)]
+    pub maximum_joint_velocity: f32,
+}
+
+#[derive(Debug, Error)]
+pub enum Violation {
+    #[error("foot targets are not reachable")]
+    FootTargetsNotReachable,
+    #[error("inverse kinematics misses the {side:?} foot target by {error} m")]
+    InverseKinematicsError { side: Side, error: f32 },
+    #[error("inverse kinematics misses the {side:?} foot orientation by {error} rad")]
+    InverseKinematicsAngleError { side: Side, error: f32 },
+    #[error("{joint} is {angle}, exceeding the limits {limits:?}")]
+    JointLimitExceeded {
+        joint: &'static str,
+        angle: f32,
+        limits: RangeInclusive<f32>,
+    },
+    #[error("soles are only {distance} m apart")]
+    FeetTooClose { distance: f32 },
+    #[error("swing sole is lifted only {clearance} m")]
+    InsufficientSwingClearance { clearance: f32 },
+    #[error("{joint} moves with {velocity} rad/s")]
+    JointVelocityExceeded { joint: &'static str, velocity: f32 },
+}
+
+pub struct Failure {
+    pub time: Duration,
+    pub phase: usize,
+    pub violation: Violation,
+}
+
+pub struct Check {
+    pub name: &'static str,
+    pub failures: Vec<Failure>,
+}
+
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn is_success(&self) -> bool {
+        self.checks.iter().all(|check| check.failures.is_empty())
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        for check in &self.checks {
+            if check.failures.is_empty() {
+                writeln!(formatter, "{}: passed", check.name)?;
+                continue;
+            }
+            writeln!(
+                formatter,
+                "{}: failed with {} violations",
+                check.name,
+                check.failures.len()
+            )?;
+            for failure in check.failures.iter().take(MAXIMUM_LISTED_VIOLATIONS) {
+                writeln!(
+                    formatter,
+                    "  {:.3}s in phase {}: {}",
+                    failure.time.as_secs_f32(),
+                    failure.phase,
+                    failure.violation
+                )?;
+            }
+            if check.failures.len() > MAXIMUM_LISTED_VIOLATIONS {
+                writeln!(
+                    formatter,
+                    "  ... and {} more",
+                    check.failures.len() - MAXIMUM_LISTED_VIOLATIONS
+                )?;
+            }
+        }
+        write!(
+            formatter,
+            "{}",
+            if self.is_success() {
+                "PASSED"
+            } else {
+                "FAILED"
+            }
+        )
+    }
+}
+
+pub fn check(frames: &[Frame], thresholds: &Thresholds) -> Report {
+    Report {
+        checks: vec![
+            Check {
+                name: "inverse kinematics",
+                failures: check_inverse_kinematics(frames, thresholds),
+            },
+            Check {
+                name: "joint limits",
+                failures: check_joint_limits(frames, thresholds),
+            },
+            Check {
+                name: "foot self-collision",
+                failures: check_foot_collision(frames, thresholds),
+            },
+            Check {
+                name: "swing clearance",
+                failures: check_swing_clearance(frames, thresholds),
+            },
+            Check {
+                name: "joint velocity",
+                failures: check_joint_velocity(frames, thresholds),
+            },
+        ],
+    }
+}
+
+fn check_inverse_kinematics(frames: &[Frame], thresholds: &Thresholds) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    for frame in frames {
+        let targets = &frame.foot_targets;
+        let (is_reachable, left_leg, right_leg) =
+            leg_angles(targets.left_foot_to_robot, targets.right_foot_to_robot);
+        if !is_reachable {
+            failures.push(Failure {
+                time: frame.time,
+                phase: frame.phase,
+                violation: Violation::FootTargetsNotReachable,
+            });
+        }
+        for (side, target, reached) in [
+            (
+                Side::Left,
+                targets.left_foot_to_robot,
+                left_foot_to_robot(&left_leg),
+            ),
+            (
+                Side::Right,
+                targets.right_foot_to_robot,
+                right_foot_to_robot(&right_leg),
+            ),
+        ] {
+            let error = (target.translation.vector - reached.translation.vector).norm();
+            if error > thresholds.maximum_inverse_kinematics_error {
+                failures.push(Failure {
+                    time: frame.time,
+                    phase: frame.phase,
+                    violation: Violation::InverseKinematicsError { side, error },
+                });
+            }
+            let angle_error = target.rotation.angle_to(&reached.rotation);
+            if angle_error > thresholds.maximum_inverse_kinematics_angle_error {
+                failures.push(Failure {
+                    time: frame.time,
+                    phase: frame.phase,
+                    violation: Violation::InverseKinematicsAngleError {
+                        side,
+                        error: angle_error,
+                    },
+                });
+            }
+        }
+    }
+    failures
+}
+
+fn check_joint_limits(frames: &[Frame], thresholds: &Thresholds) -> Vec<Failure> {
+    let joint_limits = nao_joint_limits();
+    let mut failures = Vec::new();
+    for frame in frames {
+        let positions = Joints::from_head_and_body(HeadJoints::default(), frame.positions);
+        for (joint, angle, limits) in named_joints(&positions, &joint_limits) {
+            let extended_limits = limits.start() - thresholds.joint_limit_tolerance
+                ..=limits.end() + thresholds.joint_limit_tolerance;
+            if !extended_limits.contains(&angle) {
+                failures.push(Failure {
+                    time: frame.time,
+                    phase: frame.phase,
+                    violation: Violation::JointLimitExceeded {
+                        joint,
+                        angle,
+                        limits: limits.clone(),
+                    },
+                });
+            }
+        }
+    }
+    failures
+}
+
+fn check_foot_collision(frames: &[Frame], thresholds: &Thresholds) -> Vec<Failure> {
+    frames
+        .iter()
+        .filter_map(|frame| {
+            let left_sole =
+                sole_outline_on_ground(Side::Left, frame.robot_kinematics.left_sole_to_robot);
+            let right_sole =
+                sole_outline_on_ground(Side::Right, frame.robot_kinematics.right_sole_to_robot);
+            let distance = separation(&left_sole, &right_sole);
+            (distance < thresholds.minimum_foot_distance).then_some(Failure {
+                time: frame.time,
+                phase: frame.phase,
+                violation: Violation::FeetTooClose { distance },
+            })
+        })
+        .collect()
+}
+
+/// Steps end in the cycle the step time is reset, the last step is only checked if it is finished
+fn check_swing_clearance(frames: &[Frame], thresholds: &Thresholds) -> Vec<Failure> {
+    frames
+        .split_inclusive(|frame| frame.step_time.is_zero())
+        .filter(|step| {
+            step.last().map_or(false, |last_frame| {
+                last_frame.step_time.is_zero() && !last_frame.planned_step_duration.is_zero()
+            })
+        })
+        .filter_map(|step| {
+            let clearance = step
+                .iter()
+                .map(|frame| {
+                    let left_height = frame.robot_kinematics.left_sole_to_robot.translation.z;
+                    let right_height = frame.robot_kinematics.right_sole_to_robot.translation.z;
+                    (left_height - right_height).abs()
+                })
+                .fold(0.0, f32::max);
+            (clearance < thresholds.minimum_swing_clearance).then_some(Failure {
+                time: step[0].time,
+                phase: step[0].phase,
+                violation: Violation::InsufficientSwingClearance { clearance },
+            })
+        })
+        .collect()
+}
+
+fn check_joint_velocity(frames: &[Frame], thresholds: &Thresholds) -> Vec<Failure> {
+    let joint_limits = nao_joint_limits();
+    let mut failures = Vec::new();
+    for window in frames.windows(2) {
+        let last_positions = Joints::from_head_and_body(HeadJoints::default(), window[0].positions);
+        let positions = Joints::from_head_and_body(HeadJoints::default(), window[1].positions);
+        for ((joint, last_angle, _), (_, angle, _)) in named_joints(&last_positions, &joint_limits)
+            .into_iter()
+            .zip(named_joints(&positions, &joint_limits))
+        {
+            let velocity = (angle - last_angle).abs() / CYCLE_DURATION.as_secs_f32();
+            if velocity > thresholds.maximum_joint_velocity {
+                failures.push(Failure {
+                    time: window[1].time,
+                    phase: window[1].phase,
+                    violation: Violation::JointVelocityExceeded { joint, velocity },
+                });
+            }
+        }
+    }
+    failures
+}
+
+fn left_foot_to_robot(angles: &LegJoints<f32>) -> Isometry3<f32> {
+    left_pelvis_to_robot(angles)
+        * left_hip_to_left_pelvis(angles)
+        * left_thigh_to_left_hip(angles)
+        * left_tibia_to_left_thigh(angles)
+        * left_ankle_to_left_tibia(angles)
+        * left_foot_to_left_ankle(angles)
+}
+
+fn right_foot_to_robot(angles: &LegJoints<f32>) -> Isometry3<f32> {
+    right_pelvis_to_robot(angles)
+        * right_hip_to_right_pelvis(angles)
+        * right_thigh_to_right_hip(angles)
+        * right_tibia_to_right_thigh(angles)
+        * right_ankle_to_right_tibia(angles)
+        * right_foot_to_right_ankle(angles)
+}
+
+/// Corners of the sole projected to the ground, the inner edge is closer to the ankle than the
+/// outer edge
+fn sole_outline_on_ground(side: Side, sole_to_robot: Isometry3<f32>) -> [Point2<f32>; 4] {
+    let outer = match side {
+        Side::Left => 1.0,
+        Side::Right => -1.0,
+    };
+    [
+        point![0.1, 0.05 * outer],
+        point![-0.055, 0.05 * outer],
+        point![-0.055, -0.038 * outer],
+        point![0.1, -0.038 * outer],
+    ]
+    .map(|corner| (sole_to_robot * point![corner.x, corner.y, 0.0]).xy())
+}
+
+/// Lower bound of the distance between two convex polygons, negative if they overlap
+fn separation(polygon: &[Point2<f32>], other_polygon: &[Point2<f32>]) -> f32 {
+    edge_normals(polygon)
+        .chain(edge_normals(other_polygon))
+        .map(|normal| {
+            let (minimum, maximum) = project(polygon, normal);
+            let (other_minimum, other_maximum) = project(other_polygon, normal);
+            (other_minimum - maximum).max(minimum - other_maximum)
+        })
+        .fold(f32::MIN, f32::max)
+}
+
+fn edge_normals(polygon: &[Point2<f32>]) -> impl Iterator<Item = Vector2<f32>> + '_ {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(start, end)| {
+            let edge = end - start;
+            vector![-edge.y, edge.x].normalize()
+        })
+}
+
+fn project(polygon: &[Point2<f32>], axis: Vector2<f32>) -> (f32, f32) {
+    polygon
+        .iter()
+        .map(|corner| corner.coords.dot(&axis))
+        .fold((f32::MAX, f32::MIN), |(minimum, maximum), projection| {
+            (minimum.min(projection), maximum.max(projection))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32) -> [Point2<f32>; 4] {
+        [
+            point![x, y],
+            point![x + 1.0, y],
+            point![x + 1.0, y + 1.0],
+            point![x, y + 1.0],
+        ]
+    }
+
+    #[test]
+    fn separated_squares_have_positive_separation() {
+        assert_eq!(separation(&square(0.0, 0.0), &square(1.5, 0.0)), 0.5);
+        assert_eq!(separation(&square(0.0, 0.0), &square(0.0, -3.0)), 2.0);
+    }
+
+    #[test]
+    fn overlapping_squares_have_negative_separation() {
+        assert_eq!(separation(&square(0.0, 0.0), &square(0.75, 0.5)), -0.25);
+    }
+}
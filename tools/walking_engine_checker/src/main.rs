@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use checks::{check, Thresholds};
+use clap::Parser;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    install, Result,
+};
+use parameters::directory::deserialize;
+use scenario::Scenario;
+use simulation::simulate;
+use structs::Configuration;
+
+mod checks;
+mod scenario;
+mod simulation;
+
+/// Walks the walking engine open-loop through a scenario and checks the resulting motion for
+/// kinematic feasibility
+#[derive(Parser)]
+struct Arguments {
+    /// Directory containing the parameters to check
+    #[arg(long, default_value = "etc/configuration")]
+    parameters_directory: PathBuf,
+    /// Body ID of the robot whose parameters are merged into the defaults
+    #[arg(long, default_value = "default")]
+    body_id: String,
+    /// Head ID of the robot whose parameters are merged into the defaults
+    #[arg(long, default_value = "default")]
+    head_id: String,
+    #[command(flatten)]
+    thresholds: Thresholds,
+    #[arg(default_value = "tests/walking_engine/default.json")]
+    scenario_file: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    install()?;
+    let arguments = Arguments::parse();
+
+    let configuration: Configuration = deserialize(
+        &arguments.parameters_directory,
+        &arguments.body_id,
+        &arguments.head_id,
+    )
+    .await
+    .wrap_err("failed to load parameters")?;
+    let scenario = Scenario::load(&arguments.scenario_file)?;
+
+    let frames = simulate(&configuration, &scenario)?;
+    let report = check(&frames, &arguments.thresholds);
+    println!("{report}");
+    if !report.is_success() {
+        bail!("walking engine parameters are not kinematically feasible");
+    }
+    Ok(())
+}
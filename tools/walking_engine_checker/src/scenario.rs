@@ -0,0 +1,27 @@
+use std::{fs::read_to_string, path::Path, time::Duration};
+
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Deserialize;
+use types::WalkCommand;
+
+/// Commands the walking engine executes one after another
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scenario {
+    pub phases: Vec<Phase>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Phase {
+    pub duration: Duration,
+    pub walk_command: WalkCommand,
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = read_to_string(path)
+            .wrap_err_with(|| format!("failed to read scenario {}", path.display()))?;
+        serde_json::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse scenario {}", path.display()))
+    }
+}
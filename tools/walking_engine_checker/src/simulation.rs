@@ -0,0 +1,188 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use color_eyre::{eyre::WrapErr, Result};
+use control::{
+    center_of_mass_provider::{self, CenterOfMassProvider},
+    kinematics_provider::{self, KinematicsProvider},
+    motion::walking_engine::{self, WalkingEngine},
+};
+use framework::AdditionalOutput;
+use structs::Configuration;
+use types::{
+    BodyJoints, CycleTime, Foot, FootTargets, Joints, MotionCommand, MotionSafeExits,
+    RobotKinematics, SensorData, SolePressure, Step, WalkCommand,
+};
+
+use crate::scenario::Scenario;
+
+pub const CYCLE_DURATION: Duration = Duration::from_millis(12);
+
+/// Weight in kilograms measured by the force sensitive resistors of the foot carrying the robot
+const ROBOT_WEIGHT: f32 = 5.3;
+/// Soles less than this far above the lowest sole touch the ground
+const GROUND_CONTACT_TOLERANCE: f32 = 0.0001;
+
+/// State of the walking engine after one cycle
+pub struct Frame {
+    pub time: Duration,
+    pub phase: usize,
+    pub walk_command: WalkCommand,
+    pub foot_targets: FootTargets,
+    pub positions: BodyJoints<f32>,
+    /// Forward kinematics of the commanded positions
+    pub robot_kinematics: RobotKinematics,
+    pub step_time: Duration,
+    pub planned_step_duration: Duration,
+}
+
+/// Drives the walking engine open-loop, the robot is assumed to reach the commanded positions
+/// within one cycle and the swing foot touches the ground when the planned step is over
+pub fn simulate(configuration: &Configuration, scenario: &Scenario) -> Result<Vec<Frame>> {
+    let mut kinematics_provider = KinematicsProvider::new(kinematics_provider::CreationContext {})
+        .wrap_err("failed to create node `KinematicsProvider`")?;
+    let mut center_of_mass_provider =
+        CenterOfMassProvider::new(center_of_mass_provider::CreationContext {})
+            .wrap_err("failed to create node `CenterOfMassProvider`")?;
+    let mut motion_safe_exits = MotionSafeExits::default();
+    let mut walk_return_offset = Step::default();
+    let mut walking_engine = WalkingEngine::new(walking_engine::CreationContext {
+        config: &configuration.walking_engine,
+        kick_steps: &configuration.kick_steps,
+        ready_pose: &configuration.ready_pose,
+        motion_safe_exits: &mut motion_safe_exits,
+        walk_return_offset: &mut walk_return_offset,
+    })
+    .wrap_err("failed to create node `WalkingEngine`")?;
+
+    let mut sensor_data = SensorData {
+        positions: configuration.ready_pose,
+        ..Default::default()
+    };
+    let mut robot_kinematics = kinematics_provider
+        .cycle(kinematics_provider::CycleContext {
+            sensor_data: &sensor_data,
+        })
+        .wrap_err("failed to execute cycle of node `KinematicsProvider`")?
+        .robot_kinematics
+        .value;
+    let mut step_time = Duration::ZERO;
+    let mut planned_step_duration = Duration::ZERO;
+    let mut time = Duration::ZERO;
+    let mut frames = Vec::new();
+
+    for (phase_index, phase) in scenario.phases.iter().enumerate() {
+        let phase_end = time + phase.duration;
+        while time < phase_end {
+            time += CYCLE_DURATION;
+            let center_of_mass = center_of_mass_provider
+                .cycle(center_of_mass_provider::CycleContext {
+                    robot_kinematics: &robot_kinematics,
+                })
+                .wrap_err("failed to execute cycle of node `CenterOfMassProvider`")?
+                .center_of_mass
+                .value;
+
+            let is_step_over = !planned_step_duration.is_zero()
+                && step_time + CYCLE_DURATION >= planned_step_duration;
+            let sole_pressure = sole_pressure(&robot_kinematics, is_step_over);
+            sensor_data.force_sensitive_resistors.left = foot_with_weight(sole_pressure.left);
+            sensor_data.force_sensitive_resistors.right = foot_with_weight(sole_pressure.right);
+
+            let mut foot_targets = None;
+            let mut step_time_output = None;
+            let mut planned_step_duration_output = None;
+            let walk_joints_command = walking_engine
+                .cycle(walking_engine::CycleContext {
+                    step_adjustment: AdditionalOutput::new(false, &mut None),
+                    capture_point_adjustment: AdditionalOutput::new(false, &mut None),
+                    foot_targets: AdditionalOutput::new(true, &mut foot_targets),
+                    planned_step_duration: AdditionalOutput::new(
+                        true,
+                        &mut planned_step_duration_output,
+                    ),
+                    t: AdditionalOutput::new(true, &mut step_time_output),
+                    t_on_last_phase_end: AdditionalOutput::new(false, &mut None),
+                    config: &configuration.walking_engine,
+                    kick_steps: &configuration.kick_steps,
+                    ready_pose: &configuration.ready_pose,
+                    motion_safe_exits: &mut motion_safe_exits,
+                    walk_return_offset: &mut walk_return_offset,
+                    center_of_mass: &center_of_mass,
+                    motion_command: &MotionCommand::Unstiff,
+                    robot_kinematics: &robot_kinematics,
+                    sensor_data: &sensor_data,
+                    cycle_time: &CycleTime {
+                        start_time: UNIX_EPOCH + time,
+                        last_cycle_duration: CYCLE_DURATION,
+                    },
+                    sole_pressure: &sole_pressure,
+                    has_ground_contact: &true,
+                    walk_command: &phase.walk_command,
+                })
+                .wrap_err("failed to execute cycle of node `WalkingEngine`")?
+                .walk_joints_command
+                .value;
+            step_time = step_time_output.unwrap_or_default();
+            planned_step_duration = planned_step_duration_output.unwrap_or_default();
+
+            sensor_data.positions = Joints::from_head_and_body(
+                sensor_data.positions.head,
+                walk_joints_command.positions,
+            );
+            robot_kinematics = kinematics_provider
+                .cycle(kinematics_provider::CycleContext {
+                    sensor_data: &sensor_data,
+                })
+                .wrap_err("failed to execute cycle of node `KinematicsProvider`")?
+                .robot_kinematics
+                .value;
+
+            frames.push(Frame {
+                time,
+                phase: phase_index,
+                walk_command: phase.walk_command,
+                foot_targets: foot_targets.unwrap_or_default(),
+                positions: walk_joints_command.positions,
+                robot_kinematics: robot_kinematics.clone(),
+                step_time,
+                planned_step_duration,
+            });
+        }
+    }
+
+    Ok(frames)
+}
+
+/// The lowest sole carries the robot, when the step is over the swing sole touches the ground as
+/// well
+fn sole_pressure(robot_kinematics: &RobotKinematics, is_step_over: bool) -> SolePressure {
+    let left_height = robot_kinematics.left_sole_to_robot.translation.z;
+    let right_height = robot_kinematics.right_sole_to_robot.translation.z;
+    let lowest_height = left_height.min(right_height);
+    let is_left_on_ground = is_step_over || left_height < lowest_height + GROUND_CONTACT_TOLERANCE;
+    let is_right_on_ground =
+        is_step_over || right_height < lowest_height + GROUND_CONTACT_TOLERANCE;
+    let number_of_feet_on_ground = (is_left_on_ground as u8 + is_right_on_ground as u8) as f32;
+    let weight_per_foot = ROBOT_WEIGHT / number_of_feet_on_ground;
+    SolePressure {
+        left: if is_left_on_ground {
+            weight_per_foot
+        } else {
+            0.0
+        },
+        right: if is_right_on_ground {
+            weight_per_foot
+        } else {
+            0.0
+        },
+    }
+}
+
+fn foot_with_weight(weight: f32) -> Foot {
+    Foot {
+        front_left: weight / 4.0,
+        front_right: weight / 4.0,
+        rear_left: weight / 4.0,
+        rear_right: weight / 4.0,
+    }
+}